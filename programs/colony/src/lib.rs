@@ -1,7 +1,16 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::associated_token::{AssociatedToken, Create, create_idempotent};
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, BurnChecked, transfer_checked, burn_checked};
+use anchor_spl::token_interface::{Token2022, MintTo, SetAuthority, CloseAccount, TokenMetadataInitialize, TokenMetadataUpdateField, mint_to, set_authority, close_account, token_metadata_initialize, token_metadata_update_field};
+use anchor_spl::token_interface::{InitializeMint2, MetadataPointerInitialize, MintCloseAuthorityInitialize, initialize_mint2, metadata_pointer_initialize, mint_close_authority_initialize};
+use anchor_spl::token_interface::spl_token_metadata_interface::state::Field;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::mint_close_authority::MintCloseAuthority;
+use anchor_spl::token_2022::spl_token_2022::state::{Account as TokenAccountState, Mint as MintState};
+use solana_sha256_hasher::hashv;
 
 declare_id!("BCVGJ5YoKMftBrt5fgDYhtvY7HVBccFofFiGqJtoRjqE");
 
@@ -12,7 +21,7 @@ declare_id!("BCVGJ5YoKMftBrt5fgDYhtvY7HVBccFofFiGqJtoRjqE");
 /// Game token mint ($OLO on pump.fun)
 pub const GAME_TOKEN_MINT: Pubkey = pubkey!("2pXjxbdHnYWtH2gtDN495Ve1jm8bs1zoUL6XsUi3pump");
 
-/// Land price in tokens (10,000 tokens, burned on purchase)
+/// Land price in tokens (10,000 tokens, burned on purchase unless a revenue split is set)
 pub const LAND_PRICE_TOKENS: u64 = 10_000 * TOKEN_MULTIPLIER;
 
 /// Maximum land ID (21000 lands total, matching Mars globe grid)
@@ -21,10 +30,14 @@ pub const MAX_LAND_ID: u16 = 21000;
 /// Maximum lands per wallet
 pub const MAX_LANDS_PER_USER: u8 = 10;
 
+/// Reserved lands bitmap size (one bit per land_id, bit 0 = land #1)
+pub const RESERVED_LANDS_BITMAP_LEN: usize = (MAX_LAND_ID as usize).div_ceil(8);
+
 /// Maximum land level
 pub const MAX_LEVEL: u8 = 10;
 
-/// Token decimals
+/// Token decimals the game amounts are scaled for, required of every game token mint
+/// (transfers and burns read decimals from the mint)
 pub const TOKEN_DECIMALS: u8 = 6;
 
 /// Multiplier for token amounts (10^6)
@@ -63,6 +76,195 @@ pub const SECONDS_PER_DAY: u64 = 86400;
 /// Mining launch time: 2026-02-17 16:00 CET
 pub const MINING_START_TIME: i64 = 1771340400;
 
+/// Number of resource types (ore, water, energy)
+pub const RESOURCE_COUNT: usize = 3;
+
+/// Multiplier for resource amounts (3 decimals, so slow producers don't round to zero)
+pub const RESOURCE_MULTIPLIER: u64 = 1_000;
+
+/// Resource production per day (level 1-10), as [ore, water, energy]
+pub const RESOURCE_SPEEDS: [[u64; RESOURCE_COUNT]; 10] = [
+    [10 * RESOURCE_MULTIPLIER, 5 * RESOURCE_MULTIPLIER, 2 * RESOURCE_MULTIPLIER],     // Level 1
+    [20 * RESOURCE_MULTIPLIER, 10 * RESOURCE_MULTIPLIER, 4 * RESOURCE_MULTIPLIER],    // Level 2
+    [30 * RESOURCE_MULTIPLIER, 15 * RESOURCE_MULTIPLIER, 6 * RESOURCE_MULTIPLIER],    // Level 3
+    [50 * RESOURCE_MULTIPLIER, 25 * RESOURCE_MULTIPLIER, 10 * RESOURCE_MULTIPLIER],   // Level 4
+    [80 * RESOURCE_MULTIPLIER, 40 * RESOURCE_MULTIPLIER, 16 * RESOURCE_MULTIPLIER],   // Level 5
+    [130 * RESOURCE_MULTIPLIER, 65 * RESOURCE_MULTIPLIER, 26 * RESOURCE_MULTIPLIER],  // Level 6
+    [210 * RESOURCE_MULTIPLIER, 105 * RESOURCE_MULTIPLIER, 42 * RESOURCE_MULTIPLIER], // Level 7
+    [340 * RESOURCE_MULTIPLIER, 170 * RESOURCE_MULTIPLIER, 68 * RESOURCE_MULTIPLIER], // Level 8
+    [450 * RESOURCE_MULTIPLIER, 225 * RESOURCE_MULTIPLIER, 90 * RESOURCE_MULTIPLIER], // Level 9
+    [790 * RESOURCE_MULTIPLIER, 395 * RESOURCE_MULTIPLIER, 158 * RESOURCE_MULTIPLIER], // Level 10
+];
+
+/// Resources consumed by upgrades alongside the token burn (level 2-10), as [ore, water, energy]
+pub const UPGRADE_RESOURCE_COSTS: [[u64; RESOURCE_COUNT]; 9] = [
+    [0, 0, 0],                                                                          // 1 → 2
+    [20 * RESOURCE_MULTIPLIER, 10 * RESOURCE_MULTIPLIER, 4 * RESOURCE_MULTIPLIER],      // 2 → 3
+    [60 * RESOURCE_MULTIPLIER, 30 * RESOURCE_MULTIPLIER, 12 * RESOURCE_MULTIPLIER],     // 3 → 4
+    [150 * RESOURCE_MULTIPLIER, 75 * RESOURCE_MULTIPLIER, 30 * RESOURCE_MULTIPLIER],    // 4 → 5
+    [320 * RESOURCE_MULTIPLIER, 160 * RESOURCE_MULTIPLIER, 64 * RESOURCE_MULTIPLIER],   // 5 → 6
+    [650 * RESOURCE_MULTIPLIER, 325 * RESOURCE_MULTIPLIER, 130 * RESOURCE_MULTIPLIER],  // 6 → 7
+    [1_260 * RESOURCE_MULTIPLIER, 630 * RESOURCE_MULTIPLIER, 252 * RESOURCE_MULTIPLIER], // 7 → 8
+    [2_380 * RESOURCE_MULTIPLIER, 1_190 * RESOURCE_MULTIPLIER, 476 * RESOURCE_MULTIPLIER], // 8 → 9
+    [3_600 * RESOURCE_MULTIPLIER, 1_800 * RESOURCE_MULTIPLIER, 720 * RESOURCE_MULTIPLIER], // 9 → 10
+];
+
+/// Lands per region (21 regions of 1000 lands on the Mars globe grid)
+pub const LANDS_PER_REGION: u16 = 1000;
+
+/// Land NFT symbol
+pub const LAND_NFT_SYMBOL: &str = "LAND";
+
+/// Maximum length of the land NFT metadata URI base
+pub const MAX_LAND_NFT_URI_LEN: usize = 128;
+
+/// Basis points denominator (100%)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Minimum rental duration (1 day)
+pub const MIN_RENTAL_DURATION: i64 = 86400;
+
+/// Maximum rental duration (90 days)
+pub const MAX_RENTAL_DURATION: i64 = 90 * 86400;
+
+/// Minimum staking lock duration (7 days)
+pub const MIN_STAKE_DURATION: i64 = 7 * 86400;
+
+/// Maximum staking lock duration (365 days, earns the full duration weight)
+pub const MAX_STAKE_DURATION: i64 = 365 * 86400;
+
+/// Staked amount that earns the full amount weight (100,000 tokens)
+pub const STAKE_FULL_BOOST_AMOUNT: u64 = 100_000 * TOKEN_MULTIPLIER;
+
+/// Maximum earnings boost from staking in basis points (+50%)
+pub const MAX_STAKE_BONUS_BPS: u16 = 5_000;
+
+/// Penalty for unstaking before the lock ends in basis points (20%, sent to token vault)
+pub const EARLY_UNSTAKE_PENALTY_BPS: u16 = 2_000;
+
+/// Maximum members per guild
+pub const MAX_GUILD_MEMBERS: usize = 20;
+
+/// Maximum guild name length
+pub const MAX_GUILD_NAME_LEN: usize = 32;
+
+/// Maximum guild tax on members' claims in basis points (20%)
+pub const MAX_GUILD_TAX_BPS: u16 = 2_000;
+
+/// Guild earnings bonus per member land level in basis points (+0.05%)
+pub const GUILD_BONUS_BPS_PER_LEVEL: u16 = 5;
+
+/// Maximum guild earnings bonus in basis points (+10%)
+pub const MAX_GUILD_BONUS_BPS: u16 = 1_000;
+
+/// Number of achievements (see `Achievement`)
+pub const ACHIEVEMENT_COUNT: usize = 4;
+
+/// Land level that unlocks the level achievement
+pub const ACHIEVEMENT_LEVEL: u8 = 5;
+
+/// Claim streak (days) that unlocks the streak achievement
+pub const ACHIEVEMENT_STREAK_DAYS: u16 = 30;
+
+/// Claim bonus per consecutive claim day after the first, in basis points (+1%)
+pub const STREAK_BONUS_BPS_PER_DAY: u16 = 100;
+
+/// Maximum claim streak bonus in basis points (+20%)
+pub const MAX_STREAK_BONUS_BPS: u16 = 2_000;
+
+/// Minimum time between random event requests for a land (1 day)
+pub const LAND_EVENT_COOLDOWN: i64 = 86400;
+
+/// How long a random land event modifies earnings (1 day)
+pub const LAND_EVENT_DURATION: i64 = 86400;
+
+/// Chance of a meteor strike per land event roll (percent)
+pub const METEOR_STRIKE_CHANCE: u64 = 10;
+
+/// Chance of a resource discovery per land event roll (percent)
+pub const RESOURCE_DISCOVERY_CHANCE: u64 = 20;
+
+/// Earnings modifier during a meteor strike in basis points (-50%)
+pub const METEOR_STRIKE_MODIFIER_BPS: i16 = -5_000;
+
+/// Earnings modifier during a resource discovery in basis points (+50%)
+pub const RESOURCE_DISCOVERY_MODIFIER_BPS: i16 = 5_000;
+
+/// Slots after which an unfulfilled randomness request can be cancelled (~1 hour)
+pub const RANDOMNESS_REQUEST_EXPIRY_SLOTS: u64 = 9_000;
+
+/// Tokens burned by the attacker per raid (100 tokens)
+pub const RAID_COST: u64 = 100 * TOKEN_MULTIPLIER;
+
+/// Share of the target's pending earnings stolen by a successful raid in basis points (20%)
+pub const RAID_STEAL_BPS: u16 = 2_000;
+
+/// Minimum time between raids on the same land (1 day)
+pub const RAID_COOLDOWN: i64 = 86400;
+
+/// Raid protection after the owner claims (6 hours)
+pub const RAID_PROTECTION_WINDOW: i64 = 6 * 3600;
+
+/// Base raid success chance for equal levels without defense (percent)
+pub const RAID_BASE_CHANCE: i64 = 50;
+
+/// Raid success chance per level of difference between attacker and target (percent)
+pub const RAID_CHANCE_PER_LEVEL: i64 = 5;
+
+/// Raid success chance removed per target defense level (percent)
+pub const RAID_CHANCE_PER_DEFENSE: i64 = 10;
+
+/// Raid success chance bounds (percent)
+pub const RAID_MIN_CHANCE: i64 = 5;
+pub const RAID_MAX_CHANCE: i64 = 95;
+
+/// Defense building costs in tokens (level 1-3, burned)
+pub const DEFENSE_COSTS: [u64; 3] = [
+    500 * TOKEN_MULTIPLIER,   // 0 → 1
+    1_500 * TOKEN_MULTIPLIER, // 1 → 2
+    4_000 * TOKEN_MULTIPLIER, // 2 → 3
+];
+
+/// Maximum defense level
+pub const MAX_DEFENSE_LEVEL: u8 = 3;
+
+/// Token-2022 mint extensions the game can't work with: tokens that can't be moved, balances it
+/// can't read, transfers that need extra hook accounts, and delegates that could drain its vaults
+pub const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 5] = [
+    ExtensionType::NonTransferable,
+    ExtensionType::ConfidentialTransferMint,
+    ExtensionType::ConfidentialMintBurn,
+    ExtensionType::TransferHook,
+    ExtensionType::PermanentDelegate,
+];
+
+/// Maximum Merkle proof depth for whitelist purchases (up to ~1M wallets)
+pub const MAX_WHITELIST_PROOF_LEN: usize = 20;
+
+/// Lands per row of the land grid (21000 lands = 210 columns × 100 rows)
+pub const LAND_GRID_WIDTH: u16 = 210;
+
+/// Lands per mega-plot
+pub const MIN_MEGA_PLOT_LANDS: usize = 2;
+pub const MAX_MEGA_PLOT_LANDS: usize = 4;
+
+/// Mega-plot earnings bonus per land beyond the first in basis points (+5% each)
+pub const MEGA_PLOT_BONUS_BPS_PER_LAND: u16 = 500;
+
+/// Current account layout versions (0 on chain = deployed account from before the version
+/// byte, identified by its size, see Versioned::LEGACY_SIZES)
+pub const GAME_STATE_VERSION: u8 = 3;
+pub const LAND_DATA_VERSION: u8 = 2;
+pub const USER_PROFILE_VERSION: u8 = 2;
+
+/// Players ranked on each season leaderboard
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// Season prize pool split by leaderboard rank in basis points (sums to 100%)
+pub const SEASON_PRIZE_SPLIT_BPS: [u16; LEADERBOARD_SIZE] = [
+    3_000, 2_000, 1_500, 1_000, 800, 600, 400, 300, 200, 200,
+];
+
 // ============================================================================
 // PROGRAM
 // ============================================================================
@@ -75,7 +277,7 @@ pub mod colony {
     pub fn initialize_game(ctx: Context<InitializeGame>) -> Result<()> {
         let game_state = &mut ctx.accounts.game_state;
         game_state.authority = ctx.accounts.authority.key();
-        game_state.treasury_balance = 0;
+        game_state.total_revenue = 0;
         game_state.total_lands_sold = 0;
         game_state.total_sol_collected = 0;
         game_state.is_active = true;
@@ -83,123 +285,439 @@ pub mod colony {
         game_state.vault_bump = ctx.bumps.vault;
         game_state.token_mint = GAME_TOKEN_MINT;
         game_state.token_vault_bump = 0;
+        game_state.version = GAME_STATE_VERSION;
 
         msg!("Game initialized by: {}", game_state.authority);
         Ok(())
     }
 
-    /// Buy a land plot
-    pub fn buy_land(ctx: Context<BuyLand>, land_id: u16) -> Result<()> {
-        require!(ctx.accounts.game_state.is_active, ColonyError::GameNotActive);
-        require!(land_id > 0 && land_id <= MAX_LAND_ID, ColonyError::InvalidLandId);
-        require!(
-            ctx.accounts.user_profile.lands_owned < MAX_LANDS_PER_USER,
-            ColonyError::MaxLandsReached
-        );
+    /// Buy a land plot. During the whitelist window `proof` must prove the buyer is in the
+    /// whitelist Merkle tree (ignored in the public phase).
+    pub fn buy_land(ctx: Context<BuyLand>, land_id: u16, proof: Vec<[u8; 32]>) -> Result<()> {
+        let clock = Clock::get()?;
+        let buyer = ctx.accounts.user.key();
+        check_land_purchase(
+            &mut ctx.accounts.game_state,
+            &mut ctx.accounts.user_profile,
+            ctx.accounts.reserved_lands.as_ref(),
+            &buyer,
+            land_id,
+            &proof,
+            &clock,
+        )?;
 
-        // Burn tokens from user (payment for land)
-        burn_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                BurnChecked {
-                    mint: ctx.accounts.token_mint.to_account_info(),
-                    from: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
+        // Pay for the land: burned, or split with the token vault and team wallet
+        collect_revenue(
+            &mut ctx.accounts.game_state,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.token_vault.as_ref().map(|vault| vault.to_account_info()),
+            ctx.accounts.team_token_account.as_ref().map(|account| account.to_account_info()),
+            RevenueSource::LandPurchase,
+            land_id,
             LAND_PRICE_TOKENS,
-            TOKEN_DECIMALS,
+            clock.unix_timestamp,
         )?;
 
-        // Initialize land data
-        let land_data = &mut ctx.accounts.land_data;
-        land_data.land_id = land_id;
-        land_data.owner = ctx.accounts.user.key();
-        land_data.level = 1;
-        land_data.fixed_earnings = 0;
-        land_data.last_checkout = if Clock::get()?.unix_timestamp < MINING_START_TIME {
-            MINING_START_TIME
-        } else {
-            Clock::get()?.unix_timestamp
-        };
-        land_data.bump = ctx.bumps.land_data;
+        init_land(&mut ctx.accounts.land_data, buyer, land_id, ctx.bumps.land_data, clock.unix_timestamp);
+
+        // Mint the land NFT to the buyer
+        mint_land_nft_to_holder(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.land_mint.to_account_info(),
+            ctx.bumps.land_mint,
+            &ctx.accounts.user_land_token_account.to_account_info(),
+            &ctx.accounts.nft_token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.land_data,
+        )?;
 
-        // Update user profile
-        let user_profile = &mut ctx.accounts.user_profile;
-        user_profile.owner = ctx.accounts.user.key();
-        user_profile.lands_owned = user_profile
-            .lands_owned
-            .checked_add(1)
-            .ok_or(ColonyError::Overflow)?;
-        user_profile.bump = ctx.bumps.user_profile;
+        record_land_purchase(
+            &mut ctx.accounts.user_profile,
+            ctx.accounts.referrer_profile.as_deref().map(|profile| &**profile),
+            &mut ctx.accounts.game_state,
+            buyer,
+            ctx.bumps.user_profile,
+        )?;
+
+        msg!("User {} bought land #{}", buyer, land_id);
+        Ok(())
+    }
+
+    /// Buy a land plot with SOL at `land_price_lamports` (paid into the vault) instead of
+    /// burning tokens. Same checks as buy_land.
+    pub fn buy_land_with_sol(
+        ctx: Context<BuyLandWithSol>,
+        land_id: u16,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let price = ctx.accounts.game_state.land_price_lamports;
+        require!(price > 0, ColonyError::SolPaymentDisabled);
+
+        let clock = Clock::get()?;
+        let buyer = ctx.accounts.user.key();
+        check_land_purchase(
+            &mut ctx.accounts.game_state,
+            &mut ctx.accounts.user_profile,
+            ctx.accounts.reserved_lands.as_ref(),
+            &buyer,
+            land_id,
+            &proof,
+            &clock,
+        )?;
+
+        // Pay the land price into the SOL vault
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            price,
+        )?;
 
-        // Update game state
         let game_state = &mut ctx.accounts.game_state;
-        game_state.total_lands_sold = game_state
-            .total_lands_sold
-            .checked_add(1)
+        game_state.total_sol_collected = game_state
+            .total_sol_collected
+            .checked_add(price)
             .ok_or(ColonyError::Overflow)?;
 
-        msg!("User {} bought land #{}", ctx.accounts.user.key(), land_id);
+        init_land(&mut ctx.accounts.land_data, buyer, land_id, ctx.bumps.land_data, clock.unix_timestamp);
+
+        // Mint the land NFT to the buyer
+        mint_land_nft_to_holder(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.land_mint.to_account_info(),
+            ctx.bumps.land_mint,
+            &ctx.accounts.user_land_token_account.to_account_info(),
+            &ctx.accounts.nft_token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.land_data,
+        )?;
+
+        record_land_purchase(
+            &mut ctx.accounts.user_profile,
+            ctx.accounts.referrer_profile.as_deref().map(|profile| &**profile),
+            &mut ctx.accounts.game_state,
+            buyer,
+            ctx.bumps.user_profile,
+        )?;
+
+        msg!("User {} bought land #{} for {} lamports", buyer, land_id, price);
+        Ok(())
+    }
+
+    /// Buy a land paying with a whitelisted alternative mint at its PaymentMint rate.
+    /// The payment goes to the mint's treasury token account instead of being burned.
+    pub fn buy_land_with_token(
+        ctx: Context<BuyLandWithToken>,
+        land_id: u16,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let amount = payment_amount(&ctx.accounts.payment_mint, LAND_PRICE_TOKENS)?;
+
+        let clock = Clock::get()?;
+        let buyer = ctx.accounts.user.key();
+        check_land_purchase(
+            &mut ctx.accounts.game_state,
+            &mut ctx.accounts.user_profile,
+            ctx.accounts.reserved_lands.as_ref(),
+            &buyer,
+            land_id,
+            &proof,
+            &clock,
+        )?;
+
+        // Pay the converted land price into the payment mint's treasury (the buyer also pays any transfer fee)
+        let payment_mint = ctx.accounts.payment_token_mint.to_account_info();
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.user_payment_account.to_account_info(),
+            &ctx.accounts.treasury_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &payment_mint,
+            fee_inclusive_amount(&payment_mint, amount)?,
+            &[],
+        )?;
+
+        init_land(&mut ctx.accounts.land_data, buyer, land_id, ctx.bumps.land_data, clock.unix_timestamp);
+
+        // Mint the land NFT to the buyer
+        mint_land_nft_to_holder(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.land_mint.to_account_info(),
+            ctx.bumps.land_mint,
+            &ctx.accounts.user_land_token_account.to_account_info(),
+            &ctx.accounts.nft_token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.land_data,
+        )?;
+
+        record_land_purchase(
+            &mut ctx.accounts.user_profile,
+            ctx.accounts.referrer_profile.as_deref().map(|profile| &**profile),
+            &mut ctx.accounts.game_state,
+            buyer,
+            ctx.bumps.user_profile,
+        )?;
+
+        msg!(
+            "User {} bought land #{} for {} of mint {}",
+            buyer,
+            land_id,
+            amount,
+            ctx.accounts.payment_token_mint.key()
+        );
         Ok(())
     }
 
-    /// Claim earnings from a specific land (transfers real SPL tokens to user)
+    /// Claim earnings from a specific land (transfers real SPL tokens to user).
+    /// While the land is rented only the renter can claim, and the owner's share is paid alongside.
+    /// Earnings go to `payout_token_account` if given; owners with a default payout address must
+    /// pass a token account owned by it.
     pub fn claim_earnings(ctx: Context<ClaimEarnings>, _land_id: u16) -> Result<()> {
         let clock = Clock::get()?;
         require!(clock.unix_timestamp >= MINING_START_TIME, ColonyError::MiningNotStarted);
 
         let land_data = &ctx.accounts.land_data;
-        require!(land_data.owner == ctx.accounts.user.key(), ColonyError::NotLandOwner);
+        let is_rented = land_data.rental_end > clock.unix_timestamp;
+        let owner_share_bps = if is_rented {
+            let rental = ctx
+                .accounts
+                .rental
+                .as_ref()
+                .ok_or(ColonyError::RentalAccountRequired)?;
+            require!(rental.renter == ctx.accounts.user.key(), ColonyError::NotRenter);
+            rental.owner_share_bps
+        } else {
+            require!(land_data.owner == ctx.accounts.user.key(), ColonyError::NotLandOwner);
+            require!(land_data.rental_end == 0, ColonyError::RentalNotEnded);
+            0
+        };
 
-        let earnings = calculate_earnings(land_data, clock.unix_timestamp)?;
+        // Settle the land (resources go to the owner's profile)
+        let mut earnings = settle_land(
+            &mut ctx.accounts.land_data,
+            &mut ctx.accounts.user_profile,
+            clock.unix_timestamp,
+        )?;
         require!(earnings > 0, ColonyError::NoEarnings);
 
-        // Check token vault has enough real tokens
-        require!(
-            ctx.accounts.token_vault.amount >= earnings,
-            ColonyError::InsufficientTreasury
-        );
+        // Freshly claimed lands can't be raided for a while
+        ctx.accounts.land_data.protected_until = clock.unix_timestamp + RAID_PROTECTION_WINDOW;
+
+        // Owners' own claims extend their daily claim streak. Land milestones are only unlocked
+        // by purchases and upgrades, so lands passed between wallets can't farm them.
+        let user_profile = &mut ctx.accounts.user_profile;
+        if !is_rented {
+            update_claim_streak(user_profile, clock.unix_timestamp);
+            if user_profile.claim_streak >= ACHIEVEMENT_STREAK_DAYS {
+                unlock_achievement(user_profile, Achievement::ClaimStreak30);
+            }
+
+            // Streak bonus on the owner's own claims
+            earnings = earnings
+                .checked_add(bps_of(earnings, streak_bonus_bps(user_profile.claim_streak))?)
+                .ok_or(ColonyError::Overflow)?;
+        }
+
+        // Apply the owner's guild bonus, then take the guild tax from the total
+        let mut guild_tax = 0;
+        if ctx.accounts.user_profile.guild != Pubkey::default() {
+            let guild = ctx
+                .accounts
+                .guild
+                .as_ref()
+                .ok_or(ColonyError::GuildAccountRequired)?;
+            earnings = earnings
+                .checked_add(bps_of(earnings, guild.bonus_bps)?)
+                .ok_or(ColonyError::Overflow)?;
+            if guild.tax_bps > 0 {
+                let guild_vault = ctx
+                    .accounts
+                    .guild_vault
+                    .as_ref()
+                    .ok_or(ColonyError::GuildAccountRequired)?;
+                // No tax while the treasury still holds the pre-migration mint (see
+                // migrate_guild_vault_close)
+                if guild_vault.mint == ctx.accounts.token_mint.key() {
+                    guild_tax = bps_of(earnings, guild.tax_bps)?;
+                }
+            }
+        }
+
+        let owner_share = bps_of(earnings - guild_tax, owner_share_bps)?;
+        let user_share = earnings - guild_tax - owner_share;
+
+        // Count the land's earnings towards the owner's season score
+        if let Some(season) = ctx.accounts.season.as_mut() {
+            record_season_score(season, &mut ctx.accounts.user_profile, clock.unix_timestamp, earnings)?;
+        }
+
+        // Accrue the referrer's cut of the owner's earnings (paid on top, not deducted)
+        let referral_bps = ctx.accounts.game_state.referral_bps;
+        if let Some(referrer_profile) = ctx.accounts.referrer_profile.as_mut() {
+            let reward = bps_of(earnings, referral_bps)?;
+            referrer_profile.referral_rewards = referrer_profile
+                .referral_rewards
+                .checked_add(reward)
+                .ok_or(ColonyError::Overflow)?;
+        }
+
+        // Resolve where the claimer's share goes (the owner's default payout doesn't bind renters)
+        let owner_payout = ctx.accounts.user_profile.payout_address;
+        let default_payout = if is_rented { Pubkey::default() } else { owner_payout };
+        let destination = match &ctx.accounts.payout_token_account {
+            Some(payout_token_account) => {
+                require!(
+                    default_payout == Pubkey::default()
+                        || payout_token_account.owner == default_payout,
+                    ColonyError::PayoutAddressMismatch
+                );
+                payout_token_account.to_account_info()
+            }
+            None => {
+                require!(
+                    default_payout == Pubkey::default(),
+                    ColonyError::PayoutAccountRequired
+                );
+                ctx.accounts.user_token_account.to_account_info()
+            }
+        };
 
         // Transfer real SPL tokens from vault to user
-        let bump = ctx.accounts.game_state.bump;
-        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+        vault_transfer(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_vault.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.game_state,
+            &destination,
+            user_share,
+        )?;
 
-        transfer_checked(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.token_vault.to_account_info(),
-                    to: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.game_state.to_account_info(),
-                    mint: ctx.accounts.token_mint.to_account_info(),
-                },
-                signer_seeds,
-            ),
+        if owner_share > 0 {
+            let owner_token_account = ctx
+                .accounts
+                .owner_token_account
+                .as_ref()
+                .ok_or(ColonyError::OwnerTokenAccountRequired)?;
+            let expected_owner = if owner_payout == Pubkey::default() {
+                ctx.accounts.land_data.owner
+            } else {
+                owner_payout
+            };
+            require!(
+                owner_token_account.owner == expected_owner,
+                ColonyError::InvalidTokenAccountOwner
+            );
+            vault_transfer(
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.token_vault.to_account_info(),
+                &ctx.accounts.token_mint.to_account_info(),
+                &ctx.accounts.game_state,
+                &owner_token_account.to_account_info(),
+                owner_share,
+            )?;
+        }
+
+        if guild_tax > 0 {
+            let guild_vault = ctx
+                .accounts
+                .guild_vault
+                .as_ref()
+                .ok_or(ColonyError::GuildAccountRequired)?;
+            vault_transfer(
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.token_vault.to_account_info(),
+                &ctx.accounts.token_mint.to_account_info(),
+                &ctx.accounts.game_state,
+                &guild_vault.to_account_info(),
+                guild_tax,
+            )?;
+        }
+
+        msg!(
+            "Claimed {} tokens from land #{} ({} to owner, {} guild tax)",
             earnings,
-            TOKEN_DECIMALS,
+            ctx.accounts.land_data.land_id,
+            owner_share,
+            guild_tax
+        );
+        Ok(())
+    }
+
+    /// Claim accrued referral rewards (transfers real SPL tokens from token vault)
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let rewards = ctx.accounts.user_profile.referral_rewards;
+        require!(rewards > 0, ColonyError::NoEarnings);
+
+        ctx.accounts.user_profile.referral_rewards = 0;
+
+        vault_transfer(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_vault.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.user_token_account.to_account_info(),
+            rewards,
         )?;
 
-        // Update land data
-        let land_data = &mut ctx.accounts.land_data;
-        land_data.fixed_earnings = 0;
-        land_data.last_checkout = clock.unix_timestamp;
+        msg!("Claimed {} referral reward tokens", rewards);
+        Ok(())
+    }
 
-        msg!("Claimed {} tokens from land #{}", earnings, land_data.land_id);
+    /// Claim the token reward for an unlocked achievement, once per achievement (paid from token vault)
+    pub fn claim_achievement_reward(
+        ctx: Context<ClaimAchievementReward>,
+        achievement: Achievement,
+    ) -> Result<()> {
+        let bit = 1u32 << achievement as u32;
+        let user_profile = &ctx.accounts.user_profile;
+        require!(user_profile.achievements & bit != 0, ColonyError::AchievementLocked);
+        require!(
+            user_profile.achievements_claimed & bit == 0,
+            ColonyError::AchievementAlreadyClaimed
+        );
+
+        let reward = ctx.accounts.game_state.achievement_rewards[achievement as usize];
+        require!(reward > 0, ColonyError::NoEarnings);
+
+        ctx.accounts.user_profile.achievements_claimed |= bit;
+
+        vault_transfer(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_vault.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.user_token_account.to_account_info(),
+            reward,
+        )?;
+
+        msg!("Claimed {} tokens for achievement {:?}", reward, achievement);
         Ok(())
     }
 
     /// Upgrade a land to the next level (user pays real SPL tokens)
     pub fn upgrade_land(ctx: Context<UpgradeLand>, _land_id: u16) -> Result<()> {
         let clock = Clock::get()?;
-        require!(clock.unix_timestamp >= MINING_START_TIME, ColonyError::MiningNotStarted);
-
-        let land_data = &ctx.accounts.land_data;
-        require!(land_data.owner == ctx.accounts.user.key(), ColonyError::NotLandOwner);
-        require!(land_data.level < MAX_LEVEL, ColonyError::MaxLevelReached);
-
-        let pending = calculate_earnings(land_data, clock.unix_timestamp)?;
-        let cost = UPGRADE_COSTS[(land_data.level - 1) as usize];
+        let cost = check_land_upgrade(
+            &ctx.accounts.land_data,
+            &ctx.accounts.user.key(),
+            clock.unix_timestamp,
+        )?;
 
         // Check user has enough real tokens
         require!(
@@ -207,275 +725,5141 @@ pub mod colony {
             ColonyError::InsufficientBalance
         );
 
-        // Burn tokens from user (upgrade cost)
-        burn_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                BurnChecked {
-                    mint: ctx.accounts.token_mint.to_account_info(),
-                    from: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
+        // Pay the upgrade cost: burned, or split with the token vault and team wallet
+        collect_revenue(
+            &mut ctx.accounts.game_state,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            ctx.accounts.token_vault.as_ref().map(|vault| vault.to_account_info()),
+            ctx.accounts.team_token_account.as_ref().map(|account| account.to_account_info()),
+            RevenueSource::Upgrade,
+            ctx.accounts.land_data.land_id,
             cost,
-            TOKEN_DECIMALS,
+            clock.unix_timestamp,
         )?;
 
-        // Upgrade land
-        let land_data = &mut ctx.accounts.land_data;
-        land_data.fixed_earnings = pending;
-        land_data.last_checkout = clock.unix_timestamp;
-        land_data.level += 1;
+        apply_land_upgrade(
+            &mut ctx.accounts.land_data,
+            &mut ctx.accounts.user_profile,
+            ctx.accounts.season.as_deref_mut().map(|season| &mut **season),
+            cost,
+            clock.unix_timestamp,
+        )?;
 
+        let land_data = &ctx.accounts.land_data;
         msg!("Land #{} upgraded to level {}", land_data.land_id, land_data.level);
         Ok(())
     }
 
-    /// Initialize the token vault PDA (owner only, call after set_token_mint)
-    pub fn init_token_vault(ctx: Context<InitTokenVault>) -> Result<()> {
-        require!(
-            ctx.accounts.game_state.token_mint != Pubkey::default(),
-            ColonyError::TokenMintNotSet
-        );
+    /// Upgrade a land paying with a whitelisted alternative mint at its PaymentMint rate.
+    /// The payment goes to the mint's treasury token account instead of being burned.
+    pub fn upgrade_land_with_token(ctx: Context<UpgradeLandWithToken>, _land_id: u16) -> Result<()> {
+        let clock = Clock::get()?;
+        let cost = check_land_upgrade(
+            &ctx.accounts.land_data,
+            &ctx.accounts.user.key(),
+            clock.unix_timestamp,
+        )?;
+        let amount = payment_amount(&ctx.accounts.payment_mint, cost)?;
+
+        // Pay the converted cost into the payment mint's treasury (the buyer also pays any transfer fee)
+        let payment_mint = ctx.accounts.payment_token_mint.to_account_info();
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.user_payment_account.to_account_info(),
+            &ctx.accounts.treasury_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &payment_mint,
+            fee_inclusive_amount(&payment_mint, amount)?,
+            &[],
+        )?;
 
-        let game_state = &mut ctx.accounts.game_state;
-        game_state.token_vault_bump = ctx.bumps.token_vault;
+        apply_land_upgrade(
+            &mut ctx.accounts.land_data,
+            &mut ctx.accounts.user_profile,
+            ctx.accounts.season.as_deref_mut().map(|season| &mut **season),
+            cost,
+            clock.unix_timestamp,
+        )?;
 
-        msg!("Token vault initialized for mint: {}", game_state.token_mint);
+        msg!(
+            "Land #{} upgraded to level {} for {} of mint {}",
+            ctx.accounts.land_data.land_id,
+            ctx.accounts.land_data.level,
+            amount,
+            ctx.accounts.payment_token_mint.key()
+        );
         Ok(())
     }
 
-    /// Withdraw all SOL from vault (owner only)
-    pub fn withdraw_sol(ctx: Context<WithdrawSol>) -> Result<()> {
-        let amount = ctx.accounts.vault.lamports();
-        require!(amount > 0, ColonyError::InsufficientBalance);
+    /// Upgrade a land using its pending earnings. The earnings used are applied in place from the
+    /// token vault, only the shortfall is paid by the user, and any excess stays pending.
+    pub fn compound(ctx: Context<Compound>, _land_id: u16) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp >= MINING_START_TIME, ColonyError::MiningNotStarted);
 
-        let bump = ctx.accounts.game_state.vault_bump;
-        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", &[bump]]];
+        let land_data = &ctx.accounts.land_data;
+        require!(land_data.owner == ctx.accounts.user.key(), ColonyError::NotLandOwner);
+        require!(land_data.level < MAX_LEVEL, ColonyError::MaxLevelReached);
+        require!(land_data.rental_end == 0, ColonyError::LandRented);
+        require!(land_data.mega_plot == 0, ColonyError::LandMerged);
 
-        system_program::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                system_program::Transfer {
-                    from: ctx.accounts.vault.to_account_info(),
-                    to: ctx.accounts.authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount,
+        let cost = UPGRADE_COSTS[(land_data.level - 1) as usize];
+        let resource_cost = UPGRADE_RESOURCE_COSTS[(land_data.level - 1) as usize];
+
+        // Settle the land, then pay the resource part of the upgrade
+        let earnings = settle_land(
+            &mut ctx.accounts.land_data,
+            &mut ctx.accounts.user_profile,
+            clock.unix_timestamp,
         )?;
+        debit_resources(&mut ctx.accounts.user_profile, &resource_cost)?;
 
-        msg!("Withdrawn {} lamports to authority", amount);
+        let from_earnings = earnings.min(cost);
+        let shortfall = cost - from_earnings;
+
+        // Check token vault backs the earnings and the user covers the shortfall
+        require!(
+            ctx.accounts.token_vault.amount >= from_earnings,
+            ColonyError::InsufficientTreasury
+        );
+        require!(
+            ctx.accounts.user_token_account.amount >= shortfall,
+            ColonyError::InsufficientBalance
+        );
+
+        // Pay the upgrade cost: the earnings part without leaving the token vault, the shortfall
+        // from the user (each burned, or split with the token vault and team wallet)
+        let land_id = ctx.accounts.land_data.land_id;
+        let team_token_account = ctx
+            .accounts
+            .team_token_account
+            .as_ref()
+            .map(|account| account.to_account_info());
+        if from_earnings > 0 {
+            collect_vault_revenue(
+                &mut ctx.accounts.game_state,
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.token_mint.to_account_info(),
+                &ctx.accounts.token_vault.to_account_info(),
+                team_token_account.clone(),
+                ctx.accounts.user.key(),
+                RevenueSource::Upgrade,
+                land_id,
+                from_earnings,
+                clock.unix_timestamp,
+            )?;
+        }
+        if shortfall > 0 {
+            collect_revenue(
+                &mut ctx.accounts.game_state,
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.token_mint.to_account_info(),
+                &ctx.accounts.user_token_account.to_account_info(),
+                &ctx.accounts.user.to_account_info(),
+                Some(ctx.accounts.token_vault.to_account_info()),
+                team_token_account,
+                RevenueSource::Upgrade,
+                land_id,
+                shortfall,
+                clock.unix_timestamp,
+            )?;
+        }
+
+        // Count the upgrade spend towards the season score
+        if let Some(season) = ctx.accounts.season.as_mut() {
+            record_season_score(season, &mut ctx.accounts.user_profile, clock.unix_timestamp, cost)?;
+        }
+
+        // Upgrade land (unused earnings stay claimable)
+        let land_data = &mut ctx.accounts.land_data;
+        land_data.fixed_earnings = earnings - from_earnings;
+        land_data.level += 1;
+        if land_data.level >= ACHIEVEMENT_LEVEL {
+            unlock_achievement(&mut ctx.accounts.user_profile, Achievement::Level5);
+        }
+
+        msg!(
+            "Land #{} compounded to level {} ({} from earnings, {} from user)",
+            land_data.land_id,
+            land_data.level,
+            from_earnings,
+            shortfall
+        );
         Ok(())
     }
 
-    /// Withdraw all SPL tokens from token vault to authority (owner only)
-    pub fn withdraw_tokens(ctx: Context<WithdrawTokens>) -> Result<()> {
-        let amount = ctx.accounts.token_vault.amount;
-        require!(amount > 0, ColonyError::InsufficientBalance);
+    /// Convert resources into game tokens at the configured craft rate (paid from token vault)
+    pub fn craft_tokens(ctx: Context<CraftTokens>, resource: Resource, amount: u64) -> Result<()> {
+        require!(amount > 0, ColonyError::InvalidAmount);
 
-        let bump = ctx.accounts.game_state.bump;
-        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+        let rate = ctx.accounts.game_state.craft_rates[resource as usize];
+        require!(rate > 0, ColonyError::CraftingDisabled);
 
-        transfer_checked(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.token_vault.to_account_info(),
-                    to: ctx.accounts.authority_token_account.to_account_info(),
-                    authority: ctx.accounts.game_state.to_account_info(),
-                    mint: ctx.accounts.token_mint.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount,
-            TOKEN_DECIMALS,
+        let tokens = amount
+            .checked_mul(rate)
+            .ok_or(ColonyError::Overflow)?
+            .checked_div(RESOURCE_MULTIPLIER)
+            .ok_or(ColonyError::Overflow)?;
+        require!(tokens > 0, ColonyError::InvalidAmount);
+
+        let mut cost = [0; RESOURCE_COUNT];
+        cost[resource as usize] = amount;
+        debit_resources(&mut ctx.accounts.user_profile, &cost)?;
+
+        // Transfer real SPL tokens from vault to user
+        vault_transfer(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_vault.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.user_token_account.to_account_info(),
+            tokens,
         )?;
 
-        msg!("Withdrawn {} tokens to authority", amount);
+        msg!("Crafted {} tokens from {} {:?}", tokens, amount, resource);
+        Ok(())
+    }
+
+    /// Set the default payout address for claims (Pubkey::default() routes claims back to the owner)
+    pub fn set_payout_address(ctx: Context<SetPayoutAddress>, payout_address: Pubkey) -> Result<()> {
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.payout_address = payout_address;
+        msg!("Payout address for {} set to {}", user_profile.owner, payout_address);
+        Ok(())
+    }
+
+    /// Mint the NFT for a land bought before land NFTs existed (land owner only)
+    pub fn mint_land_nft(ctx: Context<MintLandNft>, _land_id: u16) -> Result<()> {
+        require!(
+            ctx.accounts.land_data.owner == ctx.accounts.user.key(),
+            ColonyError::NotLandOwner
+        );
+
+        mint_land_nft_to_holder(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.land_mint.to_account_info(),
+            ctx.bumps.land_mint,
+            &ctx.accounts.user_land_token_account.to_account_info(),
+            &ctx.accounts.nft_token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.land_data,
+        )?;
+
+        msg!("Minted NFT for land #{}", ctx.accounts.land_data.land_id);
+        Ok(())
+    }
+
+    /// Make the current land NFT holder the land owner (anyone can call after the NFT moves).
+    /// Unclaimed earnings and resources move with the land.
+    pub fn sync_land_owner(ctx: Context<SyncLandOwner>, _land_id: u16) -> Result<()> {
+        let new_owner = ctx.accounts.holder_token_account.owner;
+        let previous_owner = ctx.accounts.land_data.owner;
+        require!(new_owner != previous_owner, ColonyError::OwnerAlreadySynced);
+        require!(ctx.accounts.land_data.mega_plot == 0, ColonyError::LandMerged);
+        require!(ctx.accounts.land_data.rental_end == 0, ColonyError::LandRented);
+
+        // Move the land between the two profiles
+        let previous_owner_profile = &mut ctx.accounts.previous_owner_profile;
+        previous_owner_profile.lands_owned = previous_owner_profile
+            .lands_owned
+            .checked_sub(1)
+            .ok_or(ColonyError::Overflow)?;
+
+        let new_owner_profile = &mut ctx.accounts.new_owner_profile;
+        require!(
+            new_owner_profile.lands_owned < MAX_LANDS_PER_USER,
+            ColonyError::MaxLandsReached
+        );
+        if new_owner_profile.owner == Pubkey::default() {
+            new_owner_profile.version = USER_PROFILE_VERSION;
+        }
+        new_owner_profile.owner = new_owner;
+        new_owner_profile.lands_owned = new_owner_profile
+            .lands_owned
+            .checked_add(1)
+            .ok_or(ColonyError::Overflow)?;
+        new_owner_profile.bump = ctx.bumps.new_owner_profile;
+
+        let land_data = &mut ctx.accounts.land_data;
+        land_data.owner = new_owner;
+
+        msg!(
+            "Land #{} owner synced from {} to {}",
+            land_data.land_id,
+            previous_owner,
+            new_owner
+        );
         Ok(())
     }
 
-    /// Close the token vault account (owner only, for re-initialization with new mint)
-    pub fn admin_close_token_vault(ctx: Context<AdminCloseTokenVault>) -> Result<()> {
+    /// Write the current land level into the NFT metadata (anyone can call)
+    pub fn refresh_land_metadata(ctx: Context<RefreshLandMetadata>, _land_id: u16) -> Result<()> {
         let bump = ctx.accounts.game_state.bump;
         let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
 
-        anchor_spl::token_interface::close_account(
+        token_metadata_update_field(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token_interface::CloseAccount {
-                    account: ctx.accounts.token_vault.to_account_info(),
-                    destination: ctx.accounts.authority.to_account_info(),
-                    authority: ctx.accounts.game_state.to_account_info(),
+                ctx.accounts.nft_token_program.to_account_info(),
+                TokenMetadataUpdateField {
+                    program_id: ctx.accounts.nft_token_program.to_account_info(),
+                    metadata: ctx.accounts.land_mint.to_account_info(),
+                    update_authority: ctx.accounts.game_state.to_account_info(),
                 },
                 signer_seeds,
             ),
+            Field::Key("level".to_string()),
+            ctx.accounts.land_data.level.to_string(),
+        )?;
+
+        // Metadata may have grown
+        top_up_rent(
+            &ctx.accounts.land_mint.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
         )?;
 
-        ctx.accounts.game_state.token_vault_bump = 0;
-        msg!("Token vault closed");
+        msg!(
+            "Land #{} metadata refreshed (level {})",
+            ctx.accounts.land_data.land_id,
+            ctx.accounts.land_data.level
+        );
         Ok(())
     }
 
-    /// Pause/unpause the game (owner only)
-    pub fn set_game_active(ctx: Context<AdminAction>, is_active: bool) -> Result<()> {
+    /// Offer a land for rent (land owner only). `renter` = Pubkey::default() opens the offer to anyone.
+    pub fn create_rental_offer(
+        ctx: Context<CreateRentalOffer>,
+        land_id: u16,
+        renter: Pubkey,
+        duration: i64,
+        upfront_fee: u64,
+        owner_share_bps: u16,
+    ) -> Result<()> {
+        let land_data = &ctx.accounts.land_data;
+        require!(land_data.owner == ctx.accounts.owner.key(), ColonyError::NotLandOwner);
+        require!(land_data.mega_plot == 0, ColonyError::LandMerged);
+        require!(renter != land_data.owner, ColonyError::InvalidRentalTerms);
+        require!(
+            (MIN_RENTAL_DURATION..=MAX_RENTAL_DURATION).contains(&duration),
+            ColonyError::InvalidRentalTerms
+        );
+        require!(
+            owner_share_bps as u64 <= BPS_DENOMINATOR,
+            ColonyError::InvalidRentalTerms
+        );
+
+        let rental = &mut ctx.accounts.rental;
+        rental.land_id = land_id;
+        rental.owner = land_data.owner;
+        rental.renter = renter;
+        rental.duration = duration;
+        rental.upfront_fee = upfront_fee;
+        rental.owner_share_bps = owner_share_bps;
+        rental.start_time = 0;
+        rental.end_time = 0;
+        rental.bump = ctx.bumps.rental;
+
+        msg!(
+            "Land #{} offered for rent: {}s, fee {}, owner share {} bps",
+            land_id,
+            duration,
+            upfront_fee,
+            owner_share_bps
+        );
+        Ok(())
+    }
+
+    /// Accept a rental offer: the renter pays the upfront fee and the owner's pending earnings are settled
+    pub fn accept_rental(ctx: Context<AcceptRental>, _land_id: u16) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp >= MINING_START_TIME, ColonyError::MiningNotStarted);
+
+        let renter = ctx.accounts.renter.key();
+        let rental = &ctx.accounts.rental;
+        require!(rental.start_time == 0, ColonyError::RentalAlreadyActive);
+        require!(
+            rental.renter == Pubkey::default() || rental.renter == renter,
+            ColonyError::NotRenter
+        );
+        require!(
+            rental.owner == ctx.accounts.land_data.owner,
+            ColonyError::RentalOfferStale
+        );
+        require!(renter != ctx.accounts.land_data.owner, ColonyError::InvalidRentalTerms);
+        require!(ctx.accounts.land_data.mega_plot == 0, ColonyError::LandMerged);
+
+        // Pay the upfront fee to the owner (the renter also pays any transfer fee)
+        if rental.upfront_fee > 0 {
+            let token_mint = ctx.accounts.token_mint.to_account_info();
+            transfer_tokens(
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.renter_token_account.to_account_info(),
+                &ctx.accounts.owner_token_account.to_account_info(),
+                &ctx.accounts.renter.to_account_info(),
+                &token_mint,
+                fee_inclusive_amount(&token_mint, rental.upfront_fee)?,
+                &[],
+            )?;
+        }
+
+        // Everything earned before the rental belongs to the owner
+        let earnings = settle_land(
+            &mut ctx.accounts.land_data,
+            &mut ctx.accounts.owner_profile,
+            clock.unix_timestamp,
+        )?;
+        if earnings > 0 {
+            vault_transfer(
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.token_vault.to_account_info(),
+                &ctx.accounts.token_mint.to_account_info(),
+                &ctx.accounts.game_state,
+                &ctx.accounts.owner_token_account.to_account_info(),
+                earnings,
+            )?;
+        }
+
+        let rental = &mut ctx.accounts.rental;
+        rental.renter = renter;
+        rental.start_time = clock.unix_timestamp;
+        rental.end_time = clock
+            .unix_timestamp
+            .checked_add(rental.duration)
+            .ok_or(ColonyError::Overflow)?;
+        ctx.accounts.land_data.rental_end = rental.end_time;
+
+        msg!(
+            "Land #{} rented by {} until {}",
+            rental.land_id,
+            renter,
+            rental.end_time
+        );
+        Ok(())
+    }
+
+    /// End a rental and close its account.
+    /// Unaccepted offers can be withdrawn by the owner, expired rentals closed by anyone.
+    /// Active rentals can be terminated early by the renter (no refund) or the owner
+    /// (refunds the unused part of the upfront fee). Earnings up to the end of the rental
+    /// (or now, if earlier) are split and paid out first.
+    pub fn end_rental(ctx: Context<EndRental>, _land_id: u16) -> Result<()> {
+        let clock = Clock::get()?;
+        let signer = ctx.accounts.signer.key();
+        let land_owner = ctx.accounts.land_data.owner;
+        let rental = &ctx.accounts.rental;
+
+        if rental.start_time == 0 {
+            require!(
+                signer == rental.owner || signer == land_owner,
+                ColonyError::Unauthorized
+            );
+        } else {
+            let active = clock.unix_timestamp < rental.end_time;
+            if active {
+                require!(
+                    signer == rental.renter || signer == land_owner,
+                    ColonyError::Unauthorized
+                );
+            }
+
+            let owner_token_account = ctx
+                .accounts
+                .owner_token_account
+                .as_ref()
+                .ok_or(ColonyError::OwnerTokenAccountRequired)?;
+            let renter_token_account = ctx
+                .accounts
+                .renter_token_account
+                .as_ref()
+                .ok_or(ColonyError::RenterTokenAccountRequired)?;
+
+            // Pay out the rental period's pending earnings; later ones accrue to the owner
+            let owner_share_bps = rental.owner_share_bps;
+            let settle_time = clock.unix_timestamp.min(rental.end_time);
+            let earnings = if ctx.accounts.land_data.last_checkout < settle_time {
+                settle_land(
+                    &mut ctx.accounts.land_data,
+                    &mut ctx.accounts.owner_profile,
+                    settle_time,
+                )?
+            } else {
+                0
+            };
+            let owner_share = bps_of(earnings, owner_share_bps)?;
+            for (to, amount) in [
+                (renter_token_account.to_account_info(), earnings - owner_share),
+                (owner_token_account.to_account_info(), owner_share),
+            ] {
+                if amount > 0 {
+                    vault_transfer(
+                        &ctx.accounts.token_program.to_account_info(),
+                        &ctx.accounts.token_vault.to_account_info(),
+                        &ctx.accounts.token_mint.to_account_info(),
+                        &ctx.accounts.game_state,
+                        &to,
+                        amount,
+                    )?;
+                }
+            }
+
+            // Owner refunds the unused part of the upfront fee
+            let rental = &ctx.accounts.rental;
+            if active && signer == land_owner && rental.upfront_fee > 0 {
+                let remaining = (rental.end_time - clock.unix_timestamp) as u128;
+                let refund = (rental.upfront_fee as u128 * remaining / rental.duration as u128) as u64;
+                if refund > 0 {
+                    let token_mint = ctx.accounts.token_mint.to_account_info();
+                    transfer_tokens(
+                        &ctx.accounts.token_program.to_account_info(),
+                        &owner_token_account.to_account_info(),
+                        &renter_token_account.to_account_info(),
+                        &ctx.accounts.signer.to_account_info(),
+                        &token_mint,
+                        fee_inclusive_amount(&token_mint, refund)?,
+                        &[],
+                    )?;
+                }
+            }
+        }
+
+        ctx.accounts.land_data.rental_end = 0;
+
+        msg!("Rental of land #{} ended by {}", ctx.accounts.rental.land_id, signer);
+        Ok(())
+        // rental account is closed by Anchor's `close = rental_owner` constraint
+    }
+
+    /// Request a random event for a land (anyone can call, once per LAND_EVENT_COOLDOWN per land).
+    /// The requester pays the request rent, refunded when the request is fulfilled.
+    pub fn request_land_event(ctx: Context<RequestLandEvent>, land_id: u16) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.randomness_source != RandomnessSource::Disabled,
+            ColonyError::RandomnessDisabled
+        );
+
+        let clock = Clock::get()?;
+        let land_data = &mut ctx.accounts.land_data;
+        require!(
+            clock.unix_timestamp >= land_data.last_event_request + LAND_EVENT_COOLDOWN,
+            ColonyError::LandEventCooldown
+        );
+        land_data.last_event_request = clock.unix_timestamp;
+
+        let request = &mut ctx.accounts.randomness_request;
+        request.land_id = land_id;
+        request.purpose = RandomnessPurpose::LandEvent;
+        request.requester = ctx.accounts.requester.key();
+        request.request_slot = clock.slot;
+        request.created_at = clock.unix_timestamp;
+        request.bump = ctx.bumps.randomness_request;
+
+        msg!("Random event requested for land #{}", land_id);
+        Ok(())
+    }
+
+    /// Fulfil a land event request with the configured randomness source and apply the outcome.
+    /// `oracle_value` is the oracle's random value (ignored by the deterministic source).
+    pub fn fulfil_land_event(
+        ctx: Context<FulfilLandEvent>,
+        _land_id: u16,
+        oracle_value: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let request = &ctx.accounts.randomness_request;
+        require!(clock.slot > request.request_slot, ColonyError::RandomnessNotReady);
+
+        let provider = randomness_provider(&ctx.accounts.game_state)?;
+        let value = provider.fulfil(
+            &request.key(),
+            request,
+            &ctx.accounts.fulfiller.key(),
+            oracle_value,
+        )?;
+        let event = roll_land_event(&value);
+
+        // Settle earnings so the event only affects the period from now on
+        let land_data = &mut ctx.accounts.land_data;
+        let pending = settle_land(land_data, &mut ctx.accounts.owner_profile, clock.unix_timestamp)?;
+        land_data.fixed_earnings = pending;
+
+        let modifier = match event {
+            LandEvent::None => 0,
+            LandEvent::MeteorStrike => METEOR_STRIKE_MODIFIER_BPS,
+            LandEvent::ResourceDiscovery => RESOURCE_DISCOVERY_MODIFIER_BPS,
+        };
+        land_data.event_modifier_bps = modifier;
+        land_data.event_end = if modifier == 0 {
+            0
+        } else {
+            clock.unix_timestamp + LAND_EVENT_DURATION
+        };
+
+        msg!("Land #{} event: {:?}", land_data.land_id, event);
+        Ok(())
+        // randomness_request is closed by Anchor's `close = requester` constraint
+    }
+
+    /// Build the next defense level on a land, lowering raid success chance (owner only, tokens burned)
+    pub fn build_defense(ctx: Context<BuildDefense>, _land_id: u16) -> Result<()> {
+        let land_data = &ctx.accounts.land_data;
+        require!(land_data.owner == ctx.accounts.user.key(), ColonyError::NotLandOwner);
+        require!(land_data.defense_level < MAX_DEFENSE_LEVEL, ColonyError::MaxLevelReached);
+
+        let cost = DEFENSE_COSTS[land_data.defense_level as usize];
+
+        // Check user has enough real tokens
+        require!(
+            ctx.accounts.user_token_account.amount >= cost,
+            ColonyError::InsufficientBalance
+        );
+
+        // Burn tokens from user (defense cost)
+        burn_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                BurnChecked {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            cost,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let land_data = &mut ctx.accounts.land_data;
+        land_data.defense_level += 1;
+
+        msg!("Land #{} defense upgraded to {}", land_data.land_id, land_data.defense_level);
+        Ok(())
+    }
+
+    /// Raid another land from one of your lands: burns RAID_COST and requests randomness.
+    /// On fulfilment a successful raid moves RAID_STEAL_BPS of the target's pending earnings
+    /// to the attacking land. Rented and merged lands can neither raid nor be raided.
+    pub fn request_raid(ctx: Context<RequestRaid>, attacker_land_id: u16, target_land_id: u16) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.randomness_source != RandomnessSource::Disabled,
+            ColonyError::RandomnessDisabled
+        );
+
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp >= MINING_START_TIME, ColonyError::MiningNotStarted);
+        let attacker_land = &ctx.accounts.attacker_land;
+        require!(
+            attacker_land.owner == ctx.accounts.attacker.key(),
+            ColonyError::NotLandOwner
+        );
+        // Rented lands pay out to the renter and merged lands earn through the mega-plot anchor,
+        // so neither side of a raid may be one
+        require!(attacker_land.rental_end == 0, ColonyError::LandRented);
+        require!(attacker_land.mega_plot == 0, ColonyError::LandMerged);
+
+        let target_land = &mut ctx.accounts.target_land;
+        require!(
+            target_land.owner != ctx.accounts.attacker.key(),
+            ColonyError::CannotRaidOwnLand
+        );
+        require!(target_land.rental_end == 0, ColonyError::LandRented);
+        require!(target_land.mega_plot == 0, ColonyError::LandMerged);
+        require!(
+            clock.unix_timestamp >= target_land.protected_until,
+            ColonyError::LandProtected
+        );
+        require!(
+            clock.unix_timestamp >= target_land.last_raided_at + RAID_COOLDOWN,
+            ColonyError::RaidCooldown
+        );
+        target_land.last_raided_at = clock.unix_timestamp;
+
+        // Check attacker has enough real tokens
+        require!(
+            ctx.accounts.user_token_account.amount >= RAID_COST,
+            ColonyError::InsufficientBalance
+        );
+
+        // Burn tokens from attacker (raid cost)
+        burn_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                BurnChecked {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.attacker.to_account_info(),
+                },
+            ),
+            RAID_COST,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let request = &mut ctx.accounts.randomness_request;
+        request.land_id = target_land_id;
+        request.purpose = RandomnessPurpose::Raid;
+        request.requester = ctx.accounts.attacker.key();
+        request.request_slot = clock.slot;
+        request.created_at = clock.unix_timestamp;
+        request.bump = ctx.bumps.randomness_request;
+        request.attacker_land_id = attacker_land_id;
+
+        msg!("Land #{} raids land #{}", attacker_land_id, target_land_id);
+        Ok(())
+    }
+
+    /// Fulfil a raid request with the configured randomness source and apply the outcome.
+    /// `oracle_value` is the oracle's random value (ignored by the deterministic source).
+    pub fn fulfil_raid(
+        ctx: Context<FulfilRaid>,
+        _target_land_id: u16,
+        oracle_value: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let request = &ctx.accounts.randomness_request;
+        require!(clock.slot > request.request_slot, ColonyError::RandomnessNotReady);
+
+        let provider = randomness_provider(&ctx.accounts.game_state)?;
+        let value = provider.fulfil(
+            &request.key(),
+            request,
+            &ctx.accounts.fulfiller.key(),
+            oracle_value,
+        )?;
+
+        let chance = raid_success_chance(&ctx.accounts.attacker_land, &ctx.accounts.target_land);
+        // A land rented out or merged since the request can no longer take part in the raid
+        let eligible = [&ctx.accounts.attacker_land, &ctx.accounts.target_land]
+            .iter()
+            .all(|land| land.rental_end == 0 && land.mega_plot == 0);
+        let success = eligible && (roll_percent(&value) as i64) < chance;
+
+        let mut stolen = 0;
+        if success {
+            // Settle the target, then move the stolen part of its pending earnings
+            let target_land = &mut ctx.accounts.target_land;
+            let pending = settle_land(
+                target_land,
+                &mut ctx.accounts.target_owner_profile,
+                clock.unix_timestamp,
+            )?;
+            stolen = bps_of(pending, RAID_STEAL_BPS)?;
+            target_land.fixed_earnings = pending - stolen;
+
+            let attacker_land = &mut ctx.accounts.attacker_land;
+            attacker_land.fixed_earnings = attacker_land
+                .fixed_earnings
+                .checked_add(stolen)
+                .ok_or(ColonyError::Overflow)?;
+        }
+
+        msg!(
+            "Raid on land #{} by land #{} {} ({}% chance), {} tokens stolen",
+            ctx.accounts.target_land.land_id,
+            ctx.accounts.attacker_land.land_id,
+            if success { "succeeded" } else { "failed" },
+            chance,
+            stolen
+        );
+        Ok(())
+        // randomness_request is closed by Anchor's `close = requester` constraint
+    }
+
+    /// Cancel a randomness request left unfulfilled for RANDOMNESS_REQUEST_EXPIRY_SLOTS (anyone
+    /// can call). The request rent goes back to the requester; a raid's RAID_COST stays burned.
+    pub fn cancel_randomness_request(
+        ctx: Context<CancelRandomnessRequest>,
+        purpose: RandomnessPurpose,
+        land_id: u16,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let request = &ctx.accounts.randomness_request;
+        require!(
+            clock.slot >= request.request_slot.saturating_add(RANDOMNESS_REQUEST_EXPIRY_SLOTS),
+            ColonyError::RandomnessRequestNotExpired
+        );
+
+        msg!("Randomness request {:?} for land #{} cancelled", purpose, land_id);
+        Ok(())
+        // randomness_request is closed by Anchor's `close = requester` constraint
+    }
+
+    /// Merge adjacent lands into a mega-plot earning their combined speed plus a merge bonus.
+    /// Remaining accounts: the LandData accounts (writable) in increasing land_id order, starting
+    /// with `anchor_land_id`. The anchor carries the combined earning stream; the other lands
+    /// keep their ownership but stop earning until the mega-plot is split.
+    pub fn merge_lands<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MergeLands<'info>>,
+        anchor_land_id: u16,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let count = ctx.remaining_accounts.len();
+        require!(
+            (MIN_MEGA_PLOT_LANDS..=MAX_MEGA_PLOT_LANDS).contains(&count),
+            ColonyError::InvalidMegaPlot
+        );
+
+        let owner = ctx.accounts.owner.key();
+        let mut lands = Vec::with_capacity(count);
+        let mut last_land_id = 0;
+        for account_info in ctx.remaining_accounts {
+            let land = Account::<LandData>::try_from(account_info)?;
+            require!(land.land_id > last_land_id, ColonyError::InvalidLandOrder);
+            last_land_id = land.land_id;
+            require!(land.owner == owner, ColonyError::NotLandOwner);
+            require!(land.mega_plot == 0, ColonyError::LandMerged);
+            require!(land.rental_end == 0, ColonyError::LandRented);
+            lands.push(land);
+        }
+        require!(lands[0].land_id == anchor_land_id, ColonyError::InvalidLandOrder);
+
+        let land_ids: Vec<u16> = lands.iter().map(|land| land.land_id).collect();
+        require!(lands_connected(&land_ids), ColonyError::LandsNotAdjacent);
+
+        let mut combined_speed: u64 = 0;
+        for land in lands.iter_mut() {
+            combined_speed = combined_speed
+                .checked_add(earning_speed(land))
+                .ok_or(ColonyError::Overflow)?;
+
+            // Keep each land's pending earnings claimable, then close its own stream
+            let pending = settle_land(land, &mut ctx.accounts.owner_profile, clock.unix_timestamp)?;
+            land.fixed_earnings = pending;
+            land.mega_plot = anchor_land_id;
+        }
+
+        let bonus_bps = MEGA_PLOT_BONUS_BPS_PER_LAND * (count as u16 - 1);
+        let mega_speed = combined_speed
+            .checked_add(bps_of(combined_speed, bonus_bps)?)
+            .ok_or(ColonyError::Overflow)?;
+        lands[0].mega_speed = mega_speed;
+
+        for land in &lands {
+            land.exit(&crate::ID)?;
+        }
+
+        let mega_plot = &mut ctx.accounts.mega_plot;
+        mega_plot.owner = owner;
+        mega_plot.anchor_land_id = anchor_land_id;
+        mega_plot.land_ids = land_ids;
+        mega_plot.earning_speed = mega_speed;
+        mega_plot.created_at = clock.unix_timestamp;
+        mega_plot.bump = ctx.bumps.mega_plot;
+
+        msg!(
+            "Mega-plot #{} merged from {} lands, earning {} per day",
+            anchor_land_id,
+            count,
+            mega_speed
+        );
+        Ok(())
+    }
+
+    /// Split a mega-plot back into its lands (mega-plot owner only).
+    /// Remaining accounts: the mega-plot's LandData accounts (writable) in increasing land_id order.
+    pub fn split_mega_plot<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SplitMegaPlot<'info>>,
+        _anchor_land_id: u16,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let mega_plot = &ctx.accounts.mega_plot;
+        require!(
+            ctx.remaining_accounts.len() == mega_plot.land_ids.len(),
+            ColonyError::LandCountMismatch
+        );
+
+        for (account_info, land_id) in ctx.remaining_accounts.iter().zip(&mega_plot.land_ids) {
+            let mut land = Account::<LandData>::try_from(account_info)?;
+            require!(land.land_id == *land_id, ColonyError::InvalidLandOrder);
+
+            // Settle the merged stream (only the anchor accrues), then restore the land's own
+            let pending = settle_land(&mut land, &mut ctx.accounts.owner_profile, clock.unix_timestamp)?;
+            land.fixed_earnings = pending;
+            land.mega_plot = 0;
+            land.mega_speed = 0;
+            land.exit(&crate::ID)?;
+        }
+
+        msg!("Mega-plot #{} split into {} lands", mega_plot.anchor_land_id, mega_plot.land_ids.len());
+        Ok(())
+        // mega_plot is closed by Anchor's `close = owner` constraint
+    }
+
+    /// Lock game tokens in the stake vault for `duration` seconds to boost all owned lands' earnings.
+    /// The boost scales with both amount and duration. A new stake replaces the boost window, so
+    /// the previous stake's unclaimed boost is first settled into each land's pending earnings.
+    /// Remaining accounts after a previous stake: all of the user's LandData accounts (writable),
+    /// in increasing land_id order.
+    pub fn stake<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Stake<'info>>,
+        amount: u64,
+        duration: i64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::Idle,
+            ColonyError::TokenMigrationInProgress
+        );
+        require!(amount > 0, ColonyError::InvalidAmount);
+        require!(
+            (MIN_STAKE_DURATION..=MAX_STAKE_DURATION).contains(&duration),
+            ColonyError::InvalidStakeDuration
+        );
+        require!(ctx.accounts.user_profile.staked_amount == 0, ColonyError::StakeAlreadyActive);
+
+        // Check user has enough real tokens (the stake vault receives `amount` after any transfer fee)
+        let token_mint = ctx.accounts.token_mint.to_account_info();
+        let deposit = fee_inclusive_amount(&token_mint, amount)?;
+        require!(
+            ctx.accounts.user_token_account.amount >= deposit,
+            ColonyError::InsufficientBalance
+        );
+
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.stake_vault.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &token_mint,
+            deposit,
+            &[],
+        )?;
+
         let game_state = &mut ctx.accounts.game_state;
-        game_state.is_active = is_active;
-        msg!("Game active status: {}", is_active);
+        game_state.total_staked = game_state
+            .total_staked
+            .checked_add(amount)
+            .ok_or(ColonyError::Overflow)?;
+
+        let clock = Clock::get()?;
+        if ctx.accounts.user_profile.stake_bonus_bps > 0 {
+            settle_stake_bonus(
+                &mut ctx.accounts.user_profile,
+                ctx.remaining_accounts,
+                clock.unix_timestamp,
+            )?;
+        }
+
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.staked_amount = amount;
+        user_profile.stake_start = clock.unix_timestamp;
+        user_profile.stake_lock_end = clock
+            .unix_timestamp
+            .checked_add(duration)
+            .ok_or(ColonyError::Overflow)?;
+        user_profile.stake_bonus_bps = stake_bonus_bps(amount, duration);
+
+        msg!(
+            "Staked {} tokens until {} (+{} bps earnings)",
+            amount,
+            user_profile.stake_lock_end,
+            user_profile.stake_bonus_bps
+        );
         Ok(())
     }
 
-    /// Set the token mint address (owner only)
-    pub fn set_token_mint(ctx: Context<AdminAction>, new_mint: Pubkey) -> Result<()> {
-        let game_state = &mut ctx.accounts.game_state;
-        game_state.token_mint = new_mint;
-        msg!("Token mint updated to: {}", new_mint);
-        Ok(())
-    }
+    /// Withdraw staked tokens. Before the lock ends a penalty is paid into the token vault
+    /// and the earnings boost stops immediately.
+    pub fn unstake(ctx: Context<Unstake>) -> Result<()> {
+        let clock = Clock::get()?;
+        let amount = ctx.accounts.user_profile.staked_amount;
+        require!(amount > 0, ColonyError::NoActiveStake);
+
+        // Stakes are drained without penalty while a token mint migration is running
+        let early = clock.unix_timestamp < ctx.accounts.user_profile.stake_lock_end
+            && ctx.accounts.game_state.token_migration == TokenMigration::Idle;
+        let penalty = if early { bps_of(amount, EARLY_UNSTAKE_PENALTY_BPS)? } else { 0 };
+
+        let bump = ctx.accounts.game_state.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+
+        // The stake vault holds exactly the stakes, so recipients bear any transfer fee
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.stake_vault.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.game_state.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            amount - penalty,
+            signer_seeds,
+        )?;
+
+        if penalty > 0 {
+            transfer_tokens(
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.stake_vault.to_account_info(),
+                &ctx.accounts.token_vault.to_account_info(),
+                &ctx.accounts.game_state.to_account_info(),
+                &ctx.accounts.token_mint.to_account_info(),
+                penalty,
+                signer_seeds,
+            )?;
+        }
+
+        release_stake(
+            &mut ctx.accounts.game_state,
+            &mut ctx.accounts.user_profile,
+            clock.unix_timestamp,
+        )?;
+
+        msg!("Unstaked {} tokens (penalty {})", amount - penalty, penalty);
+        Ok(())
+    }
+
+    /// Create a guild led by the caller, with a guild treasury fed by `tax_bps` of members' claims
+    pub fn create_guild(ctx: Context<CreateGuild>, name: String, tax_bps: u16) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= MAX_GUILD_NAME_LEN,
+            ColonyError::InvalidGuildName
+        );
+        require!(tax_bps <= MAX_GUILD_TAX_BPS, ColonyError::InvalidGuildTax);
+        require!(
+            ctx.accounts.user_profile.guild == Pubkey::default(),
+            ColonyError::AlreadyInGuild
+        );
+
+        let guild = &mut ctx.accounts.guild;
+        guild.leader = ctx.accounts.user.key();
+        guild.name = name;
+        guild.tax_bps = tax_bps;
+        guild.members = vec![ctx.accounts.user.key()];
+        guild.member_levels = vec![0];
+        guild.total_land_levels = 0;
+        guild.bonus_bps = 0;
+        guild.bump = ctx.bumps.guild;
+
+        ctx.accounts.user_profile.guild = guild.key();
+
+        msg!("Guild '{}' created by {}", guild.name, guild.leader);
+        Ok(())
+    }
+
+    /// Invite a player to the guild (leader only, pays the invite rent until it is used or revoked)
+    pub fn invite_to_guild(ctx: Context<InviteToGuild>, member: Pubkey) -> Result<()> {
+        let invite = &mut ctx.accounts.invite;
+        invite.guild = ctx.accounts.guild.key();
+        invite.member = member;
+        invite.bump = ctx.bumps.invite;
+
+        msg!("{} invited to guild '{}'", member, ctx.accounts.guild.name);
+        Ok(())
+    }
+
+    /// Revoke an unused guild invite (leader only)
+    pub fn revoke_guild_invite(ctx: Context<RevokeGuildInvite>, member: Pubkey) -> Result<()> {
+        msg!("Invite of {} to guild '{}' revoked", member, ctx.accounts.guild.name);
+        Ok(())
+        // invite account is closed by Anchor's `close = leader` constraint
+    }
+
+    /// Join a guild with the leader's invite, which is used up (the new member's lands count
+    /// towards the bonus once refreshed)
+    pub fn join_guild(ctx: Context<JoinGuild>) -> Result<()> {
+        require!(
+            ctx.accounts.user_profile.guild == Pubkey::default(),
+            ColonyError::AlreadyInGuild
+        );
+
+        let guild = &mut ctx.accounts.guild;
+        require!(guild.members.len() < MAX_GUILD_MEMBERS, ColonyError::GuildFull);
+        guild.members.push(ctx.accounts.user.key());
+        guild.member_levels.push(0);
+
+        ctx.accounts.user_profile.guild = guild.key();
+
+        msg!("{} joined guild '{}'", ctx.accounts.user.key(), guild.name);
+        Ok(())
+        // invite account is closed by Anchor's `close = leader` constraint
+    }
+
+    /// Leave a guild (the leader cannot leave, see dissolve_guild)
+    pub fn leave_guild(ctx: Context<LeaveGuild>) -> Result<()> {
+        let user = ctx.accounts.user.key();
+        let guild = &mut ctx.accounts.guild;
+        require!(guild.leader != user, ColonyError::GuildLeaderCannotLeave);
+
+        let index = guild
+            .members
+            .iter()
+            .position(|member| *member == user)
+            .ok_or(ColonyError::NotGuildMember)?;
+        guild.members.remove(index);
+        guild.member_levels.remove(index);
+        update_guild_bonus(guild);
+
+        ctx.accounts.user_profile.guild = Pubkey::default();
+
+        msg!("{} left guild '{}'", user, guild.name);
+        Ok(())
+    }
+
+    /// Remove a member from the guild (leader only, the leader cannot be removed)
+    pub fn kick_guild_member(ctx: Context<KickGuildMember>) -> Result<()> {
+        let member = ctx.accounts.member_profile.owner;
+        let guild = &mut ctx.accounts.guild;
+        require!(guild.leader != member, ColonyError::GuildLeaderCannotLeave);
+
+        let index = guild
+            .members
+            .iter()
+            .position(|m| *m == member)
+            .ok_or(ColonyError::NotGuildMember)?;
+        guild.members.remove(index);
+        guild.member_levels.remove(index);
+        update_guild_bonus(guild);
+
+        ctx.accounts.member_profile.guild = Pubkey::default();
+
+        msg!("{} removed from guild '{}'", member, guild.name);
+        Ok(())
+    }
+
+    /// Dissolve the guild once the leader is its last member: the treasury is paid to
+    /// `destination` and the guild and its vault are closed (leader only)
+    pub fn dissolve_guild(ctx: Context<DissolveGuild>) -> Result<()> {
+        require!(ctx.accounts.guild.members.len() == 1, ColonyError::GuildNotEmpty);
+
+        let bump = ctx.accounts.game_state.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+        let treasury = ctx.accounts.guild_vault.amount;
+        if treasury > 0 {
+            transfer_tokens(
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.guild_vault.to_account_info(),
+                &ctx.accounts.destination.to_account_info(),
+                &ctx.accounts.game_state.to_account_info(),
+                &ctx.accounts.token_mint.to_account_info(),
+                treasury,
+                signer_seeds,
+            )?;
+        }
+        close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.guild_vault.to_account_info(),
+                destination: ctx.accounts.leader.to_account_info(),
+                authority: ctx.accounts.game_state.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        ctx.accounts.leader_profile.guild = Pubkey::default();
+
+        msg!("Guild '{}' dissolved, {} tokens paid out", ctx.accounts.guild.name, treasury);
+        Ok(())
+        // guild account is closed by Anchor's `close = leader` constraint
+    }
+
+    /// Set the guild tax on members' claims (leader only)
+    pub fn set_guild_tax(ctx: Context<ManageGuild>, tax_bps: u16) -> Result<()> {
+        require!(tax_bps <= MAX_GUILD_TAX_BPS, ColonyError::InvalidGuildTax);
+        let guild = &mut ctx.accounts.guild;
+        guild.tax_bps = tax_bps;
+        msg!("Guild '{}' tax set to {} bps", guild.name, tax_bps);
+        Ok(())
+    }
+
+    /// Recount one member's land levels towards the guild bonus. All of the member's LandData
+    /// accounts must be passed as remaining accounts in increasing land_id order (anyone can call).
+    pub fn refresh_guild_bonus<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefreshGuildBonus<'info>>,
+    ) -> Result<()> {
+        let member = ctx.accounts.member_profile.owner;
+        require!(
+            ctx.remaining_accounts.len() == ctx.accounts.member_profile.lands_owned as usize,
+            ColonyError::LandCountMismatch
+        );
+
+        let mut member_levels: u16 = 0;
+        let mut last_land_id = 0;
+        for account_info in ctx.remaining_accounts {
+            let land = Account::<LandData>::try_from(account_info)?;
+            require!(land.land_id > last_land_id, ColonyError::InvalidLandOrder);
+            require!(land.owner == member, ColonyError::NotLandOwner);
+            last_land_id = land.land_id;
+            member_levels += land.level as u16;
+        }
+
+        let guild = &mut ctx.accounts.guild;
+        let index = guild
+            .members
+            .iter()
+            .position(|m| *m == member)
+            .ok_or(ColonyError::NotGuildMember)?;
+        guild.member_levels[index] = member_levels;
+        update_guild_bonus(guild);
+
+        msg!(
+            "Guild '{}' bonus refreshed: {} land levels, +{} bps",
+            guild.name,
+            guild.total_land_levels,
+            guild.bonus_bps
+        );
+        Ok(())
+    }
+
+    /// Withdraw tokens from the guild treasury (leader only)
+    pub fn withdraw_guild_treasury(ctx: Context<WithdrawGuildTreasury>, amount: u64) -> Result<()> {
+        require!(amount > 0, ColonyError::InvalidAmount);
+        require!(
+            ctx.accounts.guild_vault.amount >= amount,
+            ColonyError::InsufficientBalance
+        );
+
+        // Withdraws exactly `amount` from the treasury; the destination bears any transfer fee
+        let bump = ctx.accounts.game_state.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.guild_vault.to_account_info(),
+            &ctx.accounts.destination.to_account_info(),
+            &ctx.accounts.game_state.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            amount,
+            signer_seeds,
+        )?;
+
+        msg!("Withdrew {} tokens from guild '{}'", amount, ctx.accounts.guild.name);
+        Ok(())
+    }
+
+    /// After a token mint migration, pay out the guild treasury still held in the old mint to
+    /// `destination` and close it (leader only). Recreate it with migrate_guild_vault_init.
+    pub fn migrate_guild_vault_close(ctx: Context<MigrateGuildVaultClose>) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::Idle,
+            ColonyError::TokenMigrationInProgress
+        );
+
+        let bump = ctx.accounts.game_state.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+        let treasury = ctx.accounts.guild_vault.amount;
+        if treasury > 0 {
+            transfer_tokens(
+                &ctx.accounts.old_token_program.to_account_info(),
+                &ctx.accounts.guild_vault.to_account_info(),
+                &ctx.accounts.destination.to_account_info(),
+                &ctx.accounts.game_state.to_account_info(),
+                &ctx.accounts.old_token_mint.to_account_info(),
+                treasury,
+                signer_seeds,
+            )?;
+        }
+        close_account(CpiContext::new_with_signer(
+            ctx.accounts.old_token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.guild_vault.to_account_info(),
+                destination: ctx.accounts.leader.to_account_info(),
+                authority: ctx.accounts.game_state.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        msg!(
+            "Guild '{}' treasury of mint {} closed, {} tokens paid out",
+            ctx.accounts.guild.name,
+            ctx.accounts.old_token_mint.key(),
+            treasury
+        );
+        Ok(())
+    }
+
+    /// Recreate a guild treasury closed by migrate_guild_vault_close in the current game token
+    /// mint (leader only, leader pays the rent)
+    pub fn migrate_guild_vault_init(ctx: Context<MigrateGuildVaultInit>) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::Idle,
+            ColonyError::TokenMigrationInProgress
+        );
+
+        msg!(
+            "Guild '{}' treasury recreated for mint {}",
+            ctx.accounts.guild.name,
+            ctx.accounts.token_mint.key()
+        );
+        Ok(())
+    }
+
+    /// Initialize the stake vault PDA (owner only, call after the token vault exists, and again
+    /// after a token mint migration)
+    pub fn init_stake_vault(ctx: Context<InitStakeVault>) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::Idle,
+            ColonyError::TokenMigrationInProgress
+        );
+        check_mint_extensions(&ctx.accounts.token_mint.to_account_info())?;
+        require!(
+            ctx.accounts.token_mint.decimals == TOKEN_DECIMALS,
+            ColonyError::InvalidTokenDecimals
+        );
+
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.stake_vault_bump = ctx.bumps.stake_vault;
+
+        msg!("Stake vault initialized for mint: {}", game_state.token_mint);
+        Ok(())
+    }
+
+    /// Withdraw all SOL from vault (owner only)
+    pub fn withdraw_sol(ctx: Context<WithdrawSol>) -> Result<()> {
+        let amount = ctx.accounts.vault.lamports();
+        require!(amount > 0, ColonyError::InsufficientBalance);
+
+        let bump = ctx.accounts.game_state.vault_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", &[bump]]];
+
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        msg!("Withdrawn {} lamports to authority", amount);
+        Ok(())
+    }
+
+    /// Withdraw all SPL tokens from token vault to authority (owner only)
+    pub fn withdraw_tokens(ctx: Context<WithdrawTokens>) -> Result<()> {
+        let amount = ctx.accounts.token_vault.amount;
+        require!(amount > 0, ColonyError::InsufficientBalance);
+
+        let bump = ctx.accounts.game_state.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_vault.to_account_info(),
+            &ctx.accounts.authority_token_account.to_account_info(),
+            &ctx.accounts.game_state.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            amount,
+            signer_seeds,
+        )?;
+
+        msg!("Withdrawn {} tokens to authority", amount);
+        Ok(())
+    }
+
+    /// Pause/unpause the game (owner only)
+    pub fn set_game_active(ctx: Context<AdminAction>, is_active: bool) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::Idle,
+            ColonyError::TokenMigrationInProgress
+        );
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.is_active = is_active;
+        msg!("Game active status: {}", is_active);
+        Ok(())
+    }
+
+    /// Set craft rates in tokens per whole resource unit, as [ore, water, energy] (owner only, 0 disables)
+    pub fn set_craft_rates(ctx: Context<AdminAction>, rates: [u64; RESOURCE_COUNT]) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.craft_rates = rates;
+        msg!("Craft rates updated to: {:?}", rates);
+        Ok(())
+    }
+
+    /// Set the referrer's cut of referred players' claims in basis points (owner only, 0 disables)
+    pub fn set_referral_bps(ctx: Context<AdminAction>, referral_bps: u16) -> Result<()> {
+        require!(referral_bps as u64 <= BPS_DENOMINATOR, ColonyError::InvalidAmount);
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.referral_bps = referral_bps;
+        msg!("Referral reward set to {} bps", referral_bps);
+        Ok(())
+    }
+
+    /// Set token rewards per achievement, indexed by `Achievement` (owner only, 0 disables)
+    pub fn set_achievement_rewards(
+        ctx: Context<AdminAction>,
+        rewards: [u64; ACHIEVEMENT_COUNT],
+    ) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.achievement_rewards = rewards;
+        msg!("Achievement rewards updated to: {:?}", rewards);
+        Ok(())
+    }
+
+    /// Set the randomness source for random events (owner only). `oracle` is the signer allowed
+    /// to fulfil requests with the Oracle source; Deterministic is predictable and only available
+    /// in builds with the `test-randomness` feature.
+    pub fn set_randomness_source(
+        ctx: Context<AdminAction>,
+        source: RandomnessSource,
+        oracle: Pubkey,
+    ) -> Result<()> {
+        #[cfg(not(feature = "test-randomness"))]
+        require!(
+            source != RandomnessSource::Deterministic,
+            ColonyError::RandomnessSourceUnavailable
+        );
+
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.randomness_source = source;
+        game_state.randomness_oracle = oracle;
+        msg!("Randomness source set to {:?} (oracle {})", source, oracle);
+        Ok(())
+    }
+
+    /// Configure the sale phase (owner only). `Whitelist` restricts buy_land to wallets in the
+    /// Merkle tree with root `merkle_root`, each buying at most `purchase_cap` lands, until
+    /// `whitelist_end`; buy_land is public afterwards. Each whitelist window starts a new
+    /// per-wallet count.
+    pub fn set_sale_phase(
+        ctx: Context<AdminAction>,
+        phase: SalePhase,
+        merkle_root: [u8; 32],
+        whitelist_end: i64,
+        purchase_cap: u8,
+    ) -> Result<()> {
+        if phase == SalePhase::Whitelist {
+            require!(
+                whitelist_end > Clock::get()?.unix_timestamp && purchase_cap > 0,
+                ColonyError::InvalidSalePhase
+            );
+        }
+
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.sale_phase = phase;
+        game_state.whitelist_root = merkle_root;
+        game_state.whitelist_end = whitelist_end;
+        game_state.whitelist_purchase_cap = purchase_cap;
+        if phase == SalePhase::Whitelist {
+            game_state.whitelist_window = game_state
+                .whitelist_window
+                .checked_add(1)
+                .ok_or(ColonyError::Overflow)?;
+        }
+        msg!("Sale phase set to {:?} (whitelist until {}, cap {})", phase, whitelist_end, purchase_cap);
+        Ok(())
+    }
+
+    /// Whitelist an alternative payment mint (owner only). `price_per_token` is the amount of
+    /// the payment mint (base units) charged per whole game token of a price; 0 disables the
+    /// mint. Payments go to `treasury`, a token account of the payment mint.
+    pub fn set_payment_mint(
+        ctx: Context<SetPaymentMint>,
+        price_per_token: u64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        check_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+
+        let payment_mint = &mut ctx.accounts.payment_mint;
+        payment_mint.mint = ctx.accounts.mint.key();
+        payment_mint.price_per_token = price_per_token;
+        payment_mint.treasury = treasury;
+        payment_mint.bump = ctx.bumps.payment_mint;
+
+        msg!(
+            "Payment mint {} set to {} per token (treasury {})",
+            payment_mint.mint,
+            price_per_token,
+            treasury
+        );
+        Ok(())
+    }
+
+    /// Set the SOL price of a land for buy_land_with_sol (owner only, 0 disables SOL payments)
+    pub fn set_land_price_lamports(ctx: Context<AdminAction>, price: u64) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.land_price_lamports = price;
+        msg!("Land SOL price set to {} lamports", price);
+        Ok(())
+    }
+
+    /// Split land purchase and upgrade payments (owner only): `vault_bps` goes to the token
+    /// vault to fund claims, `team_bps` to `team_wallet` (a game token account) and the rest
+    /// is burned. Both 0 burns everything.
+    pub fn set_revenue_split(
+        ctx: Context<AdminAction>,
+        vault_bps: u16,
+        team_bps: u16,
+        team_wallet: Pubkey,
+    ) -> Result<()> {
+        require!(
+            vault_bps as u64 + team_bps as u64 <= BPS_DENOMINATOR,
+            ColonyError::InvalidRevenueSplit
+        );
+        require!(
+            team_bps == 0 || team_wallet != Pubkey::default(),
+            ColonyError::InvalidRevenueSplit
+        );
+
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.revenue_vault_bps = vault_bps;
+        game_state.revenue_team_bps = team_bps;
+        game_state.team_wallet = team_wallet;
+        msg!(
+            "Revenue split set: {} bps to vault, {} bps to team ({}), rest burned",
+            vault_bps,
+            team_bps,
+            team_wallet
+        );
+        Ok(())
+    }
+
+    /// Set buy_land rate limits (owner only): minimum seconds between purchases per wallet and
+    /// maximum purchases per slot across all wallets (0 disables either)
+    pub fn set_purchase_limits(
+        ctx: Context<AdminAction>,
+        purchase_cooldown: i64,
+        slot_purchase_cap: u16,
+    ) -> Result<()> {
+        require!(purchase_cooldown >= 0, ColonyError::InvalidAmount);
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.purchase_cooldown = purchase_cooldown;
+        game_state.slot_purchase_cap = slot_purchase_cap;
+        msg!(
+            "Purchase limits set: {}s per wallet, {} per slot",
+            purchase_cooldown,
+            slot_purchase_cap
+        );
+        Ok(())
+    }
+
+    /// Start the next season (owner only). Players score tokens claimed plus tokens spent on
+    /// upgrades until `duration` seconds from now; winners claim `prize_pool` from token vault
+    /// once the season is closed.
+    pub fn start_season(ctx: Context<StartSeason>, duration: i64, prize_pool: u64) -> Result<()> {
+        require!(duration > 0, ColonyError::InvalidAmount);
+        require!(!ctx.accounts.game_state.season_open, ColonyError::SeasonOpen);
+
+        let clock = Clock::get()?;
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.current_season = game_state
+            .current_season
+            .checked_add(1)
+            .ok_or(ColonyError::Overflow)?;
+        game_state.season_open = true;
+
+        let season = &mut ctx.accounts.season;
+        season.season_id = game_state.current_season;
+        season.start_time = clock.unix_timestamp;
+        season.end_time = clock
+            .unix_timestamp
+            .checked_add(duration)
+            .ok_or(ColonyError::Overflow)?;
+        season.prize_pool = prize_pool;
+        season.leaderboard = Vec::new();
+        season.closed = false;
+        season.bump = ctx.bumps.season;
+        season.prizes_claimed = 0;
+
+        msg!(
+            "Season {} started, ends at {} (prize pool {})",
+            season.season_id,
+            season.end_time,
+            prize_pool
+        );
+        Ok(())
+    }
+
+    /// Close the current season after it ends, freezing its leaderboard (owner only).
+    /// Winners then claim their prizes with claim_season_prize.
+    pub fn close_season(ctx: Context<CloseSeason>) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp >= ctx.accounts.season.end_time,
+            ColonyError::SeasonNotEnded
+        );
+
+        ctx.accounts.season.closed = true;
+        ctx.accounts.game_state.season_open = false;
+
+        msg!(
+            "Season {} closed with {} ranked players",
+            ctx.accounts.season.season_id,
+            ctx.accounts.season.leaderboard.len()
+        );
+        Ok(())
+    }
+
+    /// Claim the caller's prize of a closed season from token vault, by leaderboard rank
+    pub fn claim_season_prize(ctx: Context<ClaimSeasonPrize>, _season_id: u32) -> Result<()> {
+        let season = &ctx.accounts.season;
+        require!(season.closed, ColonyError::SeasonOpen);
+        let rank = season
+            .leaderboard
+            .iter()
+            .position(|entry| entry.player == ctx.accounts.user.key())
+            .ok_or(ColonyError::NoSeasonPrize)?;
+        let bit = 1u16 << rank;
+        require!(season.prizes_claimed & bit == 0, ColonyError::SeasonPrizeClaimed);
+
+        let prize = bps_of(season.prize_pool, SEASON_PRIZE_SPLIT_BPS[rank])?;
+        require!(prize > 0, ColonyError::NoSeasonPrize);
+
+        ctx.accounts.season.prizes_claimed |= bit;
+
+        vault_transfer(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.token_vault.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.user_token_account.to_account_info(),
+            prize,
+        )?;
+
+        msg!(
+            "Claimed {} tokens for rank {} of season {}",
+            prize,
+            rank + 1,
+            ctx.accounts.season.season_id
+        );
+        Ok(())
+    }
+
+    /// Set the base URI for land NFT metadata, resolved as `{base}{land_id}.json` (owner only)
+    pub fn set_land_nft_uri(ctx: Context<AdminAction>, uri: String) -> Result<()> {
+        require!(uri.len() <= MAX_LAND_NFT_URI_LEN, ColonyError::UriTooLong);
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.land_nft_uri = uri;
+        msg!("Land NFT URI updated to: {}", game_state.land_nft_uri);
+        Ok(())
+    }
+
+    /// Start switching the game token to `new_mint` (owner only). Pauses the game and waits for
+    /// the token and stake vaults to be drained (withdraw_tokens, players unstake or the owner
+    /// returns their stakes, then sweeps the stake vault), then
+    /// migrate_token_mint_close_vaults and migrate_token_mint_finish complete the switch.
+    /// Without existing vaults (first setup) it goes straight to finish. Early unstakes carry no
+    /// penalty meanwhile. Guild treasuries are switched afterwards by their leaders
+    /// (migrate_guild_vault_close, then migrate_guild_vault_init).
+    pub fn migrate_token_mint_start(ctx: Context<AdminAction>, new_mint: Pubkey) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        require!(
+            game_state.token_migration == TokenMigration::Idle,
+            ColonyError::TokenMigrationInProgress
+        );
+        let has_vaults = game_state.token_vault_bump != 0 || game_state.stake_vault_bump != 0;
+        require!(
+            new_mint != Pubkey::default() && (new_mint != game_state.token_mint || !has_vaults),
+            ColonyError::InvalidTokenMint
+        );
+
+        game_state.pending_token_mint = new_mint;
+        game_state.resume_active = game_state.is_active;
+        game_state.is_active = false;
+        game_state.token_migration = if has_vaults {
+            TokenMigration::Draining
+        } else {
+            TokenMigration::VaultsClosed
+        };
+
+        msg!("Token mint migration to {} started ({:?})", new_mint, game_state.token_migration);
+        Ok(())
+    }
+
+    /// Abort a token mint migration before its vaults are closed, resuming the game (owner only)
+    pub fn migrate_token_mint_cancel(ctx: Context<AdminAction>) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        require!(
+            game_state.token_migration == TokenMigration::Draining,
+            ColonyError::InvalidTokenMigrationStep
+        );
+
+        game_state.token_migration = TokenMigration::Idle;
+        game_state.pending_token_mint = Pubkey::default();
+        game_state.is_active = game_state.resume_active;
+
+        msg!("Token mint migration cancelled");
+        Ok(())
+    }
+
+    /// Return a player's stake without penalty while the migration drains (owner only)
+    pub fn migrate_token_mint_return_stake(ctx: Context<MigrateTokenMintReturnStake>) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::Draining,
+            ColonyError::InvalidTokenMigrationStep
+        );
+        let amount = ctx.accounts.staker_profile.staked_amount;
+        require!(amount > 0, ColonyError::NoActiveStake);
+
+        let bump = ctx.accounts.game_state.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.stake_vault.to_account_info(),
+            &ctx.accounts.staker_token_account.to_account_info(),
+            &ctx.accounts.game_state.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            amount,
+            signer_seeds,
+        )?;
+
+        let clock = Clock::get()?;
+        release_stake(
+            &mut ctx.accounts.game_state,
+            &mut ctx.accounts.staker_profile,
+            clock.unix_timestamp,
+        )?;
+
+        msg!("Returned {} staked tokens to {}", amount, ctx.accounts.staker.key());
+        Ok(())
+    }
+
+    /// Withdraw what is left in the stake vault once every stake is returned (tokens sent to it
+    /// directly), so it can be closed (owner only, migration step 1)
+    pub fn migrate_token_mint_sweep_stake_vault(
+        ctx: Context<MigrateTokenMintSweepStakeVault>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::Draining,
+            ColonyError::InvalidTokenMigrationStep
+        );
+        require!(ctx.accounts.game_state.total_staked == 0, ColonyError::StakesOutstanding);
+        let amount = ctx.accounts.stake_vault.amount;
+        require!(amount > 0, ColonyError::InsufficientBalance);
+
+        let bump = ctx.accounts.game_state.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.stake_vault.to_account_info(),
+            &ctx.accounts.authority_token_account.to_account_info(),
+            &ctx.accounts.game_state.to_account_info(),
+            &ctx.accounts.token_mint.to_account_info(),
+            amount,
+            signer_seeds,
+        )?;
+
+        msg!("Swept {} tokens from the stake vault to authority", amount);
+        Ok(())
+    }
+
+    /// Close the drained token and stake vaults of the old mint (owner only, migration step 2)
+    pub fn migrate_token_mint_close_vaults(ctx: Context<MigrateTokenMintCloseVaults>) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::Draining,
+            ColonyError::InvalidTokenMigrationStep
+        );
+
+        let bump = ctx.accounts.game_state.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+        let vaults = [
+            (ctx.accounts.game_state.token_vault_bump, &ctx.accounts.token_vault),
+            (ctx.accounts.game_state.stake_vault_bump, &ctx.accounts.stake_vault),
+        ];
+        for (vault_bump, vault) in vaults {
+            if vault_bump == 0 {
+                continue;
+            }
+            let vault = vault.as_ref().ok_or(ColonyError::VaultAccountRequired)?;
+            require!(vault.amount == 0, ColonyError::VaultNotEmpty);
+
+            anchor_spl::token_interface::close_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token_interface::CloseAccount {
+                        account: vault.to_account_info(),
+                        destination: ctx.accounts.authority.to_account_info(),
+                        authority: ctx.accounts.game_state.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+            )?;
+        }
+
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.token_vault_bump = 0;
+        game_state.stake_vault_bump = 0;
+        game_state.token_migration = TokenMigration::VaultsClosed;
+
+        msg!("Token and stake vaults closed for mint {}", game_state.token_mint);
+        Ok(())
+    }
+
+    /// Switch to the pending mint, create its token vault and resume the game (owner only,
+    /// final migration step). The stake vault is re-created with init_stake_vault. The team
+    /// wallet holds the old mint, so it is cleared and the team share is burned until
+    /// set_revenue_split names a new one.
+    pub fn migrate_token_mint_finish(ctx: Context<MigrateTokenMintFinish>) -> Result<()> {
+        require!(
+            ctx.accounts.game_state.token_migration == TokenMigration::VaultsClosed,
+            ColonyError::InvalidTokenMigrationStep
+        );
+        check_mint_extensions(&ctx.accounts.new_token_mint.to_account_info())?;
+        require!(
+            ctx.accounts.new_token_mint.decimals == TOKEN_DECIMALS,
+            ColonyError::InvalidTokenDecimals
+        );
+
+        let game_state = &mut ctx.accounts.game_state;
+        let old_mint = game_state.token_mint;
+        game_state.token_mint = game_state.pending_token_mint;
+        game_state.pending_token_mint = Pubkey::default();
+        game_state.team_wallet = Pubkey::default();
+        game_state.revenue_team_bps = 0;
+        game_state.token_vault_bump = ctx.bumps.token_vault;
+        game_state.token_migration = TokenMigration::Idle;
+        game_state.is_active = game_state.resume_active;
+
+        msg!("Token mint migrated from {} to {}", old_mint, game_state.token_mint);
+        Ok(())
+    }
+
+    /// Upgrade GameState to the current layout version (owner only, authority pays the rent)
+    pub fn migrate_game_state(ctx: Context<MigrateGameState>) -> Result<()> {
+        let account = ctx.accounts.game_state.to_account_info();
+        let game_state = load_versioned::<GameState>(&account)?;
+        require!(
+            ctx.accounts.authority.key() == game_state.authority,
+            ColonyError::Unauthorized
+        );
+
+        let (from, to) = migrate_versioned(
+            &account,
+            game_state,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        msg!("GameState migrated from v{} to v{}", from, to);
+        Ok(())
+    }
+
+    /// Upgrade a UserProfile to the current layout version (profile owner pays the rent).
+    /// Deployed v1 profiles don't deserialize into the current layout, so this has to run
+    /// before any other instruction loads the profile; the SDK prepends it to the user's
+    /// next transaction.
+    pub fn migrate_user_profile(ctx: Context<MigrateUserProfile>) -> Result<()> {
+        let account = ctx.accounts.user_profile.to_account_info();
+        let user_profile = load_versioned::<UserProfile>(&account)?;
+
+        let (from, to) = migrate_versioned(
+            &account,
+            user_profile,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        msg!("UserProfile for {} migrated from v{} to v{}", ctx.accounts.user.key(), from, to);
+        Ok(())
+    }
+
+    /// Upgrade a LandData account to the current layout version (anyone can pay the rent)
+    pub fn migrate_land_data(ctx: Context<MigrateLandData>, land_id: u16) -> Result<()> {
+        let account = ctx.accounts.land_data.to_account_info();
+        let land_data = load_versioned::<LandData>(&account)?;
+
+        let (from, to) = migrate_versioned(
+            &account,
+            land_data,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        msg!("LandData #{} migrated from v{} to v{}", land_id, from, to);
+        Ok(())
+    }
+
+    /// Create the reserved lands bitmap (admin only); buy_land checks it from then on
+    pub fn init_reserved_lands(ctx: Context<InitReservedLands>) -> Result<()> {
+        ctx.accounts.reserved_lands.load_init()?;
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.reserved_lands_enabled = true;
+        game_state.reserved_lands_bump = ctx.bumps.reserved_lands;
+        msg!("Reserved lands bitmap initialized");
+        Ok(())
+    }
+
+    /// Reserve or release the land_ids `start..=end` (admin only)
+    pub fn set_reserved_range(
+        ctx: Context<SetReservedRange>,
+        start: u16,
+        end: u16,
+        reserved: bool,
+    ) -> Result<()> {
+        require!(
+            start > 0 && start <= end && end <= MAX_LAND_ID,
+            ColonyError::InvalidLandId
+        );
+
+        let mut reserved_lands = ctx.accounts.reserved_lands.load_mut()?;
+        set_reserved_bits(&mut reserved_lands.bits, start, end, reserved);
+
+        msg!(
+            "Lands #{}-#{} {}",
+            start,
+            end,
+            if reserved { "reserved" } else { "released" }
+        );
+        Ok(())
+    }
+
+    /// Grant a land to `recipient` without payment (admin only, emits LandGranted). Works for
+    /// reserved land_ids; the recipient's MAX_LANDS_PER_USER limit still applies.
+    pub fn admin_grant_land(ctx: Context<AdminGrantLand>, land_id: u16) -> Result<()> {
+        require!(land_id > 0 && land_id <= MAX_LAND_ID, ColonyError::InvalidLandId);
+        require!(
+            ctx.accounts.recipient_profile.lands_owned < MAX_LANDS_PER_USER,
+            ColonyError::MaxLandsReached
+        );
+
+        let recipient = ctx.accounts.recipient.key();
+        let clock = Clock::get()?;
+
+        init_land(&mut ctx.accounts.land_data, recipient, land_id, ctx.bumps.land_data, clock.unix_timestamp);
+
+        // Mint the land NFT to the recipient
+        mint_land_nft_to_holder(
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.recipient.to_account_info(),
+            &ctx.accounts.game_state,
+            &ctx.accounts.land_mint.to_account_info(),
+            ctx.bumps.land_mint,
+            &ctx.accounts.recipient_land_token_account.to_account_info(),
+            &ctx.accounts.nft_token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.land_data,
+        )?;
+
+        record_land_purchase(
+            &mut ctx.accounts.recipient_profile,
+            None,
+            &mut ctx.accounts.game_state,
+            recipient,
+            ctx.bumps.recipient_profile,
+        )?;
+
+        emit!(LandGranted {
+            authority: ctx.accounts.authority.key(),
+            land_id,
+            recipient,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Land #{} granted to {}", land_id, recipient);
+        Ok(())
+    }
+
+    /// Set a land's level (admin only, emits LandLevelSet). Pending earnings are settled at the
+    /// old level first and stay claimable. Unlocks no achievement, milestones are only reached
+    /// through purchases and upgrades.
+    pub fn admin_set_level(ctx: Context<AdminSetLevel>, land_id: u16, level: u8) -> Result<()> {
+        require!((1..=MAX_LEVEL).contains(&level), ColonyError::InvalidLevel);
+        require!(ctx.accounts.land_data.mega_plot == 0, ColonyError::LandMerged);
+
+        let clock = Clock::get()?;
+        let land_data = &mut ctx.accounts.land_data;
+        let owner_profile = &mut ctx.accounts.owner_profile;
+
+        let pending = settle_land(land_data, owner_profile, clock.unix_timestamp)?;
+        land_data.fixed_earnings = pending;
+
+        let old_level = land_data.level;
+        land_data.level = level;
+
+        emit!(LandLevelSet {
+            authority: ctx.accounts.authority.key(),
+            land_id,
+            owner: land_data.owner,
+            old_level,
+            new_level: level,
+            settled_earnings: pending,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Land #{} level set from {} to {}", land_id, old_level, level);
+        Ok(())
+    }
+
+    /// Close a land account, burning its NFT and closing the mint, and return rent to authority (admin only).
+    /// While the NFT exists its holder co-signs the burn, the program can't move it on its own.
+    /// A rental or rental offer of the land is closed too, without settling its earnings.
+    pub fn admin_close_land(ctx: Context<AdminCloseLand>, _land_id: u16) -> Result<()> {
+        // Decrement user's lands_owned
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.lands_owned = user_profile
+            .lands_owned
+            .checked_sub(1)
+            .ok_or(ColonyError::Overflow)?;
+
+        // Decrement game_state.total_lands_sold
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.total_lands_sold = game_state
+            .total_lands_sold
+            .checked_sub(1)
+            .ok_or(ColonyError::Overflow)?;
+
+        // Burn the land NFT and close its mint so the land can be sold or granted again
+        let land_mint = ctx.accounts.land_mint.to_account_info();
+        if !land_mint.data_is_empty() {
+            close_land_mint(
+                &ctx.accounts.game_state,
+                &land_mint,
+                ctx.accounts.holder.as_ref().map(|holder| holder.to_account_info()),
+                ctx.accounts.holder_token_account.as_ref().map(|account| account.to_account_info()),
+                &ctx.accounts.authority.to_account_info(),
+                &ctx.accounts.nft_token_program.to_account_info(),
+            )?;
+        }
+
+        // Close a rental or rental offer of the land with it, returning its rent to the offer creator
+        let rental_info = ctx.accounts.rental.to_account_info();
+        if !rental_info.data_is_empty() {
+            let rental = Rental::try_deserialize(&mut &rental_info.try_borrow_data()?[..])?;
+            let rental_owner = ctx
+                .accounts
+                .rental_owner
+                .as_ref()
+                .ok_or(ColonyError::RentalOwnerRequired)?;
+            require_keys_eq!(rental_owner.key(), rental.owner, ColonyError::RentalOwnerRequired);
+            close_program_account(&rental_info, &rental_owner.to_account_info())?;
+        }
+
+        msg!("Land #{} closed by admin", ctx.accounts.land_data.land_id);
+        Ok(())
+        // land_data account is closed by Anchor's `close = authority` constraint
+    }
+
+    /// Close a user profile account and return rent to authority (admin only)
+    pub fn admin_close_user_profile(ctx: Context<AdminCloseUserProfile>) -> Result<()> {
+        require!(
+            ctx.accounts.user_profile.lands_owned == 0,
+            ColonyError::UserHasLands
+        );
+
+        msg!("User profile closed for {}", ctx.accounts.user_profile.owner);
+        Ok(())
+        // user_profile account is closed by Anchor's `close = authority` constraint
+    }
+
+    /// Get pending earnings for a land (view function via simulate)
+    pub fn get_pending_earnings(ctx: Context<GetPendingEarnings>, _land_id: u16) -> Result<u64> {
+        let land_data = &ctx.accounts.land_data;
+        let clock = Clock::get()?;
+        let earnings = match &ctx.accounts.user_profile {
+            Some(owner_profile) => {
+                calculate_boosted_earnings(land_data, owner_profile, clock.unix_timestamp)?
+            }
+            None => calculate_earnings(land_data, clock.unix_timestamp)?,
+        };
+        msg!("Pending earnings: {}", earnings);
+        Ok(earnings)
+    }
+
+    /// Get a player's current claim streak in days, 0 if a day was missed (view function via simulate)
+    pub fn get_claim_streak(ctx: Context<GetClaimStreak>) -> Result<u16> {
+        let clock = Clock::get()?;
+        let streak = current_claim_streak(&ctx.accounts.user_profile, clock.unix_timestamp);
+        msg!("Claim streak: {} days (+{} bps)", streak, streak_bonus_bps(streak));
+        Ok(streak)
+    }
+
+    /// Get pending resources for a land as [ore, water, energy] (view function via simulate)
+    pub fn get_pending_resources(
+        ctx: Context<GetPendingEarnings>,
+        _land_id: u16,
+    ) -> Result<[u64; RESOURCE_COUNT]> {
+        let land_data = &ctx.accounts.land_data;
+        let clock = Clock::get()?;
+        let produced = calculate_resources(land_data, clock.unix_timestamp)?;
+        msg!("Pending resources: {:?}", produced);
+        Ok(produced)
+    }
+}
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+fn calculate_earnings(land: &LandData, current_time: i64) -> Result<u64> {
+    if current_time < MINING_START_TIME {
+        return Ok(land.fixed_earnings);
+    }
+
+    let time_passed = (current_time - land.last_checkout) as u64;
+    let speed = earning_speed(land);
+
+    let mut earned = speed
+        .checked_mul(time_passed)
+        .ok_or(ColonyError::Overflow)?
+        .checked_div(SECONDS_PER_DAY)
+        .ok_or(ColonyError::Overflow)?;
+
+    // Random event modifier over the part of the period the event was active
+    let event_end = current_time.min(land.event_end);
+    if land.event_modifier_bps != 0 && event_end > land.last_checkout {
+        let affected = speed
+            .checked_mul((event_end - land.last_checkout) as u64)
+            .ok_or(ColonyError::Overflow)?
+            / SECONDS_PER_DAY;
+        let delta = bps_of(affected, land.event_modifier_bps.unsigned_abs())?;
+        earned = if land.event_modifier_bps > 0 {
+            earned.checked_add(delta).ok_or(ColonyError::Overflow)?
+        } else {
+            earned.saturating_sub(delta)
+        };
+    }
+
+    earned
+        .checked_add(land.fixed_earnings)
+        .ok_or(ColonyError::Overflow.into())
+}
+
+/// Token earning speed per day: the level speed, or the mega-plot's combined speed on its
+/// anchor land (other merged lands don't earn)
+fn earning_speed(land: &LandData) -> u64 {
+    if land.mega_plot == 0 {
+        EARNING_SPEEDS[(land.level - 1) as usize]
+    } else {
+        land.mega_speed
+    }
+}
+
+/// Staking boost earned by a land: the owner's stake bonus applied to the part of the
+/// earning period that overlaps the stake's boost window
+fn calculate_stake_bonus(land: &LandData, owner_profile: &UserProfile, current_time: i64) -> Result<u64> {
+    if owner_profile.stake_bonus_bps == 0 || current_time < MINING_START_TIME {
+        return Ok(0);
+    }
+
+    let start = land.last_checkout.max(owner_profile.stake_start);
+    let end = current_time.min(owner_profile.stake_lock_end);
+    if end <= start {
+        return Ok(0);
+    }
+
+    let speed = earning_speed(land);
+    let boosted = speed
+        .checked_mul((end - start) as u64)
+        .ok_or(ColonyError::Overflow)?
+        .checked_div(SECONDS_PER_DAY)
+        .ok_or(ColonyError::Overflow)?;
+
+    bps_of(boosted, owner_profile.stake_bonus_bps)
+}
+
+/// Pending earnings including the owner's staking boost
+fn calculate_boosted_earnings(land: &LandData, owner_profile: &UserProfile, current_time: i64) -> Result<u64> {
+    calculate_earnings(land, current_time)?
+        .checked_add(calculate_stake_bonus(land, owner_profile, current_time)?)
+        .ok_or(ColonyError::Overflow.into())
+}
+
+/// End a profile's stake once its tokens have left the stake vault. stake_start and the bonus
+/// are kept so unclaimed earnings stay boosted up to now.
+fn release_stake(game_state: &mut GameState, profile: &mut UserProfile, current_time: i64) -> Result<()> {
+    game_state.total_staked = game_state
+        .total_staked
+        .checked_sub(profile.staked_amount)
+        .ok_or(ColonyError::Overflow)?;
+    profile.staked_amount = 0;
+    profile.stake_lock_end = profile.stake_lock_end.min(current_time);
+    Ok(())
+}
+
+/// Settle each of the owner's lands still holding boost from the profile's previous stake into
+/// its pending earnings. Rented lands are skipped, their earnings are boosted by the renter.
+fn settle_stake_bonus<'info>(
+    profile: &mut UserProfile,
+    lands: &'info [AccountInfo<'info>],
+    current_time: i64,
+) -> Result<()> {
+    require!(lands.len() == profile.lands_owned as usize, ColonyError::LandCountMismatch);
+
+    let mut last_land_id = 0;
+    for account_info in lands {
+        let mut land = Account::<LandData>::try_from(account_info)?;
+        require!(land.land_id > last_land_id, ColonyError::InvalidLandOrder);
+        require!(land.owner == profile.owner, ColonyError::NotLandOwner);
+        last_land_id = land.land_id;
+
+        if land.rental_end != 0 || calculate_stake_bonus(&land, profile, current_time)? == 0 {
+            continue;
+        }
+        let pending = settle_land(&mut land, profile, current_time)?;
+        land.fixed_earnings = pending;
+        land.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+/// Earnings boost for a stake, weighted by amount and lock duration (each capped at full weight)
+fn stake_bonus_bps(amount: u64, duration: i64) -> u16 {
+    let amount_weight = amount.min(STAKE_FULL_BOOST_AMOUNT) as u128;
+    let duration_weight = duration.min(MAX_STAKE_DURATION) as u128;
+    (MAX_STAKE_BONUS_BPS as u128 * amount_weight * duration_weight
+        / (STAKE_FULL_BOOST_AMOUNT as u128 * MAX_STAKE_DURATION as u128)) as u16
+}
+
+fn unlock_achievement(profile: &mut UserProfile, achievement: Achievement) {
+    let bit = 1u32 << achievement as u32;
+    if profile.achievements & bit == 0 {
+        profile.achievements |= bit;
+        msg!("Achievement unlocked: {:?}", achievement);
+    }
+}
+
+fn unlock_land_count_achievements(profile: &mut UserProfile) {
+    if profile.lands_owned >= 1 {
+        unlock_achievement(profile, Achievement::FirstLand);
+    }
+    if profile.lands_owned >= MAX_LANDS_PER_USER {
+        unlock_achievement(profile, Achievement::TenLands);
+    }
+}
+
+/// Extend the claim streak for a claim on a new UTC day, restarting it if a day was missed
+fn update_claim_streak(profile: &mut UserProfile, current_time: i64) {
+    let today = current_time / SECONDS_PER_DAY as i64;
+    if profile.claim_streak > 0 && today == profile.last_claim_day {
+        return;
+    }
+    profile.claim_streak = if profile.claim_streak > 0 && today == profile.last_claim_day + 1 {
+        profile.claim_streak.saturating_add(1)
+    } else {
+        1
+    };
+    profile.last_claim_day = today;
+}
+
+/// Streak still counts if the last claim was today or yesterday
+fn current_claim_streak(profile: &UserProfile, current_time: i64) -> u16 {
+    let today = current_time / SECONDS_PER_DAY as i64;
+    if today - profile.last_claim_day <= 1 {
+        profile.claim_streak
+    } else {
+        0
+    }
+}
+
+fn streak_bonus_bps(streak: u16) -> u16 {
+    (streak.saturating_sub(1) as u64 * STREAK_BONUS_BPS_PER_DAY as u64)
+        .min(MAX_STREAK_BONUS_BPS as u64) as u16
+}
+
+/// Add `amount` to a player's score for an open season and update the leaderboard.
+/// Scores from an earlier season are reset on the player's first scoring action in a new one.
+fn record_season_score(
+    season: &mut Season,
+    profile: &mut UserProfile,
+    current_time: i64,
+    amount: u64,
+) -> Result<()> {
+    if season.closed || current_time < season.start_time || current_time >= season.end_time {
+        return Ok(());
+    }
+
+    if profile.season_id != season.season_id {
+        profile.season_id = season.season_id;
+        profile.season_score = 0;
+    }
+    profile.season_score = profile
+        .season_score
+        .checked_add(amount)
+        .ok_or(ColonyError::Overflow)?;
+
+    let player = profile.owner;
+    let score = profile.season_score;
+    let leaderboard = &mut season.leaderboard;
+    if let Some(entry) = leaderboard.iter_mut().find(|entry| entry.player == player) {
+        entry.score = score;
+    } else if leaderboard.len() < LEADERBOARD_SIZE {
+        leaderboard.push(LeaderboardEntry { player, score });
+    } else if let Some(last) = leaderboard.last_mut() {
+        if score > last.score {
+            *last = LeaderboardEntry { player, score };
+        }
+    }
+    leaderboard.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+    Ok(())
+}
+
+/// Source of random values for fulfilling randomness requests
+trait RandomnessProvider {
+    /// Check the fulfilment is allowed and return the request's random value
+    fn fulfil(
+        &self,
+        request_key: &Pubkey,
+        request: &RandomnessRequest,
+        fulfiller: &Pubkey,
+        oracle_value: [u8; 32],
+    ) -> Result<[u8; 32]>;
+}
+
+/// Trusted off-chain signer that supplies random values. Nothing is verified on chain beyond
+/// the signer, so the oracle must be trusted not to pick favourable values.
+struct OracleRandomness {
+    oracle: Pubkey,
+}
+
+impl RandomnessProvider for OracleRandomness {
+    fn fulfil(
+        &self,
+        request_key: &Pubkey,
+        _request: &RandomnessRequest,
+        fulfiller: &Pubkey,
+        oracle_value: [u8; 32],
+    ) -> Result<[u8; 32]> {
+        require!(*fulfiller == self.oracle, ColonyError::Unauthorized);
+        // Bind the oracle value to this request
+        Ok(hashv(&[&oracle_value, request_key.as_ref()]).to_bytes())
+    }
+}
+
+/// Local stand-in: value derived from the request alone, anyone can fulfil (predictable)
+#[cfg(feature = "test-randomness")]
+struct DeterministicRandomness;
+
+#[cfg(feature = "test-randomness")]
+impl RandomnessProvider for DeterministicRandomness {
+    fn fulfil(
+        &self,
+        request_key: &Pubkey,
+        request: &RandomnessRequest,
+        _fulfiller: &Pubkey,
+        _oracle_value: [u8; 32],
+    ) -> Result<[u8; 32]> {
+        Ok(hashv(&[request_key.as_ref(), &request.request_slot.to_le_bytes()]).to_bytes())
+    }
+}
+
+fn randomness_provider(game_state: &GameState) -> Result<Box<dyn RandomnessProvider>> {
+    match game_state.randomness_source {
+        RandomnessSource::Disabled => err!(ColonyError::RandomnessDisabled),
+        RandomnessSource::Oracle => Ok(Box::new(OracleRandomness {
+            oracle: game_state.randomness_oracle,
+        })),
+        #[cfg(feature = "test-randomness")]
+        RandomnessSource::Deterministic => Ok(Box::new(DeterministicRandomness)),
+        #[cfg(not(feature = "test-randomness"))]
+        RandomnessSource::Deterministic => err!(ColonyError::RandomnessSourceUnavailable),
+    }
+}
+
+/// Uniform roll in 0..100 from a random value
+fn roll_percent(value: &[u8; 32]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&value[..8]);
+    u64::from_le_bytes(bytes) % 100
+}
+
+/// Raid success chance in percent from the level difference and the target's defense
+fn raid_success_chance(attacker: &LandData, target: &LandData) -> i64 {
+    let level_diff = attacker.level as i64 - target.level as i64;
+    (RAID_BASE_CHANCE + level_diff * RAID_CHANCE_PER_LEVEL
+        - target.defense_level as i64 * RAID_CHANCE_PER_DEFENSE)
+        .clamp(RAID_MIN_CHANCE, RAID_MAX_CHANCE)
+}
+
+fn roll_land_event(value: &[u8; 32]) -> LandEvent {
+    let roll = roll_percent(value);
+    if roll < METEOR_STRIKE_CHANCE {
+        LandEvent::MeteorStrike
+    } else if roll < METEOR_STRIKE_CHANCE + RESOURCE_DISCOVERY_CHANCE {
+        LandEvent::ResourceDiscovery
+    } else {
+        LandEvent::None
+    }
+}
+
+/// Recompute a guild's total land levels and earnings bonus from its per-member levels
+fn update_guild_bonus(guild: &mut Guild) {
+    guild.total_land_levels = guild.member_levels.iter().map(|levels| *levels as u32).sum();
+    guild.bonus_bps = (guild.total_land_levels as u64 * GUILD_BONUS_BPS_PER_LEVEL as u64)
+        .min(MAX_GUILD_BONUS_BPS as u64) as u16;
+}
+
+/// Resources produced since the last checkout (merged lands other than the anchor produce
+/// nothing, like their earnings)
+fn calculate_resources(land: &LandData, current_time: i64) -> Result<[u64; RESOURCE_COUNT]> {
+    let mut produced = [0; RESOURCE_COUNT];
+    if current_time < MINING_START_TIME || (land.mega_plot != 0 && land.land_id != land.mega_plot) {
+        return Ok(produced);
+    }
+
+    let time_passed = (current_time - land.last_checkout) as u64;
+    let speeds = RESOURCE_SPEEDS[(land.level - 1) as usize];
+
+    for (amount, speed) in produced.iter_mut().zip(speeds) {
+        *amount = speed
+            .checked_mul(time_passed)
+            .ok_or(ColonyError::Overflow)?
+            .checked_div(SECONDS_PER_DAY)
+            .ok_or(ColonyError::Overflow)?;
+    }
+
+    Ok(produced)
+}
+
+/// Settle a land's pending period: credit produced resources to the owner's profile and
+/// restart earning from `current_time`. Returns the earnings the caller must pay out.
+fn settle_land(land: &mut LandData, owner_profile: &mut UserProfile, current_time: i64) -> Result<u64> {
+    let earnings = calculate_boosted_earnings(land, owner_profile, current_time)?;
+    let produced = calculate_resources(land, current_time)?;
+    credit_resources(owner_profile, &produced)?;
+
+    land.fixed_earnings = 0;
+    land.last_checkout = current_time;
+    Ok(earnings)
+}
+
+fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let share = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(ColonyError::Overflow)?
+        / BPS_DENOMINATOR as u128;
+    Ok(share as u64)
+}
+
+/// Pay real SPL tokens out of a game-owned vault (signed by the game_state PDA). The vault
+/// also pays any transfer fee, so `to` receives exactly `amount`.
+fn vault_transfer<'info>(
+    token_program: &AccountInfo<'info>,
+    token_vault: &AccountInfo<'info>,
+    token_mint: &AccountInfo<'info>,
+    game_state: &Account<'info, GameState>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    require!(
+        game_state.token_migration == TokenMigration::Idle,
+        ColonyError::TokenMigrationInProgress
+    );
+    let gross = fee_inclusive_amount(token_mint, amount)?;
+    require!(token_balance(token_vault)? >= gross, ColonyError::InsufficientTreasury);
+
+    let bump = game_state.bump;
+    let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+    transfer_tokens(
+        token_program,
+        token_vault,
+        to,
+        &game_state.to_account_info(),
+        token_mint,
+        gross,
+        signer_seeds,
+    )
+}
+
+/// transfer_checked with the mint's own decimals, rejecting unsupported mint extensions.
+/// `amount` leaves `from`; a Token-2022 transfer fee is withheld from what `to` receives.
+fn transfer_tokens<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    check_mint_extensions(mint)?;
+    let decimals = mint_decimals(mint)?;
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            TransferChecked {
+                from: from.clone(),
+                to: to.clone(),
+                authority: authority.clone(),
+                mint: mint.clone(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        decimals,
+    )
+}
+
+fn mint_decimals(mint: &AccountInfo) -> Result<u8> {
+    let data = mint.try_borrow_data()?;
+    Ok(StateWithExtensions::<MintState>::unpack(&data)?.base.decimals)
+}
+
+fn token_balance(token_account: &AccountInfo) -> Result<u64> {
+    let data = token_account.try_borrow_data()?;
+    Ok(StateWithExtensions::<TokenAccountState>::unpack(&data)?.base.amount)
+}
+
+/// Reject mints with an extension in UNSUPPORTED_MINT_EXTENSIONS (legacy SPL mints have none)
+fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let unsupported = state
+        .get_extension_types()?
+        .iter()
+        .any(|extension| UNSUPPORTED_MINT_EXTENSIONS.contains(extension));
+    require!(!unsupported, ColonyError::UnsupportedMintExtension);
+    Ok(())
+}
+
+/// Amount to send so the recipient receives `amount` after the mint's current transfer fee
+fn fee_inclusive_amount(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+    let epoch = Clock::get()?.epoch;
+    Ok(fee_config
+        .get_epoch_fee(epoch)
+        .calculate_pre_fee_amount(amount)
+        .ok_or(ColonyError::Overflow)?)
+}
+
+/// Split a payment into (burned, to token vault, to team wallet) by the configured bps
+fn split_revenue(game_state: &GameState, amount: u64) -> Result<(u64, u64, u64)> {
+    let to_vault = bps_of(amount, game_state.revenue_vault_bps)?;
+    let to_team = bps_of(amount, game_state.revenue_team_bps)?;
+    let burned = amount
+        .checked_sub(to_vault)
+        .and_then(|rest| rest.checked_sub(to_team))
+        .ok_or(ColonyError::Overflow)?;
+    Ok((burned, to_vault, to_team))
+}
+
+/// Take a game token payment from the user: burn its burn share and transfer the rest to the
+/// token vault and team wallet, which are only required when their share is non-zero
+#[allow(clippy::too_many_arguments)]
+fn collect_revenue<'info>(
+    game_state: &mut GameState,
+    token_program: &AccountInfo<'info>,
+    token_mint: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_vault: Option<AccountInfo<'info>>,
+    team_token_account: Option<AccountInfo<'info>>,
+    source: RevenueSource,
+    land_id: u16,
+    amount: u64,
+    current_time: i64,
+) -> Result<()> {
+    require!(
+        game_state.token_migration == TokenMigration::Idle,
+        ColonyError::TokenMigrationInProgress
+    );
+    let (burned, to_vault, to_team) = split_revenue(game_state, amount)?;
+    game_state.total_revenue = game_state
+        .total_revenue
+        .checked_add(amount)
+        .ok_or(ColonyError::Overflow)?;
+
+    if burned > 0 {
+        burn_checked(
+            CpiContext::new(
+                token_program.clone(),
+                BurnChecked {
+                    mint: token_mint.clone(),
+                    from: from.clone(),
+                    authority: authority.clone(),
+                },
+            ),
+            burned,
+            mint_decimals(token_mint)?,
+        )?;
+    }
+
+    // The payer also covers any transfer fee so each recipient gets its full share
+    for (share, to) in [(to_vault, token_vault), (to_team, team_token_account)] {
+        if share == 0 {
+            continue;
+        }
+        let to = to.ok_or(ColonyError::RevenueAccountRequired)?;
+        transfer_tokens(
+            token_program,
+            from,
+            &to,
+            authority,
+            token_mint,
+            fee_inclusive_amount(token_mint, share)?,
+            &[],
+        )?;
+    }
+
+    emit!(RevenueCollected {
+        payer: authority.key(),
+        source,
+        land_id,
+        burned,
+        to_vault,
+        to_team,
+        timestamp: current_time,
+    });
+    Ok(())
+}
+
+/// Take a payment out of pending earnings the token vault already holds: its own share stays,
+/// the burn share is burned from it and only the team share leaves the vault
+#[allow(clippy::too_many_arguments)]
+fn collect_vault_revenue<'info>(
+    game_state: &mut Account<'info, GameState>,
+    token_program: &AccountInfo<'info>,
+    token_mint: &AccountInfo<'info>,
+    token_vault: &AccountInfo<'info>,
+    team_token_account: Option<AccountInfo<'info>>,
+    payer: Pubkey,
+    source: RevenueSource,
+    land_id: u16,
+    amount: u64,
+    current_time: i64,
+) -> Result<()> {
+    require!(
+        game_state.token_migration == TokenMigration::Idle,
+        ColonyError::TokenMigrationInProgress
+    );
+    let (burned, to_vault, to_team) = split_revenue(game_state, amount)?;
+    game_state.total_revenue = game_state
+        .total_revenue
+        .checked_add(amount)
+        .ok_or(ColonyError::Overflow)?;
+
+    let authority = game_state.to_account_info();
+    let bump = game_state.bump;
+    let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+
+    if burned > 0 {
+        burn_checked(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                BurnChecked {
+                    mint: token_mint.clone(),
+                    from: token_vault.clone(),
+                    authority: authority.clone(),
+                },
+                signer_seeds,
+            ),
+            burned,
+            mint_decimals(token_mint)?,
+        )?;
+    }
+
+    // The vault covers any transfer fee so the team wallet gets its full share
+    if to_team > 0 {
+        let to = team_token_account.ok_or(ColonyError::RevenueAccountRequired)?;
+        transfer_tokens(
+            token_program,
+            token_vault,
+            &to,
+            &authority,
+            token_mint,
+            fee_inclusive_amount(token_mint, to_team)?,
+            signer_seeds,
+        )?;
+    }
+
+    emit!(RevenueCollected {
+        payer,
+        source,
+        land_id,
+        burned,
+        to_vault,
+        to_team,
+        timestamp: current_time,
+    });
+    Ok(())
+}
+
+fn credit_resources(profile: &mut UserProfile, amounts: &[u64; RESOURCE_COUNT]) -> Result<()> {
+    for (balance, amount) in profile.resources.iter_mut().zip(amounts) {
+        *balance = balance.checked_add(*amount).ok_or(ColonyError::Overflow)?;
+    }
+    Ok(())
+}
+
+fn debit_resources(profile: &mut UserProfile, amounts: &[u64; RESOURCE_COUNT]) -> Result<()> {
+    for (balance, amount) in profile.resources.iter_mut().zip(amounts) {
+        *balance = balance
+            .checked_sub(*amount)
+            .ok_or(ColonyError::InsufficientResources)?;
+    }
+    Ok(())
+}
+
+/// Grow a program-owned account to `new_len`, topping up rent from `payer`.
+/// Returns false if the account is already large enough.
+fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<bool> {
+    if account.data_len() >= new_len {
+        return Ok(false);
+    }
+
+    // Realloc (zero = false, new bytes are zero-initialized which means Pubkey::default())
+    #[allow(deprecated)]
+    account.realloc(new_len, false)?;
+
+    // Transfer additional rent from payer
+    top_up_rent(account, payer, system_program)?;
+
+    Ok(true)
+}
+
+/// Program accounts upgraded in place by the migrate_* instructions. Layouts only ever append
+/// fields (or reuse one with the same type), so older data deserializes zero-padded and each
+/// version step only rewrites field values.
+trait Versioned: AccountSerialize + AccountDeserialize + Space {
+    /// Current layout version
+    const VERSION: u8;
+
+    /// Account sizes of the deployed layouts from before the version byte, for v1, v2, ...
+    const LEGACY_SIZES: &'static [usize];
+
+    fn version(&self) -> u8;
+    fn set_version(&mut self, version: u8);
+
+    /// Transform fields from layout `version` to `version + 1`
+    fn upgrade_from(&mut self, version: u8) -> Result<()>;
+}
+
+impl Versioned for GameState {
+    const VERSION: u8 = GAME_STATE_VERSION;
+    const LEGACY_SIZES: &'static [usize] = &[67, 100];
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn upgrade_from(&mut self, version: u8) -> Result<()> {
+        // Appended fields start at zero = disabled
+        match version {
+            // v1 → v2: token_mint, token_vault_bump
+            1 => Ok(()),
+            // v2 → v3: the unused treasury_balance becomes total_revenue, counted from here
+            2 => {
+                self.total_revenue = 0;
+                Ok(())
+            }
+            _ => err!(ColonyError::UnknownAccountVersion),
+        }
+    }
+}
+
+impl Versioned for LandData {
+    const VERSION: u8 = LAND_DATA_VERSION;
+    const LEGACY_SIZES: &'static [usize] = &[60];
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn upgrade_from(&mut self, version: u8) -> Result<()> {
+        // Appended fields start at zero = disabled
+        match version {
+            1 => Ok(()),
+            _ => err!(ColonyError::UnknownAccountVersion),
+        }
+    }
+}
+
+impl Versioned for UserProfile {
+    const VERSION: u8 = USER_PROFILE_VERSION;
+    const LEGACY_SIZES: &'static [usize] = &[50];
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn upgrade_from(&mut self, version: u8) -> Result<()> {
+        // Appended fields start at zero = none
+        match version {
+            // v1 → v2: the legacy token_balance becomes lands_purchased, counted from here
+            1 => {
+                self.lands_purchased = 0;
+                Ok(())
+            }
+            _ => err!(ColonyError::UnknownAccountVersion),
+        }
+    }
+}
+
+/// Deserialize an account of any older layout, zero-padding data shorter than the current one
+fn load_versioned<T: Versioned>(account: &AccountInfo) -> Result<T> {
+    let data = account.try_borrow_data()?;
+    let mut padded = data.to_vec();
+    padded.resize(padded.len().max(8 + T::INIT_SPACE), 0);
+    T::try_deserialize(&mut padded.as_slice())
+}
+
+/// Stored layout version of a loaded account; legacy accounts without a version byte are
+/// identified by their size
+fn stored_version<T: Versioned>(state: &T, data_len: usize) -> Result<u8> {
+    if state.version() != 0 {
+        return Ok(state.version());
+    }
+    let index = T::LEGACY_SIZES
+        .iter()
+        .position(|size| *size == data_len)
+        .ok_or(ColonyError::UnknownAccountVersion)?;
+    Ok(index as u8 + 1)
+}
+
+/// Grow `account` to the current layout and run each version step up to T::VERSION.
+/// Returns the (from, to) versions.
+fn migrate_versioned<'info, T: Versioned>(
+    account: &AccountInfo<'info>,
+    mut state: T,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<(u8, u8)> {
+    let from = stored_version(&state, account.data_len())?;
+    require!(from <= T::VERSION, ColonyError::UnknownAccountVersion);
+
+    grow_account(account, payer, system_program, 8 + T::INIT_SPACE)?;
+    for version in from..T::VERSION {
+        state.upgrade_from(version)?;
+    }
+    state.set_version(T::VERSION);
+
+    let mut data = account.try_borrow_mut_data()?;
+    state.try_serialize(&mut &mut data[..])?;
+    Ok((from, T::VERSION))
+}
+
+/// Transfer lamports from `payer` so `account` stays rent-exempt at its current size
+fn top_up_rent<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent = Rent::get()?;
+    let minimum_balance = rent.minimum_balance(account.data_len());
+    let additional_rent = minimum_balance.saturating_sub(account.lamports());
+
+    if additional_rent > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            additional_rent,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Byte index and bit mask of a land in the reserved lands bitmap
+fn reserved_bit(land_id: u16) -> (usize, u8) {
+    let bit = (land_id - 1) as usize;
+    (bit / 8, 1 << (bit % 8))
+}
+
+fn is_land_reserved(bits: &[u8; RESERVED_LANDS_BITMAP_LEN], land_id: u16) -> bool {
+    let (byte, mask) = reserved_bit(land_id);
+    bits[byte] & mask != 0
+}
+
+/// Set or clear the bits of lands `start..=end`: whole bytes are filled at once, only the
+/// edge bytes are masked
+fn set_reserved_bits(bits: &mut [u8; RESERVED_LANDS_BITMAP_LEN], start: u16, end: u16, reserved: bool) {
+    let (first_byte, _) = reserved_bit(start);
+    let (last_byte, _) = reserved_bit(end);
+    let first_bit = (start - 1) % 8;
+    let last_bit = (end - 1) % 8;
+
+    let mut apply = |byte: usize, mask: u8| {
+        if reserved {
+            bits[byte] |= mask;
+        } else {
+            bits[byte] &= !mask;
+        }
+    };
+    let low_mask = 0xFFu8 << first_bit; // bits first_bit..=7
+    let high_mask = 0xFFu8 >> (7 - last_bit); // bits 0..=last_bit
+    if first_byte == last_byte {
+        apply(first_byte, low_mask & high_mask);
+        return;
+    }
+    apply(first_byte, low_mask);
+    apply(last_byte, high_mask);
+    bits[first_byte + 1..last_byte].fill(if reserved { 0xFF } else { 0 });
+}
+
+/// Purchase checks shared by the buy_land variants: game active, valid and unreserved land_id,
+/// wallet land limit, whitelist window and rate limits (updating their counters)
+fn check_land_purchase(
+    game_state: &mut GameState,
+    user_profile: &mut UserProfile,
+    reserved_lands: Option<&AccountLoader<ReservedLands>>,
+    buyer: &Pubkey,
+    land_id: u16,
+    proof: &[[u8; 32]],
+    clock: &Clock,
+) -> Result<()> {
+    require!(game_state.is_active, ColonyError::GameNotActive);
+    require!(land_id > 0 && land_id <= MAX_LAND_ID, ColonyError::InvalidLandId);
+    require!(
+        user_profile.lands_owned < MAX_LANDS_PER_USER,
+        ColonyError::MaxLandsReached
+    );
+
+    // Reserved ranges (partners, events, team treasury) can only be granted by the authority
+    if game_state.reserved_lands_enabled {
+        let reserved_lands = reserved_lands.ok_or(ColonyError::ReservedLandsAccountRequired)?;
+        require!(
+            !is_land_reserved(&reserved_lands.load()?.bits, land_id),
+            ColonyError::LandReserved
+        );
+    }
+
+    // Whitelist window: only proven wallets, up to the per-wallet cap
+    if whitelist_active(game_state, clock.unix_timestamp) {
+        require!(proof.len() <= MAX_WHITELIST_PROOF_LEN, ColonyError::NotWhitelisted);
+        require!(
+            verify_whitelist_proof(&game_state.whitelist_root, buyer, proof),
+            ColonyError::NotWhitelisted
+        );
+        // The count only covers the current window
+        if user_profile.whitelist_window != game_state.whitelist_window {
+            user_profile.whitelist_window = game_state.whitelist_window;
+            user_profile.whitelist_purchases = 0;
+        }
+        require!(
+            user_profile.whitelist_purchases < game_state.whitelist_purchase_cap,
+            ColonyError::WhitelistCapReached
+        );
+        user_profile.whitelist_purchases += 1;
+    }
+
+    // Anti-bot rate limits: per-wallet interval and per-slot global cap
+    if game_state.purchase_cooldown > 0 && user_profile.last_purchase_time > 0 {
+        require!(
+            clock.unix_timestamp >= user_profile.last_purchase_time + game_state.purchase_cooldown,
+            ColonyError::PurchaseCooldown
+        );
+    }
+    user_profile.last_purchase_time = clock.unix_timestamp;
+    user_profile.lands_purchased = user_profile
+        .lands_purchased
+        .checked_add(1)
+        .ok_or(ColonyError::Overflow)?;
+
+    if game_state.slot_purchase_cap > 0 {
+        if game_state.last_purchase_slot != clock.slot {
+            game_state.last_purchase_slot = clock.slot;
+            game_state.slot_purchases = 0;
+        }
+        require!(
+            game_state.slot_purchases < game_state.slot_purchase_cap,
+            ColonyError::SlotPurchaseCapReached
+        );
+        game_state.slot_purchases += 1;
+    }
+
+    Ok(())
+}
+
+/// Checks shared by the upgrade_land variants. Returns the upgrade cost in game tokens.
+fn check_land_upgrade(land_data: &LandData, user: &Pubkey, current_time: i64) -> Result<u64> {
+    require!(current_time >= MINING_START_TIME, ColonyError::MiningNotStarted);
+    require!(land_data.owner == *user, ColonyError::NotLandOwner);
+    require!(land_data.level < MAX_LEVEL, ColonyError::MaxLevelReached);
+    require!(land_data.mega_plot == 0, ColonyError::LandMerged);
+    Ok(UPGRADE_COSTS[(land_data.level - 1) as usize])
+}
+
+/// Level a land up once its token cost is paid: settle resources and pay the resource part,
+/// keep pending earnings, score the spend and unlock achievements
+fn apply_land_upgrade(
+    land_data: &mut LandData,
+    user_profile: &mut UserProfile,
+    season: Option<&mut Season>,
+    cost: u64,
+    current_time: i64,
+) -> Result<()> {
+    let pending = calculate_boosted_earnings(land_data, user_profile, current_time)?;
+    let produced = calculate_resources(land_data, current_time)?;
+    let resource_cost = UPGRADE_RESOURCE_COSTS[(land_data.level - 1) as usize];
+
+    // Settle resources produced so far, then pay the resource part of the upgrade
+    credit_resources(user_profile, &produced)?;
+    debit_resources(user_profile, &resource_cost)?;
+
+    // Count the upgrade spend towards the season score
+    if let Some(season) = season {
+        record_season_score(season, user_profile, current_time, cost)?;
+    }
+
+    land_data.fixed_earnings = pending;
+    land_data.last_checkout = current_time;
+    land_data.level += 1;
+    if land_data.level >= ACHIEVEMENT_LEVEL {
+        unlock_achievement(user_profile, Achievement::Level5);
+    }
+    Ok(())
+}
+
+/// Amount of an alternative payment mint charged for a price in game tokens
+fn payment_amount(payment_mint: &PaymentMint, game_token_amount: u64) -> Result<u64> {
+    require!(payment_mint.price_per_token > 0, ColonyError::PaymentMintDisabled);
+    let amount = (game_token_amount as u128)
+        .checked_mul(payment_mint.price_per_token as u128)
+        .ok_or(ColonyError::Overflow)?
+        / TOKEN_MULTIPLIER as u128;
+    require!(amount > 0, ColonyError::InvalidAmount);
+    u64::try_from(amount).map_err(|_| ColonyError::Overflow.into())
+}
+
+/// Initialize a new level 1 land (earning starts at mining launch at the earliest)
+fn init_land(land_data: &mut LandData, owner: Pubkey, land_id: u16, bump: u8, current_time: i64) {
+    land_data.land_id = land_id;
+    land_data.owner = owner;
+    land_data.level = 1;
+    land_data.fixed_earnings = 0;
+    land_data.last_checkout = current_time.max(MINING_START_TIME);
+    land_data.bump = bump;
+    land_data.version = LAND_DATA_VERSION;
+}
+
+/// Count a new land for its owner (achievements, referrer on the first purchase) and the game
+fn record_land_purchase(
+    user_profile: &mut UserProfile,
+    referrer_profile: Option<&UserProfile>,
+    game_state: &mut GameState,
+    owner: Pubkey,
+    profile_bump: u8,
+) -> Result<()> {
+    if user_profile.owner == Pubkey::default() {
+        user_profile.version = USER_PROFILE_VERSION;
+    }
+    user_profile.owner = owner;
+    user_profile.lands_owned = user_profile
+        .lands_owned
+        .checked_add(1)
+        .ok_or(ColonyError::Overflow)?;
+    user_profile.bump = profile_bump;
+    unlock_land_count_achievements(user_profile);
+
+    // Record the referrer on the buyer's first purchase
+    if let Some(referrer_profile) = referrer_profile {
+        if user_profile.lands_owned == 1 && user_profile.referrer == Pubkey::default() {
+            require!(referrer_profile.owner != owner, ColonyError::InvalidReferrer);
+            user_profile.referrer = referrer_profile.owner;
+            msg!("Referred by {}", referrer_profile.owner);
+        }
+    }
+
+    game_state.total_lands_sold = game_state
+        .total_lands_sold
+        .checked_add(1)
+        .ok_or(ColonyError::Overflow)?;
+    Ok(())
+}
+
+fn whitelist_active(game_state: &GameState, current_time: i64) -> bool {
+    game_state.sale_phase == SalePhase::Whitelist && current_time < game_state.whitelist_end
+}
+
+/// Verify a wallet against the whitelist Merkle root. Leaves are sha256(wallet); parent nodes
+/// hash the sorted pair of children, so proofs carry no left/right flags.
+fn verify_whitelist_proof(root: &[u8; 32], wallet: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let mut node = hashv(&[wallet.as_ref()]).to_bytes();
+    for sibling in proof {
+        node = if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node == *root
+}
+
+/// Grid position (column, row) of a land
+fn land_position(land_id: u16) -> (u16, u16) {
+    ((land_id - 1) % LAND_GRID_WIDTH, (land_id - 1) / LAND_GRID_WIDTH)
+}
+
+fn lands_adjacent(a: u16, b: u16) -> bool {
+    let (ax, ay) = land_position(a);
+    let (bx, by) = land_position(b);
+    ax.abs_diff(bx) + ay.abs_diff(by) == 1
+}
+
+/// Whether the lands form one edge-connected group on the grid
+fn lands_connected(land_ids: &[u16]) -> bool {
+    let mut reached = vec![false; land_ids.len()];
+    let mut stack = vec![0];
+    reached[0] = true;
+    while let Some(i) = stack.pop() {
+        for j in 0..land_ids.len() {
+            if !reached[j] && lands_adjacent(land_ids[i], land_ids[j]) {
+                reached[j] = true;
+                stack.push(j);
+            }
+        }
+    }
+    reached.iter().all(|r| *r)
+}
+
+fn land_region(land_id: u16) -> u16 {
+    (land_id - 1) / LANDS_PER_REGION + 1
+}
+
+/// Create the land mint and `holder`'s token account for it, write the land metadata, mint the
+/// single NFT and revoke the mint authority so supply stays at one.
+#[allow(clippy::too_many_arguments)]
+fn mint_land_nft_to_holder<'info>(
+    payer: &AccountInfo<'info>,
+    holder: &AccountInfo<'info>,
+    game_state: &Account<'info, GameState>,
+    land_mint: &AccountInfo<'info>,
+    land_mint_bump: u8,
+    holder_token_account: &AccountInfo<'info>,
+    nft_token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    land: &LandData,
+) -> Result<()> {
+    let bump = game_state.bump;
+    let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+    let authority = game_state.to_account_info();
+
+    create_land_mint(payer, game_state, land_mint, land_mint_bump, nft_token_program, system_program, land.land_id)?;
+
+    create_idempotent(CpiContext::new(
+        associated_token_program.clone(),
+        Create {
+            payer: payer.clone(),
+            associated_token: holder_token_account.clone(),
+            authority: holder.clone(),
+            mint: land_mint.clone(),
+            system_program: system_program.clone(),
+            token_program: nft_token_program.clone(),
+        },
+    ))?;
+
+    let uri = if game_state.land_nft_uri.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}.json", game_state.land_nft_uri, land.land_id)
+    };
+
+    token_metadata_initialize(
+        CpiContext::new_with_signer(
+            nft_token_program.clone(),
+            TokenMetadataInitialize {
+                program_id: nft_token_program.clone(),
+                metadata: land_mint.clone(),
+                update_authority: authority.clone(),
+                mint_authority: authority.clone(),
+                mint: land_mint.clone(),
+            },
+            signer_seeds,
+        ),
+        format!("Colony Land #{}", land.land_id),
+        LAND_NFT_SYMBOL.to_string(),
+        uri,
+    )?;
+
+    let fields = [
+        ("land_id", land.land_id.to_string()),
+        ("level", land.level.to_string()),
+        ("region", land_region(land.land_id).to_string()),
+    ];
+    for (key, value) in fields {
+        token_metadata_update_field(
+            CpiContext::new_with_signer(
+                nft_token_program.clone(),
+                TokenMetadataUpdateField {
+                    program_id: nft_token_program.clone(),
+                    metadata: land_mint.clone(),
+                    update_authority: authority.clone(),
+                },
+                signer_seeds,
+            ),
+            Field::Key(key.to_string()),
+            value,
+        )?;
+    }
+
+    // Metadata grew the mint account past its initial rent
+    top_up_rent(land_mint, payer, system_program)?;
+
+    mint_to(
+        CpiContext::new_with_signer(
+            nft_token_program.clone(),
+            MintTo {
+                mint: land_mint.clone(),
+                to: holder_token_account.clone(),
+                authority: authority.clone(),
+            },
+            signer_seeds,
+        ),
+        1,
+    )?;
+
+    set_authority(
+        CpiContext::new_with_signer(
+            nft_token_program.clone(),
+            SetAuthority {
+                current_authority: authority,
+                account_or_mint: land_mint.clone(),
+            },
+            signer_seeds,
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+
+    Ok(())
+}
+
+/// Create the land mint PDA with decimals 0, game_state as mint authority, metadata pointer and
+/// close authority, and no freeze authority or permanent delegate, so holders fully own their NFT.
+/// Lamports sent to the PDA beforehand only lower the rent paid by `payer`.
+fn create_land_mint<'info>(
+    payer: &AccountInfo<'info>,
+    game_state: &Account<'info, GameState>,
+    land_mint: &AccountInfo<'info>,
+    land_mint_bump: u8,
+    nft_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    land_id: u16,
+) -> Result<()> {
+    let land_id_bytes = land_id.to_le_bytes();
+    let mint_seeds: &[&[&[u8]]] = &[&[b"land_mint", land_id_bytes.as_ref(), &[land_mint_bump]]];
+    let space = ExtensionType::try_calculate_account_len::<MintState>(&[
+        ExtensionType::MetadataPointer,
+        ExtensionType::MintCloseAuthority,
+    ])?;
+
+    let minimum_balance = Rent::get()?.minimum_balance(space);
+    let additional_rent = minimum_balance.saturating_sub(land_mint.lamports());
+    if additional_rent > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: land_mint.clone(),
+                },
+            ),
+            additional_rent,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate {
+                account_to_allocate: land_mint.clone(),
+            },
+            mint_seeds,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign {
+                account_to_assign: land_mint.clone(),
+            },
+            mint_seeds,
+        ),
+        nft_token_program.key,
+    )?;
+
+    let authority = game_state.key();
+    metadata_pointer_initialize(
+        CpiContext::new(
+            nft_token_program.clone(),
+            MetadataPointerInitialize {
+                token_program_id: nft_token_program.clone(),
+                mint: land_mint.clone(),
+            },
+        ),
+        Some(authority),
+        Some(land_mint.key()),
+    )?;
+    mint_close_authority_initialize(
+        CpiContext::new(
+            nft_token_program.clone(),
+            MintCloseAuthorityInitialize {
+                token_program_id: nft_token_program.clone(),
+                mint: land_mint.clone(),
+            },
+        ),
+        Some(&authority),
+    )?;
+    initialize_mint2(
+        CpiContext::new(
+            nft_token_program.clone(),
+            InitializeMint2 {
+                mint: land_mint.clone(),
+            },
+        ),
+        0,
+        &authority,
+        None,
+    )
+}
+
+/// Close a program account outside of Anchor's `close` constraint, sending its rent to `destination`
+fn close_program_account<'info>(account: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let lamports = destination
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(ColonyError::Overflow)?;
+    **destination.try_borrow_mut_lamports()? = lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.assign(&System::id());
+    account.resize(0)?;
+    Ok(())
+}
+
+/// Burn a land's NFT, signed by its holder, and close the mint, returning its rent to
+/// `destination`
+fn close_land_mint<'info>(
+    game_state: &Account<'info, GameState>,
+    land_mint: &AccountInfo<'info>,
+    holder: Option<AccountInfo<'info>>,
+    holder_token_account: Option<AccountInfo<'info>>,
+    destination: &AccountInfo<'info>,
+    nft_token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let authority = game_state.to_account_info();
+    let (supply, closable) = {
+        let data = land_mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+        let close_authority = mint
+            .get_extension::<MintCloseAuthority>()
+            .ok()
+            .and_then(|extension| Option::<Pubkey>::from(extension.close_authority));
+        (mint.base.supply, close_authority == Some(authority.key()))
+    };
+    require!(closable, ColonyError::LandMintNotClosable);
+
+    let bump = game_state.bump;
+    let signer_seeds: &[&[&[u8]]] = &[&[b"game_state", &[bump]]];
+
+    if supply > 0 {
+        let (Some(holder), Some(holder_token_account)) = (holder, holder_token_account) else {
+            return err!(ColonyError::NotNftHolder);
+        };
+        burn_checked(
+            CpiContext::new(
+                nft_token_program.clone(),
+                BurnChecked {
+                    mint: land_mint.clone(),
+                    from: holder_token_account,
+                    authority: holder,
+                },
+            ),
+            supply,
+            0,
+        )?;
+    }
+
+    close_account(CpiContext::new_with_signer(
+        nft_token_program.clone(),
+        CloseAccount {
+            account: land_mint.clone(),
+            destination: destination.clone(),
+            authority,
+        },
+        signer_seeds,
+    ))
+}
+
+// ============================================================================
+// ACCOUNTS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeGame<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GameState::INIT_SPACE,
+        seeds = [b"game_state"],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+
+    /// CHECK: Vault PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct BuyLand<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + LandData::INIT_SPACE,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        mut,
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the token vault share of the payment (required when revenue_vault_bps > 0)
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the team share of the payment (required when revenue_team_bps > 0)
+    #[account(
+        mut,
+        token::mint = token_mint,
+        address = game_state.team_wallet @ ColonyError::InvalidTeamWallet
+    )]
+    pub team_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Land NFT mint, created by mint_land_nft_to_holder
+    #[account(
+        mut,
+        seeds = [b"land_mint", land_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub land_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Buyer's land NFT token account, created by mint_land_nft_to_holder
+    #[account(mut)]
+    pub user_land_token_account: UncheckedAccount<'info>,
+
+    /// Referrer's profile, recorded on the buyer's first purchase
+    pub referrer_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    /// Required once the reserved lands bitmap exists
+    #[account(
+        seeds = [b"reserved_lands"],
+        bump = game_state.reserved_lands_bump
+    )]
+    pub reserved_lands: Option<AccountLoader<'info, ReservedLands>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub nft_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct BuyLandWithSol<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    /// CHECK: Vault PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = game_state.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + LandData::INIT_SPACE,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// CHECK: Land NFT mint, created by mint_land_nft_to_holder
+    #[account(
+        mut,
+        seeds = [b"land_mint", land_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub land_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Buyer's land NFT token account, created by mint_land_nft_to_holder
+    #[account(mut)]
+    pub user_land_token_account: UncheckedAccount<'info>,
+
+    /// Referrer's profile, recorded on the buyer's first purchase
+    pub referrer_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    /// Required once the reserved lands bitmap exists
+    #[account(
+        seeds = [b"reserved_lands"],
+        bump = game_state.reserved_lands_bump
+    )]
+    pub reserved_lands: Option<AccountLoader<'info, ReservedLands>>,
+
+    pub nft_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct BuyLandWithToken<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        seeds = [b"payment_mint", payment_token_mint.key().as_ref()],
+        bump = payment_mint.bump
+    )]
+    pub payment_mint: Box<Account<'info, PaymentMint>>,
+
+    pub payment_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = payment_token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_payment_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = payment_mint.treasury @ ColonyError::InvalidTreasury,
+        token::mint = payment_token_mint,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + LandData::INIT_SPACE,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// CHECK: Land NFT mint, created by mint_land_nft_to_holder
+    #[account(
+        mut,
+        seeds = [b"land_mint", land_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub land_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Buyer's land NFT token account, created by mint_land_nft_to_holder
+    #[account(mut)]
+    pub user_land_token_account: UncheckedAccount<'info>,
+
+    /// Referrer's profile, recorded on the buyer's first purchase
+    pub referrer_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    /// Required once the reserved lands bitmap exists
+    #[account(
+        seeds = [b"reserved_lands"],
+        bump = game_state.reserved_lands_bump
+    )]
+    pub reserved_lands: Option<AccountLoader<'info, ReservedLands>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub nft_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct ClaimEarnings<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    /// Land owner's profile (resources are credited to the owner, also while rented)
+    #[account(
+        mut,
+        seeds = [b"user_profile", land_data.owner.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Required while the land is rented
+    #[account(
+        seeds = [b"rental", land_id.to_le_bytes().as_ref()],
+        bump = rental.bump
+    )]
+    pub rental: Option<Account<'info, Rental>>,
+
+    /// Owner's referrer profile; referral rewards accrue only when it is passed
+    #[account(
+        mut,
+        seeds = [b"user_profile", user_profile.referrer.as_ref()],
+        bump = referrer_profile.bump
+    )]
+    pub referrer_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    /// Current season, scores the owner's earnings while the season is open
+    #[account(
+        mut,
+        seeds = [b"season", game_state.current_season.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Owner's guild, required when the owner is in a guild
+    #[account(
+        constraint = guild.key() == user_profile.guild @ ColonyError::NotGuildMember
+    )]
+    pub guild: Option<Box<Account<'info, Guild>>>,
+
+    /// Owner's guild treasury, required when the guild has a tax (its mint is checked in the
+    /// handler, a treasury left in a pre-migration mint takes no tax)
+    #[account(
+        mut,
+        token::authority = game_state,
+        seeds = [b"guild_vault", user_profile.guild.as_ref()],
+        bump
+    )]
+    pub guild_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the claimer's share instead of `user_token_account`
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub payout_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the owner's share while the land is rented (owned by the owner's payout address if set)
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub owner_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAchievementReward<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct UpgradeLand<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Current season, scores the upgrade spend while the season is open
+    #[account(
+        mut,
+        seeds = [b"season", game_state.current_season.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    #[account(
+        mut,
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the token vault share of the payment (required when revenue_vault_bps > 0)
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the team share of the payment (required when revenue_team_bps > 0)
+    #[account(
+        mut,
+        token::mint = token_mint,
+        address = game_state.team_wallet @ ColonyError::InvalidTeamWallet
+    )]
+    pub team_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct UpgradeLandWithToken<'info> {
+    pub user: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Current season, scores the upgrade spend while the season is open
+    #[account(
+        mut,
+        seeds = [b"season", game_state.current_season.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    #[account(
+        seeds = [b"payment_mint", payment_token_mint.key().as_ref()],
+        bump = payment_mint.bump
+    )]
+    pub payment_mint: Box<Account<'info, PaymentMint>>,
+
+    pub payment_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = payment_token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_payment_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = payment_mint.treasury @ ColonyError::InvalidTreasury,
+        token::mint = payment_token_mint,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct Compound<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// Current season, scores the upgrade spend while the season is open
+    #[account(
+        mut,
+        seeds = [b"season", game_state.current_season.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    #[account(
+        mut,
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the team share of the upgrade cost (required when revenue_team_bps > 0)
+    #[account(
+        mut,
+        token::mint = token_mint,
+        address = game_state.team_wallet @ ColonyError::InvalidTeamWallet
+    )]
+    pub team_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CraftTokens<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutAddress<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct MintLandNft<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    /// CHECK: Land NFT mint, created by mint_land_nft_to_holder
+    #[account(
+        mut,
+        seeds = [b"land_mint", land_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub land_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Owner's land NFT token account, created by mint_land_nft_to_holder
+    #[account(mut)]
+    pub user_land_token_account: UncheckedAccount<'info>,
+
+    pub nft_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct SyncLandOwner<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        seeds = [b"land_mint", land_id.to_le_bytes().as_ref()],
+        bump,
+        mint::token_program = nft_token_program,
+    )]
+    pub land_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        token::mint = land_mint,
+        token::token_program = nft_token_program,
+        constraint = holder_token_account.amount == 1 @ ColonyError::NotNftHolder
+    )]
+    pub holder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", land_data.owner.as_ref()],
+        bump = previous_owner_profile.bump
+    )]
+    pub previous_owner_profile: Account<'info, UserProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", holder_token_account.owner.as_ref()],
+        bump
+    )]
+    pub new_owner_profile: Account<'info, UserProfile>,
+
+    pub nft_token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct RefreshLandMetadata<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        seeds = [b"land_mint", land_id.to_le_bytes().as_ref()],
+        bump,
+        mint::token_program = nft_token_program,
+    )]
+    pub land_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub nft_token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct CreateRentalOffer<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Rental::INIT_SPACE,
+        seeds = [b"rental", land_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub rental: Account<'info, Rental>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct AcceptRental<'info> {
+    #[account(mut)]
+    pub renter: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        seeds = [b"rental", land_id.to_le_bytes().as_ref()],
+        bump = rental.bump
+    )]
+    pub rental: Account<'info, Rental>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", land_data.owner.as_ref()],
+        bump = owner_profile.bump
+    )]
+    pub owner_profile: Account<'info, UserProfile>,
+
+    /// CHECK: Land owner wallet, only used as the owner token account authority
+    #[account(address = land_data.owner)]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = renter,
+        associated_token::token_program = token_program,
+    )]
+    pub renter_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = renter,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct EndRental<'info> {
+    pub signer: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        seeds = [b"rental", land_id.to_le_bytes().as_ref()],
+        bump = rental.bump,
+        close = rental_owner
+    )]
+    pub rental: Account<'info, Rental>,
+
+    /// CHECK: Receives the rental account rent, must be the offer creator
+    #[account(mut, address = rental.owner)]
+    pub rental_owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", land_data.owner.as_ref()],
+        bump = owner_profile.bump
+    )]
+    pub owner_profile: Account<'info, UserProfile>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required when ending an accepted rental
+    #[account(
+        mut,
+        token::mint = token_mint,
+        constraint = owner_token_account.owner == land_data.owner @ ColonyError::InvalidTokenAccountOwner
+    )]
+    pub owner_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Required when ending an accepted rental
+    #[account(
+        mut,
+        token::mint = token_mint,
+        constraint = renter_token_account.owner == rental.renter @ ColonyError::InvalidTokenAccountOwner
+    )]
+    pub renter_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct RequestLandEvent<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = 8 + RandomnessRequest::INIT_SPACE,
+        seeds = [b"randomness".as_ref(), &[RandomnessPurpose::LandEvent as u8], land_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct FulfilLandEvent<'info> {
+    /// Oracle signer (Oracle source) or anyone (Deterministic source)
+    pub fulfiller: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    /// Land owner's profile (resources are settled to the owner)
+    #[account(
+        mut,
+        seeds = [b"user_profile", land_data.owner.as_ref()],
+        bump = owner_profile.bump
+    )]
+    pub owner_profile: Account<'info, UserProfile>,
+
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"randomness".as_ref(), &[RandomnessPurpose::LandEvent as u8], land_id.to_le_bytes().as_ref()],
+        bump = randomness_request.bump
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+
+    /// CHECK: Receives the request rent refund, validated against the request
+    #[account(mut, address = randomness_request.requester)]
+    pub requester: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct BuildDefense<'info> {
+    pub user: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(attacker_land_id: u16, target_land_id: u16)]
+pub struct RequestRaid<'info> {
+    #[account(mut)]
+    pub attacker: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        seeds = [b"land_data", attacker_land_id.to_le_bytes().as_ref()],
+        bump = attacker_land.bump
+    )]
+    pub attacker_land: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", target_land_id.to_le_bytes().as_ref()],
+        bump = target_land.bump
+    )]
+    pub target_land: Account<'info, LandData>,
+
+    #[account(
+        init,
+        payer = attacker,
+        space = 8 + RandomnessRequest::INIT_SPACE,
+        seeds = [b"randomness".as_ref(), &[RandomnessPurpose::Raid as u8], target_land_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+
+    #[account(
+        mut,
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = attacker,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(target_land_id: u16)]
+pub struct FulfilRaid<'info> {
+    /// Oracle signer (Oracle source) or anyone (Deterministic source)
+    pub fulfiller: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", target_land_id.to_le_bytes().as_ref()],
+        bump = target_land.bump
+    )]
+    pub target_land: Account<'info, LandData>,
+
+    /// Target owner's profile (resources are settled to the owner)
+    #[account(
+        mut,
+        seeds = [b"user_profile", target_land.owner.as_ref()],
+        bump = target_owner_profile.bump
+    )]
+    pub target_owner_profile: Account<'info, UserProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"land_data", randomness_request.attacker_land_id.to_le_bytes().as_ref()],
+        bump = attacker_land.bump
+    )]
+    pub attacker_land: Account<'info, LandData>,
+
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"randomness".as_ref(), &[RandomnessPurpose::Raid as u8], target_land_id.to_le_bytes().as_ref()],
+        bump = randomness_request.bump
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+
+    /// CHECK: Receives the request rent refund, validated against the request
+    #[account(mut, address = randomness_request.requester)]
+    pub requester: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(purpose: RandomnessPurpose, land_id: u16)]
+pub struct CancelRandomnessRequest<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"randomness".as_ref(), &[purpose as u8], land_id.to_le_bytes().as_ref()],
+        bump = randomness_request.bump
+    )]
+    pub randomness_request: Account<'info, RandomnessRequest>,
+
+    /// CHECK: Receives the request rent refund, validated against the request
+    #[account(mut, address = randomness_request.requester)]
+    pub requester: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(anchor_land_id: u16)]
+pub struct MergeLands<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", owner.key().as_ref()],
+        bump = owner_profile.bump
+    )]
+    pub owner_profile: Account<'info, UserProfile>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MegaPlot::INIT_SPACE,
+        seeds = [b"mega_plot", anchor_land_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub mega_plot: Account<'info, MegaPlot>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(anchor_land_id: u16)]
+pub struct SplitMegaPlot<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", owner.key().as_ref()],
+        bump = owner_profile.bump
+    )]
+    pub owner_profile: Account<'info, UserProfile>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ColonyError::NotLandOwner,
+        seeds = [b"mega_plot", anchor_land_id.to_le_bytes().as_ref()],
+        bump = mega_plot.bump
+    )]
+    pub mega_plot: Account<'info, MegaPlot>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"stake_vault"],
+        bump = game_state.stake_vault_bump
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"stake_vault"],
+        bump = game_state.stake_vault_bump
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives early unstake penalties
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CreateGuild<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Guild::INIT_SPACE,
+        seeds = [b"guild", user.key().as_ref()],
+        bump
+    )]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"guild_vault", guild.key().as_ref()],
+        bump
+    )]
+    pub guild_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(member: Pubkey)]
+pub struct InviteToGuild<'info> {
+    #[account(mut)]
+    pub leader: Signer<'info>,
+
+    #[account(has_one = leader @ ColonyError::Unauthorized)]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        init,
+        payer = leader,
+        space = 8 + GuildInvite::INIT_SPACE,
+        seeds = [b"guild_invite", guild.key().as_ref(), member.as_ref()],
+        bump
+    )]
+    pub invite: Account<'info, GuildInvite>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(member: Pubkey)]
+pub struct RevokeGuildInvite<'info> {
+    #[account(mut)]
+    pub leader: Signer<'info>,
+
+    #[account(has_one = leader @ ColonyError::Unauthorized)]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        mut,
+        seeds = [b"guild_invite", guild.key().as_ref(), member.as_ref()],
+        bump = invite.bump,
+        close = leader
+    )]
+    pub invite: Account<'info, GuildInvite>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGuild<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        mut,
+        seeds = [b"guild_invite", guild.key().as_ref(), user.key().as_ref()],
+        bump = invite.bump,
+        close = leader
+    )]
+    pub invite: Account<'info, GuildInvite>,
+
+    /// CHECK: Receives the invite rent, must be the guild leader
+    #[account(mut, address = guild.leader)]
+    pub leader: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveGuild<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump,
+        constraint = user_profile.guild == guild.key() @ ColonyError::NotGuildMember
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub guild: Account<'info, Guild>,
+}
+
+#[derive(Accounts)]
+pub struct ManageGuild<'info> {
+    pub leader: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = leader @ ColonyError::Unauthorized
+    )]
+    pub guild: Account<'info, Guild>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshGuildBonus<'info> {
+    #[account(mut)]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        constraint = member_profile.guild == guild.key() @ ColonyError::NotGuildMember
+    )]
+    pub member_profile: Account<'info, UserProfile>,
+}
+
+#[derive(Accounts)]
+pub struct KickGuildMember<'info> {
+    pub leader: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = leader @ ColonyError::Unauthorized
+    )]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        mut,
+        constraint = member_profile.guild == guild.key() @ ColonyError::NotGuildMember
+    )]
+    pub member_profile: Account<'info, UserProfile>,
+}
+
+#[derive(Accounts)]
+pub struct DissolveGuild<'info> {
+    #[account(mut)]
+    pub leader: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        mut,
+        seeds = [b"user_profile", leader.key().as_ref()],
+        bump = leader_profile.bump
+    )]
+    pub leader_profile: Account<'info, UserProfile>,
+
+    #[account(
+        mut,
+        has_one = leader @ ColonyError::Unauthorized,
+        close = leader
+    )]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"guild_vault", guild.key().as_ref()],
+        bump
+    )]
+    pub guild_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawGuildTreasury<'info> {
+    pub leader: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(has_one = leader @ ColonyError::Unauthorized)]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"guild_vault", guild.key().as_ref()],
+        bump
+    )]
+    pub guild_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGuildVaultClose<'info> {
+    #[account(mut)]
+    pub leader: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(has_one = leader @ ColonyError::Unauthorized)]
+    pub guild: Account<'info, Guild>,
+
+    /// Mint the treasury was created with (any mint but the current game token)
+    #[account(
+        constraint = old_token_mint.key() != game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub old_token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = old_token_mint,
+        token::authority = game_state,
+        token::token_program = old_token_program,
+        seeds = [b"guild_vault", guild.key().as_ref()],
+        bump
+    )]
+    pub guild_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = old_token_mint,
+        token::token_program = old_token_program,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub old_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGuildVaultInit<'info> {
+    #[account(mut)]
+    pub leader: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
+
+    #[account(has_one = leader @ ColonyError::Unauthorized)]
+    pub guild: Account<'info, Guild>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = leader,
+        token::mint = token_mint,
+        token::authority = game_state,
+        token::token_program = token_program,
+        seeds = [b"guild_vault", guild.key().as_ref()],
+        bump
+    )]
+    pub guild_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitStakeVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"stake_vault"],
+        bump
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
 
-    /// One-time migration to extend GameState (owner only)
-    pub fn migrate_v2(ctx: Context<MigrateV2>) -> Result<()> {
-        let game_state = &ctx.accounts.game_state;
+#[derive(Accounts)]
+pub struct StartSeason<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-        // Verify authority from raw bytes (offset 8, 32 bytes)
-        let data = game_state.try_borrow_data()?;
-        let stored_authority = Pubkey::try_from(&data[8..40])
-            .map_err(|_| ColonyError::Unauthorized)?;
-        require!(
-            ctx.accounts.authority.key() == stored_authority,
-            ColonyError::Unauthorized
-        );
-        let current_len = data.len();
-        drop(data);
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
 
-        let new_len = 8 + GameState::INIT_SPACE;
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Season::INIT_SPACE,
+        seeds = [b"season", (game_state.current_season + 1).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub season: Account<'info, Season>,
 
-        if current_len >= new_len {
-            msg!("GameState already at correct size ({})", current_len);
-            return Ok(());
-        }
+    pub system_program: Program<'info, System>,
+}
 
-        // Transfer additional rent from authority
-        let rent = Rent::get()?;
-        let new_minimum_balance = rent.minimum_balance(new_len);
-        let current_balance = game_state.lamports();
-        let additional_rent = new_minimum_balance.saturating_sub(current_balance);
+#[derive(Accounts)]
+pub struct CloseSeason<'info> {
+    pub authority: Signer<'info>,
 
-        if additional_rent > 0 {
-            system_program::transfer(
-                CpiContext::new(
-                    ctx.accounts.system_program.to_account_info(),
-                    system_program::Transfer {
-                        from: ctx.accounts.authority.to_account_info(),
-                        to: game_state.to_account_info(),
-                    },
-                ),
-                additional_rent,
-            )?;
-        }
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
 
-        // Realloc (zero = false, new bytes are zero-initialized which means Pubkey::default())
-        #[allow(deprecated)]
-        game_state.realloc(new_len, false)?;
+    #[account(
+        mut,
+        seeds = [b"season", game_state.current_season.to_le_bytes().as_ref()],
+        bump = season.bump,
+        constraint = !season.closed @ ColonyError::SeasonClosed
+    )]
+    pub season: Account<'info, Season>,
+}
 
-        msg!(
-            "GameState migrated from {} to {} bytes",
-            current_len,
-            new_len
-        );
-        Ok(())
-    }
+#[derive(Accounts)]
+#[instruction(season_id: u32)]
+pub struct ClaimSeasonPrize<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
 
-    /// Close a land account and return rent to authority (admin only)
-    pub fn admin_close_land(ctx: Context<AdminCloseLand>, _land_id: u16) -> Result<()> {
-        // Decrement user's lands_owned
-        let user_profile = &mut ctx.accounts.user_profile;
-        user_profile.lands_owned = user_profile
-            .lands_owned
-            .checked_sub(1)
-            .ok_or(ColonyError::Overflow)?;
+    pub game_state: Account<'info, GameState>,
 
-        // Decrement game_state.total_lands_sold
-        let game_state = &mut ctx.accounts.game_state;
-        game_state.total_lands_sold = game_state
-            .total_lands_sold
-            .checked_sub(1)
-            .ok_or(ColonyError::Overflow)?;
+    #[account(
+        mut,
+        seeds = [b"season", season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
 
-        msg!("Land #{} closed by admin", ctx.accounts.land_data.land_id);
-        Ok(())
-        // land_data account is closed by Anchor's `close = authority` constraint
-    }
+    #[account(
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-    /// Close a user profile account and return rent to authority (admin only)
-    pub fn admin_close_user_profile(ctx: Context<AdminCloseUserProfile>) -> Result<()> {
-        require!(
-            ctx.accounts.user_profile.lands_owned == 0,
-            ColonyError::UserHasLands
-        );
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
 
-        msg!("User profile closed for {}", ctx.accounts.user_profile.owner);
-        Ok(())
-        // user_profile account is closed by Anchor's `close = authority` constraint
-    }
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    /// Get pending earnings for a land (view function via simulate)
-    pub fn get_pending_earnings(ctx: Context<GetPendingEarnings>, _land_id: u16) -> Result<u64> {
-        let land_data = &ctx.accounts.land_data;
-        let clock = Clock::get()?;
-        let earnings = calculate_earnings(land_data, clock.unix_timestamp)?;
-        msg!("Pending earnings: {}", earnings);
-        Ok(earnings)
-    }
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
-// ============================================================================
-// HELPER FUNCTIONS
-// ============================================================================
+#[derive(Accounts)]
+pub struct MigrateTokenMintFinish<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-fn calculate_earnings(land: &LandData, current_time: i64) -> Result<u64> {
-    if current_time < MINING_START_TIME {
-        return Ok(land.fixed_earnings);
-    }
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
 
-    let time_passed = (current_time - land.last_checkout) as u64;
-    let speed = EARNING_SPEEDS[(land.level - 1) as usize];
+    #[account(
+        constraint = new_token_mint.key() == game_state.pending_token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub new_token_mint: InterfaceAccount<'info, Mint>,
 
-    let earned = speed
-        .checked_mul(time_passed)
-        .ok_or(ColonyError::Overflow)?
-        .checked_div(SECONDS_PER_DAY)
-        .ok_or(ColonyError::Overflow)?
-        .checked_add(land.fixed_earnings)
-        .ok_or(ColonyError::Overflow)?;
+    #[account(
+        init,
+        payer = authority,
+        token::mint = new_token_mint,
+        token::authority = game_state,
+        token::token_program = token_program,
+        seeds = [b"token_vault"],
+        bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
 
-    Ok(earned)
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
-// ============================================================================
-// ACCOUNTS
-// ============================================================================
-
 #[derive(Accounts)]
-pub struct InitializeGame<'info> {
-    #[account(mut)]
+pub struct SetPaymentMint<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
     pub authority: Signer<'info>,
 
+    pub game_state: Account<'info, GameState>,
+
+    /// Alternative payment mint (the game token itself can't be whitelisted)
     #[account(
-        init,
+        constraint = mint.key() != game_state.token_mint @ ColonyError::InvalidTokenMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
         payer = authority,
-        space = 8 + GameState::INIT_SPACE,
-        seeds = [b"game_state"],
+        space = 8 + PaymentMint::INIT_SPACE,
+        seeds = [b"payment_mint", mint.key().as_ref()],
         bump
     )]
+    pub payment_mint: Account<'info, PaymentMint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSol<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
     pub game_state: Account<'info, GameState>,
 
     /// CHECK: Vault PDA that holds SOL
     #[account(
         mut,
         seeds = [b"vault"],
-        bump
+        bump = game_state.vault_bump
     )]
     pub vault: SystemAccount<'info>,
 
@@ -483,65 +5867,102 @@ pub struct InitializeGame<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(land_id: u16)]
-pub struct BuyLand<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+pub struct WithdrawTokens<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
 
     #[account(mut)]
     pub game_state: Account<'info, GameState>,
 
     #[account(
-        init,
-        payer = user,
-        space = 8 + LandData::INIT_SPACE,
-        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
-        bump
+        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
     )]
-    pub land_data: Account<'info, LandData>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         init_if_needed,
-        payer = user,
-        space = 8 + UserProfile::INIT_SPACE,
-        seeds = [b"user_profile", user.key().as_ref()],
-        bump
+        payer = authority,
+        associated_token::mint = token_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program,
     )]
-    pub user_profile: Account<'info, UserProfile>,
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTokenMintReturnStake<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
+
+    /// CHECK: Owner of the stake, only receives tokens
+    pub staker: UncheckedAccount<'info>,
 
     #[account(
         mut,
+        seeds = [b"user_profile", staker.key().as_ref()],
+        bump = staker_profile.bump
+    )]
+    pub staker_profile: Account<'info, UserProfile>,
+
+    #[account(
         constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
     )]
     pub token_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
+        token::mint = token_mint,
+        token::authority = game_state,
+        seeds = [b"stake_vault"],
+        bump = game_state.stake_vault_bump
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
         associated_token::mint = token_mint,
-        associated_token::authority = user,
+        associated_token::authority = staker,
         associated_token::token_program = token_program,
     )]
-    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub staker_token_account: InterfaceAccount<'info, TokenAccount>,
 
     pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(land_id: u16)]
-pub struct ClaimEarnings<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-
-    #[account(mut)]
-    pub game_state: Account<'info, GameState>,
-
+pub struct MigrateTokenMintSweepStakeVault<'info> {
     #[account(
-        mut,
-        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
-        bump = land_data.bump
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
     )]
-    pub land_data: Account<'info, LandData>,
+    pub authority: Signer<'info>,
+
+    pub game_state: Account<'info, GameState>,
 
     #[account(
         constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
@@ -552,19 +5973,19 @@ pub struct ClaimEarnings<'info> {
         mut,
         token::mint = token_mint,
         token::authority = game_state,
-        seeds = [b"token_vault"],
-        bump = game_state.token_vault_bump
+        seeds = [b"stake_vault"],
+        bump = game_state.stake_vault_bump
     )]
-    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         init_if_needed,
-        payer = user,
+        payer = authority,
         associated_token::mint = token_mint,
-        associated_token::authority = user,
+        associated_token::authority = authority,
         associated_token::token_program = token_program,
     )]
-    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
 
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -572,69 +5993,101 @@ pub struct ClaimEarnings<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(land_id: u16)]
-pub struct UpgradeLand<'info> {
-    pub user: Signer<'info>,
+pub struct MigrateTokenMintCloseVaults<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
 
     #[account(mut)]
     pub game_state: Account<'info, GameState>,
 
+    /// Required while the token vault exists
     #[account(
         mut,
-        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
-        bump = land_data.bump
+        token::authority = game_state,
+        seeds = [b"token_vault"],
+        bump = game_state.token_vault_bump
     )]
-    pub land_data: Account<'info, LandData>,
+    pub token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
+    /// Required while the stake vault exists
     #[account(
         mut,
-        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+        token::authority = game_state,
+        seeds = [b"stake_vault"],
+        bump = game_state.stake_vault_bump
     )]
-    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub stake_vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AdminAction<'info> {
     #[account(
-        mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = user,
-        associated_token::token_program = token_program,
+        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
     )]
-    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub authority: Signer<'info>,
 
-    pub token_program: Interface<'info, TokenInterface>,
+    #[account(mut)]
+    pub game_state: Account<'info, GameState>,
 }
 
 #[derive(Accounts)]
-pub struct InitTokenVault<'info> {
+pub struct MigrateGameState<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
+    /// CHECK: Authority checked after zero-padded deserialization (account may have old layout)
     #[account(
         mut,
-        constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
+        seeds = [b"game_state"],
+        bump,
     )]
-    pub game_state: Account<'info, GameState>,
+    pub game_state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserProfile<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
 
+    /// CHECK: Raw realloc during migration (account may have old layout)
     #[account(
-        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
+        mut,
+        owner = crate::ID,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump,
     )]
-    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub user_profile: UncheckedAccount<'info>,
 
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(land_id: u16)]
+pub struct MigrateLandData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Raw realloc during migration (account may have old layout)
     #[account(
-        init,
-        payer = authority,
-        token::mint = token_mint,
-        token::authority = game_state,
-        seeds = [b"token_vault"],
-        bump
+        mut,
+        owner = crate::ID,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump,
     )]
-    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub land_data: UncheckedAccount<'info>,
 
-    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawSol<'info> {
+pub struct InitReservedLands<'info> {
     #[account(
         mut,
         constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
@@ -644,58 +6097,38 @@ pub struct WithdrawSol<'info> {
     #[account(mut)]
     pub game_state: Account<'info, GameState>,
 
-    /// CHECK: Vault PDA that holds SOL
     #[account(
-        mut,
-        seeds = [b"vault"],
-        bump = game_state.vault_bump
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<ReservedLands>(),
+        seeds = [b"reserved_lands"],
+        bump
     )]
-    pub vault: SystemAccount<'info>,
+    pub reserved_lands: AccountLoader<'info, ReservedLands>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawTokens<'info> {
+pub struct SetReservedRange<'info> {
     #[account(
-        mut,
         constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
     )]
     pub authority: Signer<'info>,
 
-    #[account(mut)]
     pub game_state: Account<'info, GameState>,
 
-    #[account(
-        constraint = token_mint.key() == game_state.token_mint @ ColonyError::InvalidTokenMint
-    )]
-    pub token_mint: InterfaceAccount<'info, Mint>,
-
     #[account(
         mut,
-        token::mint = token_mint,
-        token::authority = game_state,
-        seeds = [b"token_vault"],
-        bump = game_state.token_vault_bump
-    )]
-    pub token_vault: InterfaceAccount<'info, TokenAccount>,
-
-    #[account(
-        init_if_needed,
-        payer = authority,
-        associated_token::mint = token_mint,
-        associated_token::authority = authority,
-        associated_token::token_program = token_program,
+        seeds = [b"reserved_lands"],
+        bump = game_state.reserved_lands_bump
     )]
-    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
-
-    pub token_program: Interface<'info, TokenInterface>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
+    pub reserved_lands: AccountLoader<'info, ReservedLands>,
 }
 
 #[derive(Accounts)]
-pub struct AdminCloseTokenVault<'info> {
+#[instruction(land_id: u16)]
+pub struct AdminGrantLand<'info> {
     #[account(
         mut,
         constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
@@ -705,42 +6138,68 @@ pub struct AdminCloseTokenVault<'info> {
     #[account(mut)]
     pub game_state: Account<'info, GameState>,
 
+    /// CHECK: Any wallet can receive a land
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LandData::INIT_SPACE,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub land_data: Account<'info, LandData>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_profile: Account<'info, UserProfile>,
+
+    /// CHECK: Land NFT mint, created by mint_land_nft_to_holder
     #[account(
         mut,
-        token::authority = game_state,
-        seeds = [b"token_vault"],
-        bump = game_state.token_vault_bump
+        seeds = [b"land_mint", land_id.to_le_bytes().as_ref()],
+        bump,
     )]
-    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub land_mint: UncheckedAccount<'info>,
 
-    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Recipient's land NFT token account, created by mint_land_nft_to_holder
+    #[account(mut)]
+    pub recipient_land_token_account: UncheckedAccount<'info>,
+
+    pub nft_token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct AdminAction<'info> {
+#[instruction(land_id: u16)]
+pub struct AdminSetLevel<'info> {
     #[account(
         constraint = authority.key() == game_state.authority @ ColonyError::Unauthorized
     )]
     pub authority: Signer<'info>,
 
-    #[account(mut)]
     pub game_state: Account<'info, GameState>,
-}
-
-#[derive(Accounts)]
-pub struct MigrateV2<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
 
-    /// CHECK: Manual authority validation during migration (account may have old layout)
     #[account(
         mut,
-        seeds = [b"game_state"],
-        bump,
+        seeds = [b"land_data", land_id.to_le_bytes().as_ref()],
+        bump = land_data.bump
     )]
-    pub game_state: UncheckedAccount<'info>,
+    pub land_data: Account<'info, LandData>,
 
-    pub system_program: Program<'info, System>,
+    /// Land owner's profile (resources are settled to the owner)
+    #[account(
+        mut,
+        seeds = [b"user_profile", land_data.owner.as_ref()],
+        bump = owner_profile.bump
+    )]
+    pub owner_profile: Account<'info, UserProfile>,
 }
 
 #[derive(Accounts)]
@@ -769,6 +6228,39 @@ pub struct AdminCloseLand<'info> {
         bump = user_profile.bump
     )]
     pub user_profile: Account<'info, UserProfile>,
+
+    /// CHECK: Land NFT mint, empty for lands without an NFT; burned and closed otherwise
+    #[account(
+        mut,
+        seeds = [b"land_mint", land_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub land_mint: UncheckedAccount<'info>,
+
+    /// Land NFT holder, signs the burn while the NFT exists
+    pub holder: Option<Signer<'info>>,
+
+    /// CHECK: Rental PDA of the land, empty unless an offer or rental exists; closed otherwise
+    #[account(
+        mut,
+        seeds = [b"rental", land_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub rental: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the rental account rent, must be the offer creator (required with a rental)
+    #[account(mut)]
+    pub rental_owner: Option<UncheckedAccount<'info>>,
+
+    /// Token account holding the land NFT, required while the NFT exists
+    #[account(
+        mut,
+        token::mint = land_mint,
+        token::token_program = nft_token_program,
+    )]
+    pub holder_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub nft_token_program: Program<'info, Token2022>,
 }
 
 #[derive(Accounts)]
@@ -799,6 +6291,18 @@ pub struct GetPendingEarnings<'info> {
         bump = land_data.bump
     )]
     pub land_data: Account<'info, LandData>,
+
+    /// Land owner's profile, includes the owner's staking boost when given
+    #[account(
+        seeds = [b"user_profile", land_data.owner.as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Option<Account<'info, UserProfile>>,
+}
+
+#[derive(Accounts)]
+pub struct GetClaimStreak<'info> {
+    pub user_profile: Account<'info, UserProfile>,
 }
 
 // ============================================================================
@@ -809,7 +6313,7 @@ pub struct GetPendingEarnings<'info> {
 #[derive(InitSpace)]
 pub struct GameState {
     pub authority: Pubkey,        // 32 bytes
-    pub treasury_balance: u64,    // 8 bytes - DEPRECATED: unused, kept for account layout compatibility
+    pub total_revenue: u64,       // 8 bytes - game tokens paid for lands and upgrades (v3, was treasury_balance)
     pub total_lands_sold: u64,    // 8 bytes
     pub total_sol_collected: u64, // 8 bytes
     pub is_active: bool,          // 1 byte
@@ -817,6 +6321,36 @@ pub struct GameState {
     pub vault_bump: u8,           // 1 byte
     pub token_mint: Pubkey,       // 32 bytes - associated SPL token mint
     pub token_vault_bump: u8,     // 1 byte - token vault PDA bump
+    pub craft_rates: [u64; RESOURCE_COUNT], // 24 bytes - tokens per whole resource unit (0 = disabled)
+    #[max_len(MAX_LAND_NFT_URI_LEN)]
+    pub land_nft_uri: String,     // 4 + 128 bytes - land NFT metadata URI base
+    pub stake_vault_bump: u8,     // 1 byte - stake vault PDA bump
+    pub referral_bps: u16,        // 2 bytes - referrer's cut of referred claims (0 = disabled)
+    pub current_season: u32,      // 4 bytes - latest season ID (0 = none started)
+    pub season_open: bool,        // 1 byte - current season not yet closed
+    pub achievement_rewards: [u64; ACHIEVEMENT_COUNT], // 32 bytes - token reward per achievement (0 = disabled)
+    pub randomness_source: RandomnessSource, // 1 byte - source used to fulfil randomness requests
+    pub randomness_oracle: Pubkey, // 32 bytes - signer allowed to fulfil with the Oracle source
+    pub sale_phase: SalePhase,    // 1 byte - buy_land access (Public = open to everyone)
+    pub whitelist_root: [u8; 32], // 32 bytes - Merkle root of whitelisted wallets
+    pub whitelist_end: i64,       // 8 bytes - end of the whitelist window (public afterwards)
+    pub whitelist_purchase_cap: u8, // 1 byte - lands each wallet may buy during the whitelist window
+    pub purchase_cooldown: i64,   // 8 bytes - minimum seconds between a wallet's purchases (0 = disabled)
+    pub slot_purchase_cap: u16,   // 2 bytes - maximum purchases per slot across all wallets (0 = disabled)
+    pub last_purchase_slot: u64,  // 8 bytes - slot of the latest purchase (per-slot cap window)
+    pub slot_purchases: u16,      // 2 bytes - purchases made in last_purchase_slot
+    pub reserved_lands_enabled: bool, // 1 byte - reserved lands bitmap initialized (checked by buy_land)
+    pub reserved_lands_bump: u8,  // 1 byte - reserved lands bitmap PDA bump
+    pub land_price_lamports: u64, // 8 bytes - land price for buy_land_with_sol (0 = SOL payments disabled)
+    pub revenue_vault_bps: u16,   // 2 bytes - share of land/upgrade payments sent to the token vault
+    pub revenue_team_bps: u16,    // 2 bytes - share of land/upgrade payments sent to team_wallet
+    pub team_wallet: Pubkey,      // 32 bytes - game token account receiving the team share
+    pub token_migration: TokenMigration, // 1 byte - token mint migration step (Idle = none running)
+    pub pending_token_mint: Pubkey, // 32 bytes - mint being migrated to
+    pub resume_active: bool,      // 1 byte - is_active to restore when the migration ends
+    pub version: u8,              // 1 byte - layout version (see GAME_STATE_VERSION)
+    pub whitelist_window: u32,    // 4 bytes - id of the latest whitelist window
+    pub total_staked: u64,        // 8 bytes - tokens staked across all profiles
 }
 
 #[account]
@@ -828,6 +6362,16 @@ pub struct LandData {
     pub fixed_earnings: u64,  // 8 bytes
     pub last_checkout: i64,   // 8 bytes
     pub bump: u8,             // 1 byte
+    pub rental_end: i64,      // 8 bytes - end of the active rental (0 = not rented)
+    pub event_modifier_bps: i16, // 2 bytes - earnings modifier of the active random event
+    pub event_end: i64,       // 8 bytes - end of the active random event
+    pub last_event_request: i64, // 8 bytes - last random event request (cooldown)
+    pub defense_level: u8,    // 1 byte - defense buildings (0-3), lower raid success
+    pub protected_until: i64, // 8 bytes - raid protection after the last claim
+    pub last_raided_at: i64,  // 8 bytes - last raid on this land (cooldown)
+    pub mega_plot: u16,       // 2 bytes - anchor land_id of the mega-plot this land is merged into (0 = none)
+    pub mega_speed: u64,      // 8 bytes - combined mega-plot earning speed (anchor land only)
+    pub version: u8,          // 1 byte - layout version (see LAND_DATA_VERSION)
 }
 
 #[account]
@@ -835,10 +6379,226 @@ pub struct LandData {
 pub struct UserProfile {
     pub owner: Pubkey,        // 32 bytes
     pub lands_owned: u8,      // 1 byte
-    pub token_balance: u64,   // 8 bytes - legacy internal balance (unused with SPL tokens)
+    pub lands_purchased: u64, // 8 bytes - paid land purchases since v2, grants excluded (was token_balance)
+    pub bump: u8,             // 1 byte
+    pub resources: [u64; RESOURCE_COUNT], // 24 bytes - [ore, water, energy] balances
+    pub payout_address: Pubkey,   // 32 bytes - default claim destination owner (default = none)
+    pub staked_amount: u64,       // 8 bytes - tokens locked in the stake vault
+    pub stake_start: i64,         // 8 bytes - start of the staking boost window
+    pub stake_lock_end: i64,      // 8 bytes - lock end (boost stops here, or at early unstake)
+    pub stake_bonus_bps: u16,     // 2 bytes - earnings boost while staked
+    pub referrer: Pubkey,         // 32 bytes - player who referred this user (default = none)
+    pub referral_rewards: u64,    // 8 bytes - accrued referral rewards awaiting claim
+    pub guild: Pubkey,            // 32 bytes - guild membership (default = none)
+    pub season_id: u32,           // 4 bytes - season the score belongs to
+    pub season_score: u64,        // 8 bytes - tokens claimed + spent on upgrades this season
+    pub achievements: u32,        // 4 bytes - unlocked achievements bitmap (bit = Achievement)
+    pub achievements_claimed: u32, // 4 bytes - achievements whose reward was claimed
+    pub claim_streak: u16,        // 2 bytes - consecutive days with an owner claim
+    pub last_claim_day: i64,      // 8 bytes - UTC day (unix time / 86400) of the last claim
+    pub whitelist_purchases: u8,  // 1 byte - lands bought during whitelist_window
+    pub last_purchase_time: i64,  // 8 bytes - last buy_land (purchase cooldown)
+    pub version: u8,              // 1 byte - layout version (see USER_PROFILE_VERSION)
+    pub whitelist_window: u32,    // 4 bytes - whitelist window whitelist_purchases belongs to
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Guild {
+    pub leader: Pubkey,           // 32 bytes - founder, also the guild PDA seed
+    #[max_len(MAX_GUILD_NAME_LEN)]
+    pub name: String,             // 4 + 32 bytes
+    pub tax_bps: u16,             // 2 bytes - cut of members' claims sent to the guild vault
+    #[max_len(MAX_GUILD_MEMBERS)]
+    pub members: Vec<Pubkey>,     // 4 + 20 * 32 bytes
+    #[max_len(MAX_GUILD_MEMBERS)]
+    pub member_levels: Vec<u16>,  // 4 + 20 * 2 bytes - each member's land levels at last refresh
+    pub total_land_levels: u32,   // 4 bytes - sum of member_levels
+    pub bonus_bps: u16,           // 2 bytes - earnings bonus for members
+    pub bump: u8,                 // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct GuildInvite {
+    pub guild: Pubkey,            // 32 bytes
+    pub member: Pubkey,           // 32 bytes - invited wallet
+    pub bump: u8,                 // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Rental {
+    pub land_id: u16,         // 2 bytes
+    pub owner: Pubkey,        // 32 bytes - owner who created the offer
+    pub renter: Pubkey,       // 32 bytes - Pubkey::default() = open offer until accepted
+    pub duration: i64,        // 8 bytes - rental length in seconds
+    pub upfront_fee: u64,     // 8 bytes - tokens paid by the renter to the owner on accept
+    pub owner_share_bps: u16, // 2 bytes - owner's cut of each renter claim
+    pub start_time: i64,      // 8 bytes - 0 until accepted
+    pub end_time: i64,        // 8 bytes
     pub bump: u8,             // 1 byte
 }
 
+#[account]
+#[derive(InitSpace)]
+pub struct RandomnessRequest {
+    pub land_id: u16,             // 2 bytes
+    pub purpose: RandomnessPurpose, // 1 byte - also part of the PDA seeds
+    pub requester: Pubkey,        // 32 bytes - pays the request rent, refunded on fulfilment or cancel
+    pub request_slot: u64,        // 8 bytes - fulfilment must happen in a later slot
+    pub created_at: i64,          // 8 bytes
+    pub bump: u8,                 // 1 byte
+    pub attacker_land_id: u16,    // 2 bytes - raiding land (Raid requests only)
+}
+
+/// Land_ids withheld from buy_land (zero-copy: the bitmap is too large to deserialize per purchase)
+#[account(zero_copy)]
+pub struct ReservedLands {
+    pub bits: [u8; RESERVED_LANDS_BITMAP_LEN], // 2625 bytes - bit (land_id - 1) set = reserved
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentMint {
+    pub mint: Pubkey,             // 32 bytes - accepted payment mint
+    pub price_per_token: u64,     // 8 bytes - payment base units per whole game token (0 = disabled)
+    pub treasury: Pubkey,         // 32 bytes - token account receiving payments
+    pub bump: u8,                 // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MegaPlot {
+    pub owner: Pubkey,            // 32 bytes
+    pub anchor_land_id: u16,      // 2 bytes - lowest merged land_id (carries the earning stream)
+    #[max_len(MAX_MEGA_PLOT_LANDS)]
+    pub land_ids: Vec<u16>,       // 4 + 2 * 4 bytes - merged lands in increasing order
+    pub earning_speed: u64,       // 8 bytes - combined speed including the merge bonus
+    pub created_at: i64,          // 8 bytes
+    pub bump: u8,                 // 1 byte
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Season {
+    pub season_id: u32,           // 4 bytes
+    pub start_time: i64,          // 8 bytes
+    pub end_time: i64,            // 8 bytes
+    pub prize_pool: u64,          // 8 bytes - claimed from token vault by the winners after close
+    #[max_len(LEADERBOARD_SIZE)]
+    pub leaderboard: Vec<LeaderboardEntry>, // 4 + 10 * 40 bytes - sorted by score, highest first
+    pub closed: bool,             // 1 byte
+    pub bump: u8,                 // 1 byte
+    pub prizes_claimed: u16,      // 2 bytes - bit per leaderboard rank whose prize was claimed
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct LeaderboardEntry {
+    pub player: Pubkey,
+    pub score: u64,
+}
+
+/// Resource types produced by lands
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resource {
+    Ore,
+    Water,
+    Energy,
+}
+
+/// Who may buy land
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum SalePhase {
+    Public,
+    Whitelist,
+}
+
+/// Token mint migration step: Draining waits for the old vaults to be emptied, VaultsClosed
+/// for the new mint's vault to be created
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum TokenMigration {
+    Idle,
+    Draining,
+    VaultsClosed,
+}
+
+/// Where random values come from (Deterministic is predictable, `test-randomness` builds only)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum RandomnessSource {
+    Disabled,
+    Oracle,
+    Deterministic,
+}
+
+/// Payment that produced a RevenueCollected event
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevenueSource {
+    LandPurchase,
+    Upgrade,
+}
+
+/// What a randomness request is used for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum RandomnessPurpose {
+    LandEvent,
+    Raid,
+}
+
+/// Outcome of a random land event
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LandEvent {
+    None,
+    MeteorStrike,
+    ResourceDiscovery,
+}
+
+/// Milestones tracked in `UserProfile::achievements` (discriminant = bit index)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Achievement {
+    FirstLand,
+    Level5,
+    TenLands,
+    ClaimStreak30,
+}
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+/// Audit record of an admin land grant
+#[event]
+pub struct LandGranted {
+    pub authority: Pubkey,
+    pub land_id: u16,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Audit record of an admin level change
+#[event]
+pub struct LandLevelSet {
+    pub authority: Pubkey,
+    pub land_id: u16,
+    pub owner: Pubkey,
+    pub old_level: u8,
+    pub new_level: u8,
+    pub settled_earnings: u64,
+    pub timestamp: i64,
+}
+
+/// How a land purchase or upgrade payment was split
+#[event]
+pub struct RevenueCollected {
+    pub payer: Pubkey,
+    pub source: RevenueSource,
+    pub land_id: u16,
+    pub burned: u64,
+    pub to_vault: u64,
+    pub to_team: u64,
+    pub timestamp: i64,
+}
+
 // ============================================================================
 // ERRORS
 // ============================================================================
@@ -875,4 +6635,150 @@ pub enum ColonyError {
     InvalidTokenMint,
     #[msg("User still has lands owned")]
     UserHasLands,
+    #[msg("Insufficient resources")]
+    InsufficientResources,
+    #[msg("Crafting is disabled for this resource")]
+    CraftingDisabled,
+    #[msg("Token account does not hold the land NFT")]
+    NotNftHolder,
+    #[msg("Land owner already matches the NFT holder")]
+    OwnerAlreadySynced,
+    #[msg("URI is too long")]
+    UriTooLong,
+    #[msg("Invalid rental terms")]
+    InvalidRentalTerms,
+    #[msg("Rental is already active")]
+    RentalAlreadyActive,
+    #[msg("You are not the renter of this land")]
+    NotRenter,
+    #[msg("Land is rented, rental account required")]
+    RentalAccountRequired,
+    #[msg("Rental offer was made by a previous owner")]
+    RentalOfferStale,
+    #[msg("Owner token account required")]
+    OwnerTokenAccountRequired,
+    #[msg("Renter token account required")]
+    RenterTokenAccountRequired,
+    #[msg("Token account has the wrong owner")]
+    InvalidTokenAccountOwner,
+    #[msg("Payout token account required by the default payout address")]
+    PayoutAccountRequired,
+    #[msg("Payout token account is not owned by the default payout address")]
+    PayoutAddressMismatch,
+    #[msg("Land is rented, its earnings belong to the renter")]
+    LandRented,
+    #[msg("Invalid stake duration")]
+    InvalidStakeDuration,
+    #[msg("Stake is already active")]
+    StakeAlreadyActive,
+    #[msg("No active stake")]
+    NoActiveStake,
+    #[msg("Cannot refer yourself")]
+    InvalidReferrer,
+    #[msg("Invalid guild name")]
+    InvalidGuildName,
+    #[msg("Guild tax is too high")]
+    InvalidGuildTax,
+    #[msg("Already in a guild")]
+    AlreadyInGuild,
+    #[msg("Guild is full")]
+    GuildFull,
+    #[msg("Not a member of this guild")]
+    NotGuildMember,
+    #[msg("Guild leader cannot leave the guild")]
+    GuildLeaderCannotLeave,
+    #[msg("Guild account required")]
+    GuildAccountRequired,
+    #[msg("Lands must be passed in increasing land ID order")]
+    InvalidLandOrder,
+    #[msg("All of the member's lands must be passed")]
+    LandCountMismatch,
+    #[msg("Current season is still open")]
+    SeasonOpen,
+    #[msg("Season has not ended yet")]
+    SeasonNotEnded,
+    #[msg("Season is already closed")]
+    SeasonClosed,
+    #[msg("No prize for this player in the season")]
+    NoSeasonPrize,
+    #[msg("Achievement not unlocked")]
+    AchievementLocked,
+    #[msg("Achievement reward already claimed")]
+    AchievementAlreadyClaimed,
+    #[msg("Randomness source is disabled")]
+    RandomnessDisabled,
+    #[msg("Randomness request cannot be fulfilled in the same slot")]
+    RandomnessNotReady,
+    #[msg("Land event was requested too recently")]
+    LandEventCooldown,
+    #[msg("Cannot raid your own land")]
+    CannotRaidOwnLand,
+    #[msg("Land is protected from raids after a claim")]
+    LandProtected,
+    #[msg("Land was raided too recently")]
+    RaidCooldown,
+    #[msg("A mega-plot needs 2-4 lands")]
+    InvalidMegaPlot,
+    #[msg("Lands are not adjacent")]
+    LandsNotAdjacent,
+    #[msg("Land is merged into a mega-plot")]
+    LandMerged,
+    #[msg("Invalid sale phase configuration")]
+    InvalidSalePhase,
+    #[msg("Wallet is not whitelisted")]
+    NotWhitelisted,
+    #[msg("Whitelist purchase cap reached")]
+    WhitelistCapReached,
+    #[msg("Purchases are too frequent, wait for the cooldown")]
+    PurchaseCooldown,
+    #[msg("Too many land purchases in this slot, try again")]
+    SlotPurchaseCapReached,
+    #[msg("Land is reserved")]
+    LandReserved,
+    #[msg("Reserved lands account required")]
+    ReservedLandsAccountRequired,
+    #[msg("Invalid land level")]
+    InvalidLevel,
+    #[msg("Buying land with SOL is disabled")]
+    SolPaymentDisabled,
+    #[msg("Payment mint is disabled")]
+    PaymentMintDisabled,
+    #[msg("Invalid treasury token account")]
+    InvalidTreasury,
+    #[msg("Invalid revenue split")]
+    InvalidRevenueSplit,
+    #[msg("Token vault or team token account required for the revenue split")]
+    RevenueAccountRequired,
+    #[msg("Invalid team wallet token account")]
+    InvalidTeamWallet,
+    #[msg("Mint uses an unsupported Token-2022 extension")]
+    UnsupportedMintExtension,
+    #[msg("A token mint migration is in progress")]
+    TokenMigrationInProgress,
+    #[msg("Wrong token mint migration step")]
+    InvalidTokenMigrationStep,
+    #[msg("Vault must be drained before the migration")]
+    VaultNotEmpty,
+    #[msg("Vault account required")]
+    VaultAccountRequired,
+    #[msg("Unknown account layout version")]
+    UnknownAccountVersion,
+    #[msg("Land NFT mint cannot be burned and closed by the program")]
+    LandMintNotClosable,
+    #[msg("Rental has expired, end it to settle the renter's earnings first")]
+    RentalNotEnded,
+    #[msg("Guild still has other members")]
+    GuildNotEmpty,
+    #[msg("Randomness request has not expired yet")]
+    RandomnessRequestNotExpired,
+    #[msg("Randomness source is not available in this build")]
+    RandomnessSourceUnavailable,
+    #[msg("Token mint must have TOKEN_DECIMALS decimals")]
+    InvalidTokenDecimals,
+    #[msg("Stakes must be returned first")]
+    StakesOutstanding,
+    #[msg("Season prize already claimed")]
+    SeasonPrizeClaimed,
+    #[msg("The rental owner account is required")]
+    RentalOwnerRequired,
 }
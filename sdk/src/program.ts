@@ -12,6 +12,7 @@ import {
   TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
+import { sha256 } from "@noble/hashes/sha256";
 import { IDL, PROGRAM_ID } from "./idl";
 
 // ============================================================================
@@ -24,11 +25,90 @@ export const GAME_TOKEN_MINT = new PublicKey("2pXjxbdHnYWtH2gtDN495Ve1jm8bs1zoUL
 export const MAX_LAND_ID = 21000;
 export const MAX_LANDS_PER_USER = 10;
 export const MIN_LAND_ID = 1;
+export const RESERVED_LANDS_BITMAP_LEN = Math.ceil(MAX_LAND_ID / 8);
 export const MAX_LEVEL = 10;
 export const TOKEN_DECIMALS = 6;
 export const TOKEN_MULTIPLIER = 1_000_000;
 export const LAND_PRICE_TOKENS = 10_000 * TOKEN_MULTIPLIER; // 10,000 tokens (burned on purchase)
 export const SECONDS_PER_DAY = 86400;
+export const LANDS_PER_REGION = 1000;
+export const BPS_DENOMINATOR = 10_000;
+export const MIN_RENTAL_DURATION = SECONDS_PER_DAY;
+export const MAX_RENTAL_DURATION = 90 * SECONDS_PER_DAY;
+export const MIN_STAKE_DURATION = 7 * SECONDS_PER_DAY;
+export const MAX_STAKE_DURATION = 365 * SECONDS_PER_DAY;
+export const STAKE_FULL_BOOST_AMOUNT = 100_000 * TOKEN_MULTIPLIER;
+export const MAX_STAKE_BONUS_BPS = 5_000; // +50% earnings
+export const EARLY_UNSTAKE_PENALTY_BPS = 2_000; // 20%, sent to token vault
+export const MAX_GUILD_MEMBERS = 20;
+export const MAX_GUILD_NAME_LEN = 32;
+export const MAX_GUILD_TAX_BPS = 2_000; // 20%
+export const GUILD_BONUS_BPS_PER_LEVEL = 5;
+export const MAX_GUILD_BONUS_BPS = 1_000; // +10%
+/** Achievements in bit order of UserProfile.achievements */
+export const ACHIEVEMENTS = ["firstLand", "level5", "tenLands", "claimStreak30"] as const;
+export type Achievement = (typeof ACHIEVEMENTS)[number];
+export const ACHIEVEMENT_LEVEL = 5;
+export const ACHIEVEMENT_STREAK_DAYS = 30;
+export const STREAK_BONUS_BPS_PER_DAY = 100; // +1% per consecutive claim day after the first
+export const MAX_STREAK_BONUS_BPS = 2_000; // +20%
+
+export const LAND_EVENT_COOLDOWN = SECONDS_PER_DAY;
+export const LAND_EVENT_DURATION = SECONDS_PER_DAY;
+export const METEOR_STRIKE_MODIFIER_BPS = -5_000;
+export const RESOURCE_DISCOVERY_MODIFIER_BPS = 5_000;
+
+/** Who may buy land (whitelist reverts to public after `whitelistEnd`) */
+export type SalePhase = "public" | "whitelist";
+export const MAX_WHITELIST_PROOF_LEN = 20;
+
+/** Current account layout versions (0 on chain = deployed account from before the version byte) */
+export const GAME_STATE_VERSION = 3;
+export const LAND_DATA_VERSION = 2;
+export const USER_PROFILE_VERSION = 2;
+const ACCOUNT_VERSIONS = {
+  gameState: GAME_STATE_VERSION,
+  landData: LAND_DATA_VERSION,
+  userProfile: USER_PROFILE_VERSION,
+};
+
+/** Token mint migration steps ("idle" = no migration running) */
+export type TokenMigration = "idle" | "draining" | "vaultsClosed";
+
+/** Randomness sources (Deterministic is predictable, only in `test-randomness` builds) */
+export type RandomnessSource = "disabled" | "oracle" | "deterministic";
+
+/** Randomness request purposes, in on-chain discriminant order (used in PDA seeds) */
+export const RANDOMNESS_PURPOSES = ["landEvent", "raid"] as const;
+export type RandomnessPurpose = (typeof RANDOMNESS_PURPOSES)[number];
+
+export const RAID_COST = 100 * TOKEN_MULTIPLIER;
+export const RANDOMNESS_REQUEST_EXPIRY_SLOTS = 9_000;
+export const RAID_STEAL_BPS = 2_000; // 20% of the target's pending earnings
+export const RAID_COOLDOWN = SECONDS_PER_DAY;
+export const RAID_PROTECTION_WINDOW = 6 * 3600; // after each claim
+export const RAID_BASE_CHANCE = 50;
+export const RAID_CHANCE_PER_LEVEL = 5;
+export const RAID_CHANCE_PER_DEFENSE = 10;
+export const RAID_MIN_CHANCE = 5;
+export const RAID_MAX_CHANCE = 95;
+/** Defense building costs in tokens (index 0 = level 0→1) */
+export const DEFENSE_COSTS = [
+  500 * TOKEN_MULTIPLIER, // 0 → 1
+  1_500 * TOKEN_MULTIPLIER, // 1 → 2
+  4_000 * TOKEN_MULTIPLIER, // 2 → 3
+];
+export const MAX_DEFENSE_LEVEL = 3;
+
+/** Land grid: 21000 lands = 210 columns × 100 rows */
+export const LAND_GRID_WIDTH = 210;
+export const MIN_MEGA_PLOT_LANDS = 2;
+export const MAX_MEGA_PLOT_LANDS = 4;
+export const MEGA_PLOT_BONUS_BPS_PER_LAND = 500; // +5% per land beyond the first
+
+export const LEADERBOARD_SIZE = 10;
+/** Season prize pool split by leaderboard rank (bps) */
+export const SEASON_PRIZE_SPLIT_BPS = [3_000, 2_000, 1_500, 1_000, 800, 600, 400, 300, 200, 200];
 
 /** Mining launch time: 2026-02-17 16:00 CET */
 export const MINING_START_TIME = 1771340400;
@@ -61,6 +141,40 @@ export const EARNING_SPEEDS = [
   79_000 * TOKEN_MULTIPLIER, // Level 10
 ];
 
+/** Resource types, in on-chain order */
+export const RESOURCES = ["ore", "water", "energy"] as const;
+export type Resource = (typeof RESOURCES)[number];
+
+/** Multiplier for resource amounts (3 decimals) */
+export const RESOURCE_MULTIPLIER = 1_000;
+
+/** Resource production per day as [ore, water, energy] (index 0 = level 1) */
+export const RESOURCE_SPEEDS = [
+  [10, 5, 2], // Level 1
+  [20, 10, 4], // Level 2
+  [30, 15, 6], // Level 3
+  [50, 25, 10], // Level 4
+  [80, 40, 16], // Level 5
+  [130, 65, 26], // Level 6
+  [210, 105, 42], // Level 7
+  [340, 170, 68], // Level 8
+  [450, 225, 90], // Level 9
+  [790, 395, 158], // Level 10
+].map((speeds) => speeds.map((s) => s * RESOURCE_MULTIPLIER));
+
+/** Resources consumed by upgrades as [ore, water, energy] (index 0 = level 1→2) */
+export const UPGRADE_RESOURCE_COSTS = [
+  [0, 0, 0], // 1 → 2
+  [20, 10, 4], // 2 → 3
+  [60, 30, 12], // 3 → 4
+  [150, 75, 30], // 4 → 5
+  [320, 160, 64], // 5 → 6
+  [650, 325, 130], // 6 → 7
+  [1_260, 630, 252], // 7 → 8
+  [2_380, 1_190, 476], // 8 → 9
+  [3_600, 1_800, 720], // 9 → 10
+].map((costs) => costs.map((c) => c * RESOURCE_MULTIPLIER));
+
 // ============================================================================
 // TYPES
 // ============================================================================
@@ -72,7 +186,8 @@ export interface ColonyClientConfig {
 
 export interface GameState {
   authority: PublicKey;
-  treasuryBalance: BN;
+  /** Game tokens paid for lands and upgrades (stale until the account reaches v3) */
+  totalRevenue: BN;
   totalLandsSold: BN;
   totalSolCollected: BN;
   isActive: boolean;
@@ -80,6 +195,35 @@ export interface GameState {
   vaultBump: number;
   tokenMint: PublicKey;
   tokenVaultBump: number;
+  craftRates: BN[];
+  landNftUri: string;
+  stakeVaultBump: number;
+  referralBps: number;
+  currentSeason: number;
+  seasonOpen: boolean;
+  achievementRewards: BN[];
+  randomnessSource: RandomnessSource;
+  randomnessOracle: PublicKey;
+  salePhase: SalePhase;
+  whitelistRoot: number[];
+  whitelistEnd: BN;
+  whitelistPurchaseCap: number;
+  purchaseCooldown: BN;
+  slotPurchaseCap: number;
+  lastPurchaseSlot: BN;
+  slotPurchases: number;
+  reservedLandsEnabled: boolean;
+  reservedLandsBump: number;
+  landPriceLamports: BN;
+  revenueVaultBps: number;
+  revenueTeamBps: number;
+  teamWallet: PublicKey;
+  tokenMigration: TokenMigration;
+  pendingTokenMint: PublicKey;
+  resumeActive: boolean;
+  version: number;
+  whitelistWindow: number;
+  totalStaked: BN;
 }
 
 export interface LandData {
@@ -89,12 +233,138 @@ export interface LandData {
   fixedEarnings: BN;
   lastCheckout: BN;
   bump: number;
+  rentalEnd: BN;
+  eventModifierBps: number;
+  eventEnd: BN;
+  lastEventRequest: BN;
+  defenseLevel: number;
+  protectedUntil: BN;
+  lastRaidedAt: BN;
+  megaPlot: number;
+  megaSpeed: BN;
+  version: number;
+}
+
+export interface RandomnessRequest {
+  landId: number;
+  purpose: RandomnessPurpose;
+  requester: PublicKey;
+  requestSlot: BN;
+  createdAt: BN;
+  bump: number;
+  attackerLandId: number;
+}
+
+export interface Rental {
+  landId: number;
+  owner: PublicKey;
+  renter: PublicKey;
+  duration: BN;
+  upfrontFee: BN;
+  ownerShareBps: number;
+  startTime: BN;
+  endTime: BN;
+  bump: number;
 }
 
 export interface UserProfile {
   owner: PublicKey;
   landsOwned: number;
-  tokenBalance: BN;
+  /** Paid land purchases since profile v2, grants excluded (stale until the account reaches v2) */
+  landsPurchased: BN;
+  bump: number;
+  resources: BN[];
+  payoutAddress: PublicKey;
+  stakedAmount: BN;
+  stakeStart: BN;
+  stakeLockEnd: BN;
+  stakeBonusBps: number;
+  referrer: PublicKey;
+  referralRewards: BN;
+  guild: PublicKey;
+  seasonId: number;
+  seasonScore: BN;
+  achievements: number;
+  achievementsClaimed: number;
+  claimStreak: number;
+  lastClaimDay: BN;
+  whitelistPurchases: number;
+  lastPurchaseTime: BN;
+  version: number;
+  whitelistWindow: number;
+}
+
+export interface LeaderboardEntry {
+  player: PublicKey;
+  score: BN;
+}
+
+export interface Season {
+  seasonId: number;
+  startTime: BN;
+  endTime: BN;
+  prizePool: BN;
+  leaderboard: LeaderboardEntry[];
+  closed: boolean;
+  bump: number;
+  /** Bit per leaderboard rank whose prize was claimed */
+  prizesClaimed: number;
+}
+
+/** Audit event emitted by admin_grant_land */
+export interface LandGrantedEvent {
+  authority: PublicKey;
+  landId: number;
+  recipient: PublicKey;
+  timestamp: BN;
+}
+
+/** Audit event emitted by admin_set_level */
+export interface LandLevelSetEvent {
+  authority: PublicKey;
+  landId: number;
+  owner: PublicKey;
+  oldLevel: number;
+  newLevel: number;
+  settledEarnings: BN;
+  timestamp: BN;
+}
+
+/** Emitted by buy_land and upgrade_land with how the payment was split */
+export interface RevenueCollectedEvent {
+  payer: PublicKey;
+  source: { landPurchase?: {} } | { upgrade?: {} };
+  landId: number;
+  burned: BN;
+  toVault: BN;
+  toTeam: BN;
+  timestamp: BN;
+}
+
+export interface PaymentMint {
+  mint: PublicKey;
+  pricePerToken: BN;
+  treasury: PublicKey;
+  bump: number;
+}
+
+export interface MegaPlot {
+  owner: PublicKey;
+  anchorLandId: number;
+  landIds: number[];
+  earningSpeed: BN;
+  createdAt: BN;
+  bump: number;
+}
+
+export interface Guild {
+  leader: PublicKey;
+  name: string;
+  taxBps: number;
+  members: PublicKey[];
+  memberLevels: number[];
+  totalLandLevels: number;
+  bonusBps: number;
   bump: number;
 }
 
@@ -147,6 +417,13 @@ export function tokenVaultPda(programId: PublicKey = new PublicKey(PROGRAM_ID))
   );
 }
 
+/** Convert u32 to little-endian bytes */
+function u32ToLeBytes(value: number): Uint8Array {
+  const bytes = new Uint8Array(4);
+  new DataView(bytes.buffer).setUint32(0, value, true);
+  return bytes;
+}
+
 /** Convert u16 to little-endian bytes */
 function u16ToLeBytes(value: number): Uint8Array {
   const bytes = new Uint8Array(2);
@@ -165,6 +442,116 @@ export function landDataPda(
   );
 }
 
+export function landMintPda(
+  landId: number,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("land_mint"), u16ToLeBytes(landId)],
+    programId
+  );
+}
+
+export function randomnessRequestPda(
+  purpose: RandomnessPurpose,
+  landId: number,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [
+      encoder.encode("randomness"),
+      new Uint8Array([RANDOMNESS_PURPOSES.indexOf(purpose)]),
+      u16ToLeBytes(landId),
+    ],
+    programId
+  );
+}
+
+export function seasonPda(
+  seasonId: number,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("season"), u32ToLeBytes(seasonId)],
+    programId
+  );
+}
+
+export function paymentMintPda(
+  mint: PublicKey,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("payment_mint"), mint.toBytes()],
+    programId
+  );
+}
+
+export function megaPlotPda(
+  anchorLandId: number,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("mega_plot"), u16ToLeBytes(anchorLandId)],
+    programId
+  );
+}
+
+export function guildPda(
+  leader: PublicKey,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("guild"), leader.toBytes()],
+    programId
+  );
+}
+
+export function guildVaultPda(
+  guild: PublicKey,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("guild_vault"), guild.toBytes()],
+    programId
+  );
+}
+
+export function guildInvitePda(
+  guild: PublicKey,
+  member: PublicKey,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("guild_invite"), guild.toBytes(), member.toBytes()],
+    programId
+  );
+}
+
+export function stakeVaultPda(programId: PublicKey = new PublicKey(PROGRAM_ID)) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("stake_vault")],
+    programId
+  );
+}
+
+export function reservedLandsPda(programId: PublicKey = new PublicKey(PROGRAM_ID)) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("reserved_lands")],
+    programId
+  );
+}
+
+export function rentalPda(
+  landId: number,
+  programId: PublicKey = new PublicKey(PROGRAM_ID)
+) {
+  return PublicKey.findProgramAddressSync(
+    [encoder.encode("rental"), u16ToLeBytes(landId)],
+    programId
+  );
+}
+
 export function userProfilePda(
   user: PublicKey,
   programId: PublicKey = new PublicKey(PROGRAM_ID)
@@ -182,20 +569,243 @@ export function userProfilePda(
 /**
  * Calculate pending earnings for a land
  */
+/**
+ * Raid success chance in percent (mirrors the on-chain raid_success_chance)
+ */
+export function getRaidSuccessChance(attacker: LandData, target: LandData): number {
+  const chance =
+    RAID_BASE_CHANCE +
+    (attacker.level - target.level) * RAID_CHANCE_PER_LEVEL -
+    target.defenseLevel * RAID_CHANCE_PER_DEFENSE;
+  return Math.min(RAID_MAX_CHANCE, Math.max(RAID_MIN_CHANCE, chance));
+}
+
+/**
+ * Whether a land can currently be raided (protection window and cooldown elapsed)
+ */
+export function isRaidable(land: LandData, currentTime: number): boolean {
+  return (
+    currentTime >= land.protectedUntil.toNumber() &&
+    currentTime >= land.lastRaidedAt.toNumber() + RAID_COOLDOWN
+  );
+}
+
+/**
+ * Amount of an alternative payment mint charged for a price in game tokens (base units).
+ * This is what the treasury receives; the payer also covers any Token-2022 transfer fee.
+ */
+export function getPaymentAmount(paymentMint: PaymentMint, gameTokenAmount: BN): BN {
+  return gameTokenAmount.mul(paymentMint.pricePerToken).divn(TOKEN_MULTIPLIER);
+}
+
+/**
+ * Split a land purchase or upgrade payment the way the program does: burned, to the token
+ * vault and to the team wallet
+ */
+export function getRevenueSplit(
+  gameState: GameState,
+  amount: BN
+): { burned: BN; toVault: BN; toTeam: BN } {
+  const toVault = amount.muln(gameState.revenueVaultBps).divn(BPS_DENOMINATOR);
+  const toTeam = amount.muln(gameState.revenueTeamBps).divn(BPS_DENOMINATOR);
+  return { burned: amount.sub(toVault).sub(toTeam), toVault, toTeam };
+}
+
+/**
+ * Seconds until a wallet may buy land again under the per-wallet purchase cooldown
+ */
+export function getPurchaseCooldownRemaining(
+  gameState: GameState,
+  profile: UserProfile | null,
+  currentTime: number
+): number {
+  const cooldown = gameState.purchaseCooldown.toNumber();
+  if (cooldown === 0 || !profile || profile.lastPurchaseTime.isZero()) {
+    return 0;
+  }
+  return Math.max(0, profile.lastPurchaseTime.toNumber() + cooldown - currentTime);
+}
+
+function hashPair(a: Uint8Array, b: Uint8Array): Uint8Array {
+  const [first, second] = Buffer.compare(Buffer.from(a), Buffer.from(b)) <= 0 ? [a, b] : [b, a];
+  const data = new Uint8Array(64);
+  data.set(first);
+  data.set(second, 32);
+  return sha256(data);
+}
+
+/**
+ * Build the whitelist Merkle tree (leaves are sha256(wallet), parents hash the sorted pair of
+ * children, matching the on-chain verifier). Returns the root and a proof builder.
+ */
+export function buildWhitelistTree(wallets: PublicKey[]): {
+  root: Uint8Array;
+  getProof: (wallet: PublicKey) => Uint8Array[];
+} {
+  if (wallets.length === 0) throw new Error("Whitelist is empty");
+
+  const levels: Uint8Array[][] = [wallets.map((wallet) => sha256(wallet.toBytes()))];
+  while (levels[levels.length - 1].length > 1) {
+    const level = levels[levels.length - 1];
+    const parents: Uint8Array[] = [];
+    for (let i = 0; i < level.length; i += 2) {
+      // An odd node out is promoted unchanged
+      parents.push(i + 1 < level.length ? hashPair(level[i], level[i + 1]) : level[i]);
+    }
+    levels.push(parents);
+  }
+
+  const getProof = (wallet: PublicKey): Uint8Array[] => {
+    let index = wallets.findIndex((w) => w.equals(wallet));
+    if (index < 0) throw new Error("Wallet is not whitelisted");
+
+    const proof: Uint8Array[] = [];
+    for (const level of levels.slice(0, -1)) {
+      const sibling = index ^ 1;
+      if (sibling < level.length) proof.push(level[sibling]);
+      index >>= 1;
+    }
+    return proof;
+  };
+
+  return { root: levels[levels.length - 1][0], getProof };
+}
+
+/**
+ * Token earning speed of a land: its level speed, or the mega-plot's combined speed on the
+ * anchor land (other merged lands don't earn)
+ */
+export function getLandEarningSpeed(land: LandData): BN {
+  return land.megaPlot === 0 ? new BN(EARNING_SPEEDS[land.level - 1]) : land.megaSpeed;
+}
+
+/**
+ * Grid position of a land
+ */
+export function getLandPosition(landId: number): { x: number; y: number } {
+  return { x: (landId - 1) % LAND_GRID_WIDTH, y: Math.floor((landId - 1) / LAND_GRID_WIDTH) };
+}
+
+/**
+ * Whether the lands form one edge-connected group on the grid (mega-plot requirement)
+ */
+export function areLandsConnected(landIds: number[]): boolean {
+  if (landIds.length === 0) return false;
+  const adjacent = (a: number, b: number) => {
+    const pa = getLandPosition(a);
+    const pb = getLandPosition(b);
+    return Math.abs(pa.x - pb.x) + Math.abs(pa.y - pb.y) === 1;
+  };
+
+  const reached = new Set([0]);
+  const stack = [0];
+  while (stack.length > 0) {
+    const i = stack.pop()!;
+    landIds.forEach((landId, j) => {
+      if (!reached.has(j) && adjacent(landIds[i], landId)) {
+        reached.add(j);
+        stack.push(j);
+      }
+    });
+  }
+  return reached.size === landIds.length;
+}
+
 export function calculateEarnings(land: LandData, currentTime: number): BN {
   if (currentTime < MINING_START_TIME) {
     return land.fixedEarnings;
   }
 
   const timePassed = new BN(currentTime).sub(land.lastCheckout);
-  const speed = new BN(EARNING_SPEEDS[land.level - 1]);
+  const speed = getLandEarningSpeed(land);
+
+  let earned = speed.mul(timePassed).div(new BN(SECONDS_PER_DAY));
+
+  // Random event modifier over the part of the period the event was active
+  const eventEnd = BN.min(new BN(currentTime), land.eventEnd);
+  if (land.eventModifierBps !== 0 && eventEnd.gt(land.lastCheckout)) {
+    const affected = speed.mul(eventEnd.sub(land.lastCheckout)).div(new BN(SECONDS_PER_DAY));
+    const delta = affected.muln(Math.abs(land.eventModifierBps)).divn(BPS_DENOMINATOR);
+    earned = land.eventModifierBps > 0 ? earned.add(delta) : BN.max(earned.sub(delta), new BN(0));
+  }
+
+  return earned.add(land.fixedEarnings);
+}
 
-  const earned = speed
-    .mul(timePassed)
+/**
+ * Calculate the staking boost a land earns from its owner's stake
+ */
+export function calculateStakeBonus(
+  land: LandData,
+  ownerProfile: UserProfile,
+  currentTime: number
+): BN {
+  if (ownerProfile.stakeBonusBps === 0 || currentTime < MINING_START_TIME) {
+    return new BN(0);
+  }
+
+  const start = BN.max(land.lastCheckout, ownerProfile.stakeStart);
+  const end = BN.min(new BN(currentTime), ownerProfile.stakeLockEnd);
+  if (end.lte(start)) {
+    return new BN(0);
+  }
+
+  return getLandEarningSpeed(land)
+    .mul(end.sub(start))
     .div(new BN(SECONDS_PER_DAY))
-    .add(land.fixedEarnings);
+    .muln(ownerProfile.stakeBonusBps)
+    .divn(BPS_DENOMINATOR);
+}
+
+/**
+ * Calculate pending earnings for a land including the owner's staking boost
+ */
+export function calculateBoostedEarnings(
+  land: LandData,
+  ownerProfile: UserProfile,
+  currentTime: number
+): BN {
+  return calculateEarnings(land, currentTime).add(
+    calculateStakeBonus(land, ownerProfile, currentTime)
+  );
+}
+
+/**
+ * Get the earnings boost (bps) for staking `amount` for `duration` seconds
+ */
+export function getStakeBonusBps(amount: BN, duration: number): number {
+  const amountWeight = BN.min(amount, new BN(STAKE_FULL_BOOST_AMOUNT));
+  const durationWeight = new BN(Math.min(duration, MAX_STAKE_DURATION));
+  return new BN(MAX_STAKE_BONUS_BPS)
+    .mul(amountWeight)
+    .mul(durationWeight)
+    .div(new BN(STAKE_FULL_BOOST_AMOUNT).mul(new BN(MAX_STAKE_DURATION)))
+    .toNumber();
+}
+
+/**
+ * Calculate pending resources for a land as [ore, water, energy] (merged lands other than the
+ * anchor produce nothing)
+ */
+export function calculateResources(land: LandData, currentTime: number): BN[] {
+  if (currentTime < MINING_START_TIME || (land.megaPlot !== 0 && land.landId !== land.megaPlot)) {
+    return RESOURCES.map(() => new BN(0));
+  }
+
+  const timePassed = new BN(currentTime).sub(land.lastCheckout);
+  return RESOURCE_SPEEDS[land.level - 1].map((speed) =>
+    new BN(speed).mul(timePassed).div(new BN(SECONDS_PER_DAY))
+  );
+}
 
-  return earned;
+/**
+ * Get resource cost for next level as [ore, water, energy]
+ */
+export function getUpgradeResourceCost(currentLevel: number): BN[] {
+  if (currentLevel < 1 || currentLevel >= MAX_LEVEL) {
+    return RESOURCES.map(() => new BN(0));
+  }
+  return UPGRADE_RESOURCE_COSTS[currentLevel - 1].map((c) => new BN(c));
 }
 
 /**
@@ -218,6 +828,35 @@ export function getEarningSpeed(level: number): BN {
   return new BN(EARNING_SPEEDS[level - 1]);
 }
 
+/**
+ * Get the region (1-21) a land belongs to
+ */
+export function getLandRegion(landId: number): number {
+  return Math.floor((landId - 1) / LANDS_PER_REGION) + 1;
+}
+
+/**
+ * Current claim streak in days (0 if a day was missed since the last claim)
+ */
+export function getCurrentClaimStreak(profile: UserProfile, currentTime: number): number {
+  const today = Math.floor(currentTime / SECONDS_PER_DAY);
+  return today - profile.lastClaimDay.toNumber() <= 1 ? profile.claimStreak : 0;
+}
+
+/**
+ * Claim bonus (bps) for a claim streak
+ */
+export function getStreakBonusBps(streak: number): number {
+  return Math.min(Math.max(streak - 1, 0) * STREAK_BONUS_BPS_PER_DAY, MAX_STREAK_BONUS_BPS);
+}
+
+/**
+ * Check whether an achievement is set in an achievements bitmap
+ */
+export function hasAchievement(bitmap: number, achievement: Achievement): boolean {
+  return (bitmap & (1 << ACHIEVEMENTS.indexOf(achievement))) !== 0;
+}
+
 /**
  * Validate land ID (must be 1-21000)
  */
@@ -249,6 +888,7 @@ export function createColonyClient(config: ColonyClientConfig) {
   const [gameStateAddress] = gameStatePda(programId);
   const [vaultAddress] = vaultPda(programId);
   const [tokenVaultAddress] = tokenVaultPda(programId);
+  const [stakeVaultAddress] = stakeVaultPda(programId);
 
   // ========== HELPERS ==========
 
@@ -284,17 +924,49 @@ export function createColonyClient(config: ColonyClientConfig) {
     throw new Error(`Transaction confirmation timeout for ${signature}`);
   }
 
+  /**
+   * Fetch and decode a program account. Accounts that still have a pre-migration
+   * (shorter) layout are zero-padded so newly added fields decode as defaults.
+   */
+  async function fetchAccount(
+    name: "gameState" | "landData" | "userProfile" | "rental",
+    address: PublicKey
+  ) {
+    const info = await connection.getAccountInfo(address);
+    if (!info) throw new Error(`Account ${address.toBase58()} not found`);
+
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const size: number = (program.account as any)[name].size;
+    const data =
+      info.data.length < size
+        ? Buffer.concat([info.data, Buffer.alloc(size - info.data.length)])
+        : info.data;
+    return program.coder.accounts.decode(name, data);
+  }
+
+  /**
+   * Check whether an account exists but still has an older layout (shorter, or an older version)
+   */
+  async function needsMigration(
+    name: "gameState" | "landData" | "userProfile",
+    address: PublicKey
+  ): Promise<boolean> {
+    const info = await connection.getAccountInfo(address);
+    if (!info) return false;
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    if (info.data.length < (program.account as any)[name].size) return true;
+    const account = await fetchAccount(name, address);
+    return account.version < ACCOUNT_VERSIONS[name];
+  }
+
   // ========== READ METHODS ==========
 
   async function getGameState(): Promise<GameState | null> {
     try {
-      // eslint-disable-next-line @typescript-eslint/no-explicit-any
-      const account = await (program.account as any).gameState.fetch(
-        gameStateAddress
-      );
+      const account = await fetchAccount("gameState", gameStateAddress);
       return {
         authority: account.authority,
-        treasuryBalance: account.treasuryBalance,
+        totalRevenue: account.totalRevenue,
         totalLandsSold: account.totalLandsSold,
         totalSolCollected: account.totalSolCollected,
         isActive: account.isActive,
@@ -302,6 +974,35 @@ export function createColonyClient(config: ColonyClientConfig) {
         vaultBump: account.vaultBump,
         tokenMint: account.tokenMint,
         tokenVaultBump: account.tokenVaultBump,
+        craftRates: account.craftRates,
+        landNftUri: account.landNftUri,
+        stakeVaultBump: account.stakeVaultBump,
+        referralBps: account.referralBps,
+        currentSeason: account.currentSeason,
+        seasonOpen: account.seasonOpen,
+        achievementRewards: account.achievementRewards,
+        randomnessSource: Object.keys(account.randomnessSource)[0] as RandomnessSource,
+        randomnessOracle: account.randomnessOracle,
+        salePhase: Object.keys(account.salePhase)[0] as SalePhase,
+        whitelistRoot: account.whitelistRoot,
+        whitelistEnd: account.whitelistEnd,
+        whitelistPurchaseCap: account.whitelistPurchaseCap,
+        purchaseCooldown: account.purchaseCooldown,
+        slotPurchaseCap: account.slotPurchaseCap,
+        lastPurchaseSlot: account.lastPurchaseSlot,
+        slotPurchases: account.slotPurchases,
+        reservedLandsEnabled: account.reservedLandsEnabled,
+        reservedLandsBump: account.reservedLandsBump,
+        landPriceLamports: account.landPriceLamports,
+        revenueVaultBps: account.revenueVaultBps,
+        revenueTeamBps: account.revenueTeamBps,
+        teamWallet: account.teamWallet,
+        tokenMigration: Object.keys(account.tokenMigration)[0] as TokenMigration,
+        pendingTokenMint: account.pendingTokenMint,
+        resumeActive: account.resumeActive,
+        version: account.version,
+        whitelistWindow: account.whitelistWindow,
+        totalStaked: account.totalStaked,
       };
     } catch {
       return null;
@@ -313,10 +1014,7 @@ export function createColonyClient(config: ColonyClientConfig) {
 
     try {
       const [landDataAddress] = landDataPda(landId, programId);
-      // eslint-disable-next-line @typescript-eslint/no-explicit-any
-      const account = await (program.account as any).landData.fetch(
-        landDataAddress
-      );
+      const account = await fetchAccount("landData", landDataAddress);
       return {
         landId: account.landId,
         owner: account.owner,
@@ -324,6 +1022,16 @@ export function createColonyClient(config: ColonyClientConfig) {
         fixedEarnings: account.fixedEarnings,
         lastCheckout: account.lastCheckout,
         bump: account.bump,
+        rentalEnd: account.rentalEnd,
+        eventModifierBps: account.eventModifierBps,
+        eventEnd: account.eventEnd,
+        lastEventRequest: account.lastEventRequest,
+        defenseLevel: account.defenseLevel,
+        protectedUntil: account.protectedUntil,
+        lastRaidedAt: account.lastRaidedAt,
+        megaPlot: account.megaPlot,
+        megaSpeed: account.megaSpeed,
+        version: account.version,
       };
     } catch {
       return null;
@@ -344,6 +1052,28 @@ export function createColonyClient(config: ColonyClientConfig) {
     return landData.owner.equals(owner);
   }
 
+  /**
+   * Get the wallet currently holding a land's NFT (null if the land has no NFT yet)
+   */
+  async function getLandNftHolder(
+    landId: number
+  ): Promise<{ owner: PublicKey; tokenAccount: PublicKey } | null> {
+    const [landMint] = landMintPda(landId, programId);
+    try {
+      const largest = await connection.getTokenLargestAccounts(landMint);
+      const holder = largest.value.find((a) => a.amount === "1");
+      if (!holder) return null;
+
+      const info = await connection.getParsedAccountInfo(holder.address);
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      const parsed = (info.value?.data as any)?.parsed?.info;
+      if (!parsed) return null;
+      return { owner: new PublicKey(parsed.owner), tokenAccount: holder.address };
+    } catch {
+      return null;
+    }
+  }
+
   async function getVaultBalance(): Promise<number> {
     return await connection.getBalance(vaultAddress);
   }
@@ -360,19 +1090,40 @@ export function createColonyClient(config: ColonyClientConfig) {
     }
 
     const currentTime = Math.floor(Date.now() / 1000);
-    return calculateEarnings(landData, currentTime);
+    const ownerProfile = await getUserProfile(landData.owner);
+    return ownerProfile
+      ? calculateBoostedEarnings(landData, ownerProfile, currentTime)
+      : calculateEarnings(landData, currentTime);
   }
 
   async function getUserProfile(user: PublicKey): Promise<UserProfile | null> {
     try {
       const [address] = userProfilePda(user, programId);
-      // eslint-disable-next-line @typescript-eslint/no-explicit-any
-      const account = await (program.account as any).userProfile.fetch(address);
+      const account = await fetchAccount("userProfile", address);
       return {
         owner: account.owner,
         landsOwned: account.landsOwned,
-        tokenBalance: account.tokenBalance,
+        landsPurchased: account.landsPurchased,
         bump: account.bump,
+        resources: account.resources,
+        payoutAddress: account.payoutAddress,
+        stakedAmount: account.stakedAmount,
+        stakeStart: account.stakeStart,
+        stakeLockEnd: account.stakeLockEnd,
+        stakeBonusBps: account.stakeBonusBps,
+        referrer: account.referrer,
+        referralRewards: account.referralRewards,
+        guild: account.guild,
+        seasonId: account.seasonId,
+        seasonScore: account.seasonScore,
+        achievements: account.achievements,
+        achievementsClaimed: account.achievementsClaimed,
+        claimStreak: account.claimStreak,
+        lastClaimDay: account.lastClaimDay,
+        whitelistPurchases: account.whitelistPurchases,
+        lastPurchaseTime: account.lastPurchaseTime,
+        version: account.version,
+        whitelistWindow: account.whitelistWindow,
       };
     } catch {
       return null;
@@ -384,16 +1135,213 @@ export function createColonyClient(config: ColonyClientConfig) {
     return profile?.landsOwned ?? 0;
   }
 
-  /**
-   * Get token context (mint + vault address) from game state.
-   * Fetches game state to determine the current token mint.
-   */
-  async function getTokenContext(): Promise<TokenContext> {
-    const state = await getGameState();
-    if (!state) throw new Error("Game not initialized");
-    return {
-      tokenMint: state.tokenMint,
-      tokenVaultAddress,
+  async function getPendingResources(landId: number): Promise<BN[]> {
+    const landData = await getLandData(landId);
+    if (!landData) {
+      return RESOURCES.map(() => new BN(0));
+    }
+
+    const currentTime = Math.floor(Date.now() / 1000);
+    return calculateResources(landData, currentTime);
+  }
+
+  async function getClaimStreak(user: PublicKey): Promise<number> {
+    const profile = await getUserProfile(user);
+    if (!profile) {
+      return 0;
+    }
+
+    const currentTime = Math.floor(Date.now() / 1000);
+    return getCurrentClaimStreak(profile, currentTime);
+  }
+
+  /**
+   * Check whether a user's profile exists but still has a pre-migration (shorter) layout.
+   * The program only loads migrated profiles, so the SDK prepends migrate_user_profile to the user's next transaction.
+   */
+  async function needsProfileMigration(user: PublicKey): Promise<boolean> {
+    const info = await connection.getAccountInfo(userProfilePda(user, programId)[0]);
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    return info !== null && info.data.length < (program.account as any).userProfile.size;
+  }
+
+  /**
+   * Check whether GameState still has an older layout (see migrateGameState)
+   */
+  async function needsGameStateMigration(): Promise<boolean> {
+    return needsMigration("gameState", gameStateAddress);
+  }
+
+  /**
+   * Build migrate_land_data instructions to prepend when a land account is outdated
+   */
+  async function buildLandMigrationInstructions(
+    landId: number
+  ): Promise<TransactionInstruction[]> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const [landDataAddress] = landDataPda(landId, programId);
+    if (!(await needsMigration("landData", landDataAddress))) return [];
+
+    const ix = await program.methods
+      .migrateLandData(landId)
+      .accounts({
+        payer: wallet.publicKey,
+        landData: landDataAddress,
+        systemProgram: SystemProgram.programId,
+      })
+      .instruction();
+    return [ix];
+  }
+
+  async function getRandomnessRequest(
+    purpose: RandomnessPurpose,
+    landId: number
+  ): Promise<RandomnessRequest | null> {
+    try {
+      const account = await fetchAccount(
+        "randomnessRequest",
+        randomnessRequestPda(purpose, landId, programId)[0]
+      );
+      return {
+        landId: account.landId,
+        purpose: Object.keys(account.purpose)[0] as RandomnessPurpose,
+        requester: account.requester,
+        requestSlot: account.requestSlot,
+        createdAt: account.createdAt,
+        bump: account.bump,
+        attackerLandId: account.attackerLandId,
+      };
+    } catch {
+      return null;
+    }
+  }
+
+  async function getSeason(seasonId: number): Promise<Season | null> {
+    try {
+      const account = await fetchAccount("season", seasonPda(seasonId, programId)[0]);
+      return {
+        seasonId: account.seasonId,
+        startTime: account.startTime,
+        endTime: account.endTime,
+        prizePool: account.prizePool,
+        leaderboard: account.leaderboard,
+        closed: account.closed,
+        bump: account.bump,
+        prizesClaimed: account.prizesClaimed,
+      };
+    } catch {
+      return null;
+    }
+  }
+
+  /**
+   * Current season's account address while it is open, null otherwise (passed to scoring instructions)
+   */
+  async function getOpenSeasonAddress(): Promise<PublicKey | null> {
+    const state = await getGameState();
+    if (!state || !state.seasonOpen) return null;
+    return seasonPda(state.currentSeason, programId)[0];
+  }
+
+  async function getGuild(address: PublicKey): Promise<Guild | null> {
+    try {
+      const account = await fetchAccount("guild", address);
+      return {
+        leader: account.leader,
+        name: account.name,
+        taxBps: account.taxBps,
+        members: account.members,
+        memberLevels: account.memberLevels,
+        totalLandLevels: account.totalLandLevels,
+        bonusBps: account.bonusBps,
+        bump: account.bump,
+      };
+    } catch {
+      return null;
+    }
+  }
+
+  async function getPaymentMint(mint: PublicKey): Promise<PaymentMint | null> {
+    try {
+      const account = await fetchAccount("paymentMint", paymentMintPda(mint, programId)[0]);
+      return {
+        mint: account.mint,
+        pricePerToken: account.pricePerToken,
+        treasury: account.treasury,
+        bump: account.bump,
+      };
+    } catch {
+      return null;
+    }
+  }
+
+  async function getMegaPlot(anchorLandId: number): Promise<MegaPlot | null> {
+    try {
+      const account = await fetchAccount("megaPlot", megaPlotPda(anchorLandId, programId)[0]);
+      return {
+        owner: account.owner,
+        anchorLandId: account.anchorLandId,
+        landIds: account.landIds,
+        earningSpeed: account.earningSpeed,
+        createdAt: account.createdAt,
+        bump: account.bump,
+      };
+    } catch {
+      return null;
+    }
+  }
+
+  async function getRental(landId: number): Promise<Rental | null> {
+    try {
+      const [address] = rentalPda(landId, programId);
+      const account = await fetchAccount("rental", address);
+      return {
+        landId: account.landId,
+        owner: account.owner,
+        renter: account.renter,
+        duration: account.duration,
+        upfrontFee: account.upfrontFee,
+        ownerShareBps: account.ownerShareBps,
+        startTime: account.startTime,
+        endTime: account.endTime,
+        bump: account.bump,
+      };
+    } catch {
+      return null;
+    }
+  }
+
+  /**
+   * Build migrate_user_profile instructions to prepend when the user's profile is outdated
+   */
+  async function buildProfileMigrationInstructions(
+    user: PublicKey
+  ): Promise<TransactionInstruction[]> {
+    if (!(await needsProfileMigration(user))) return [];
+
+    const [userProfileAddress] = userProfilePda(user, programId);
+    const ix = await program.methods
+      .migrateUserProfile()
+      .accounts({
+        user,
+        userProfile: userProfileAddress,
+        systemProgram: SystemProgram.programId,
+      })
+      .instruction();
+    return [ix];
+  }
+
+  /**
+   * Get token context (mint + vault address) from game state.
+   * Fetches game state to determine the current token mint.
+   */
+  async function getTokenContext(): Promise<TokenContext> {
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+    return {
+      tokenMint: state.tokenMint,
+      tokenVaultAddress,
     };
   }
 
@@ -486,6 +1434,17 @@ export function createColonyClient(config: ColonyClientConfig) {
    * Get land IDs owned by a specific user.
    * Filters LandData accounts by owner field directly on-chain.
    */
+  /**
+   * Check whether a land_id is reserved (only the authority can grant reserved lands)
+   */
+  async function isLandReserved(landId: number): Promise<boolean> {
+    const info = await connection.getAccountInfo(reservedLandsPda(programId)[0]);
+    if (!info) return false;
+
+    const bit = landId - 1;
+    return (info.data[8 + Math.floor(bit / 8)] & (1 << bit % 8)) !== 0;
+  }
+
   async function getUserLandIds(user: PublicKey): Promise<number[]> {
     try {
       const landDataDiscriminator = new Uint8Array([188, 85, 52, 43, 52, 142, 58, 79]);
@@ -547,19 +1506,19 @@ export function createColonyClient(config: ColonyClientConfig) {
     return tx;
   }
 
-  async function initTokenVault(): Promise<string> {
+  async function initStakeVault(): Promise<string> {
     if (!wallet) throw new Error("Wallet required");
 
     const state = await getGameState();
     if (!state) throw new Error("Game not initialized");
 
     const tx = await program.methods
-      .initTokenVault()
+      .initStakeVault()
       .accounts({
         authority: wallet.publicKey,
         gameState: gameStateAddress,
         tokenMint: state.tokenMint,
-        tokenVault: tokenVaultAddress,
+        stakeVault: stakeVaultAddress,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
@@ -568,34 +1527,53 @@ export function createColonyClient(config: ColonyClientConfig) {
     return tx;
   }
 
-  async function buyLand(landId: number): Promise<string> {
+  /**
+   * Accounts shared by the buy_land variants (land, NFT, profile, referrer, reserved lands)
+   */
+  async function buildLandPurchaseAccounts(landId: number, referrer?: PublicKey) {
     if (!wallet) throw new Error("Wallet required");
     if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
 
-    const ctx = await getTokenContext();
-    const [landDataAddress] = landDataPda(landId, programId);
-    const [userProfileAddress] = userProfilePda(wallet.publicKey, programId);
-    const userTokenAccount = getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey);
+    let referrerProfile: PublicKey | null = null;
+    if (referrer) {
+      if ((await getUserProfile(referrer)) === null || (await needsProfileMigration(referrer))) {
+        throw new Error("Referrer has no up-to-date profile");
+      }
+      [referrerProfile] = userProfilePda(referrer, programId);
+    }
+
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+    if (state.reservedLandsEnabled && (await isLandReserved(landId))) {
+      throw new Error(`Land #${landId} is reserved`);
+    }
 
-    const accounts = {
-      user: wallet.publicKey,
-      gameState: gameStateAddress,
-      landData: landDataAddress,
-      userProfile: userProfileAddress,
-      tokenMint: ctx.tokenMint,
-      userTokenAccount,
-      tokenProgram: TOKEN_2022_PROGRAM_ID,
-      systemProgram: SystemProgram.programId,
+    const [landMint] = landMintPda(landId, programId);
+    return {
+      state,
+      accounts: {
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        landMint,
+        userLandTokenAccount: getUserTokenAccountAddress(landMint, wallet.publicKey),
+        referrerProfile,
+        reservedLands: state.reservedLandsEnabled ? reservedLandsPda(programId)[0] : null,
+        nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      },
     };
+  }
 
-    const ix = await program.methods
-      .buyLand(landId)
-      .accounts(accounts)
-      .instruction();
+  async function sendLandPurchase(ix: TransactionInstruction): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
 
     const tx = new Transaction().add(
       ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 }),
       ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 100_000 }),
+      ...(await buildProfileMigrationInstructions(wallet.publicKey)),
       ix
     );
     const { blockhash } = await connection.getLatestBlockhash("confirmed");
@@ -606,54 +1584,196 @@ export function createColonyClient(config: ColonyClientConfig) {
     return sendAndConfirmTx(signedTx);
   }
 
-  async function claimEarnings(landId: number): Promise<string> {
-    if (!wallet) throw new Error("Wallet required");
-    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+  /**
+   * Revenue split recipients for buy_land and upgrade_land, passed only when their share is set
+   */
+  function buildRevenueAccounts(state: GameState) {
+    return {
+      tokenVault: state.revenueVaultBps > 0 ? tokenVaultAddress : null,
+      teamTokenAccount: state.revenueTeamBps > 0 ? state.teamWallet : null,
+    };
+  }
 
+  /**
+   * Buy a land. `referrer` is recorded on the buyer's first purchase and must have a
+   * current profile (see needsProfileMigration). `whitelistProof` (from buildWhitelistTree)
+   * is required during the whitelist window.
+   */
+  async function buyLand(
+    landId: number,
+    referrer?: PublicKey,
+    whitelistProof: Uint8Array[] = []
+  ): Promise<string> {
+    const { state, accounts } = await buildLandPurchaseAccounts(landId, referrer);
     const ctx = await getTokenContext();
-    const [landDataAddress] = landDataPda(landId, programId);
-    const userTokenAccount = getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey);
 
-    const tx = await program.methods
-      .claimEarnings(landId)
+    const ix = await program.methods
+      .buyLand(landId, whitelistProof.map((node) => Array.from(node)))
       .accounts({
-        user: wallet.publicKey,
-        gameState: gameStateAddress,
-        landData: landDataAddress,
+        ...accounts,
         tokenMint: ctx.tokenMint,
-        tokenVault: ctx.tokenVaultAddress,
-        userTokenAccount,
+        userTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, accounts.user),
+        ...buildRevenueAccounts(state),
         tokenProgram: TOKEN_2022_PROGRAM_ID,
-        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
       })
-      .rpc();
+      .instruction();
 
-    return tx;
+    return sendLandPurchase(ix);
+  }
+
+  /**
+   * Accounts for paying with a whitelisted alternative mint (SPL Token or Token-2022)
+   */
+  async function buildPaymentAccounts(mint: PublicKey) {
+    if (!wallet) throw new Error("Wallet required");
+
+    const paymentMint = await getPaymentMint(mint);
+    if (!paymentMint || paymentMint.pricePerToken.isZero()) {
+      throw new Error(`Mint ${mint.toBase58()} is not accepted for payment`);
+    }
+    const mintInfo = await connection.getAccountInfo(mint);
+    if (!mintInfo) throw new Error(`Mint ${mint.toBase58()} not found`);
+
+    return {
+      paymentMint: paymentMintPda(mint, programId)[0],
+      paymentTokenMint: mint,
+      userPaymentAccount: getAssociatedTokenAddressSync(mint, wallet.publicKey, true, mintInfo.owner),
+      treasuryTokenAccount: paymentMint.treasury,
+      tokenProgram: mintInfo.owner,
+    };
+  }
+
+  /**
+   * Buy a land paying with a whitelisted alternative mint (e.g. USDC) at its configured rate
+   */
+  async function buyLandWithToken(
+    landId: number,
+    paymentMint: PublicKey,
+    referrer?: PublicKey,
+    whitelistProof: Uint8Array[] = []
+  ): Promise<string> {
+    const { accounts } = await buildLandPurchaseAccounts(landId, referrer);
+
+    const ix = await program.methods
+      .buyLandWithToken(landId, whitelistProof.map((node) => Array.from(node)))
+      .accounts({
+        ...accounts,
+        ...(await buildPaymentAccounts(paymentMint)),
+      })
+      .instruction();
+
+    return sendLandPurchase(ix);
+  }
+
+  /**
+   * Buy a land with SOL at the configured lamport price (paid into the vault)
+   */
+  async function buyLandWithSol(
+    landId: number,
+    referrer?: PublicKey,
+    whitelistProof: Uint8Array[] = []
+  ): Promise<string> {
+    const { state, accounts } = await buildLandPurchaseAccounts(landId, referrer);
+    if (state.landPriceLamports.isZero()) throw new Error("Buying land with SOL is disabled");
+
+    const ix = await program.methods
+      .buyLandWithSol(landId, whitelistProof.map((node) => Array.from(node)))
+      .accounts({
+        ...accounts,
+        vault: vaultAddress,
+      })
+      .instruction();
+
+    return sendLandPurchase(ix);
+  }
+
+  /**
+   * Claim earnings from a land. Earnings go to `payoutTokenAccount` if given, otherwise to the
+   * owner's default payout address (if set) or the signer's token account.
+   */
+  async function claimEarnings(
+    landId: number,
+    payoutTokenAccount?: PublicKey
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const ix = await buildClaimInstruction(landId, undefined, payoutTokenAccount);
+
+    const tx = new Transaction().add(
+      ...(await buildLandMigrationInstructions(landId)),
+      ...(await buildProfileMigrationInstructions(wallet.publicKey)),
+      ix
+    );
+    const { blockhash } = await connection.getLatestBlockhash("confirmed");
+    tx.recentBlockhash = blockhash;
+    tx.feePayer = wallet.publicKey;
+
+    const signedTx = await wallet.signTransaction(tx);
+    return sendAndConfirmTx(signedTx);
   }
 
   /**
-   * Build a claim instruction for a single land
+   * Build a claim instruction for a single land (as owner, or as renter while the land is rented)
    */
   async function buildClaimInstruction(
     landId: number,
-    tokenCtx?: TokenContext
+    tokenCtx?: TokenContext,
+    payoutTokenAccount?: PublicKey
   ): Promise<TransactionInstruction> {
     if (!wallet) throw new Error("Wallet required");
 
     const ctx = tokenCtx || await getTokenContext();
+    const landData = await getLandData(landId);
+    if (!landData) throw new Error(`Land #${landId} not found`);
+    const ownerProfile = await getUserProfile(landData.owner);
+
     const [landDataAddress] = landDataPda(landId, programId);
+    const [userProfileAddress] = userProfilePda(landData.owner, programId);
     const userTokenAccount = getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey);
 
+    const isRented = landData.rentalEnd.toNumber() > Math.floor(Date.now() / 1000);
+
+    // Owner's default payout address routes the owner's claims and rental share
+    const ownerPayout =
+      ownerProfile && !ownerProfile.payoutAddress.equals(PublicKey.default)
+        ? ownerProfile.payoutAddress
+        : null;
+    // Referral rewards accrue only if the referrer's profile can be passed (current layout)
+    const referrer = ownerProfile?.referrer ?? PublicKey.default;
+    const referrerProfile =
+      !referrer.equals(PublicKey.default) && !(await needsProfileMigration(referrer))
+        ? userProfilePda(referrer, programId)[0]
+        : null;
+
+    const guild = ownerProfile?.guild ?? PublicKey.default;
+    const inGuild = !guild.equals(PublicKey.default);
+
+    const payout =
+      payoutTokenAccount ??
+      (!isRented && ownerPayout
+        ? getUserTokenAccountAddress(ctx.tokenMint, ownerPayout)
+        : null);
+
     return await program.methods
       .claimEarnings(landId)
       .accounts({
         user: wallet.publicKey,
         gameState: gameStateAddress,
         landData: landDataAddress,
+        userProfile: userProfileAddress,
+        rental: isRented ? rentalPda(landId, programId)[0] : null,
+        referrerProfile,
+        season: await getOpenSeasonAddress(),
+        guild: inGuild ? guild : null,
+        guildVault: inGuild ? guildVaultPda(guild, programId)[0] : null,
         tokenMint: ctx.tokenMint,
         tokenVault: ctx.tokenVaultAddress,
         userTokenAccount,
+        payoutTokenAccount: payout,
+        ownerTokenAccount: isRented
+          ? getUserTokenAccountAddress(ctx.tokenMint, ownerPayout ?? landData.owner)
+          : null,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -681,6 +1801,9 @@ export function createColonyClient(config: ColonyClientConfig) {
     // Fetch token context once for all batches
     const tokenCtx = await getTokenContext();
 
+    // Outdated profiles must be migrated before the first claim
+    let profileMigrationIxs = await buildProfileMigrationInstructions(wallet.publicKey);
+
     // Capture pending earnings BEFORE claiming (after claim they reset to ~0)
     const pendingByLand = new Map<number, BN>();
     await Promise.all(
@@ -718,20 +1841,22 @@ export function createColonyClient(config: ColonyClientConfig) {
         instructions.push(
           ComputeBudgetProgram.setComputeUnitLimit({
             units: Math.min(300_000 * batch.length, 1_400_000),
-          })
+          }),
+          ...profileMigrationIxs
         );
+        const headerLength = instructions.length;
 
         for (const landId of batch) {
           try {
             const ix = await buildClaimInstruction(landId, tokenCtx);
-            instructions.push(ix);
+            instructions.push(...(await buildLandMigrationInstructions(landId)), ix);
           } catch (err) {
             console.error(`Failed to build claim instruction for land ${landId}:`, err);
             result.failedLandIds.push(landId);
           }
         }
 
-        if (instructions.length <= 1) {
+        if (instructions.length <= headerLength) {
           processedLands += batch.length;
           continue;
         }
@@ -745,6 +1870,7 @@ export function createColonyClient(config: ColonyClientConfig) {
         const signature = await sendAndConfirmTx(signedTx);
 
         result.txSignatures.push(signature);
+        profileMigrationIxs = [];
 
         for (const landId of batch) {
           if (!result.failedLandIds.includes(landId)) {
@@ -799,13 +1925,41 @@ export function createColonyClient(config: ColonyClientConfig) {
     return result;
   }
 
+  /**
+   * Upgrade a land paying with a whitelisted alternative mint at its configured rate
+   */
+  async function upgradeLandWithToken(landId: number, paymentMint: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const tx = await program.methods
+      .upgradeLandWithToken(landId)
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        season: await getOpenSeasonAddress(),
+        ...(await buildPaymentAccounts(paymentMint)),
+      })
+      .preInstructions([
+        ...(await buildLandMigrationInstructions(landId)),
+        ...(await buildProfileMigrationInstructions(wallet.publicKey)),
+      ])
+      .rpc();
+
+    return tx;
+  }
+
   async function upgradeLand(landId: number): Promise<string> {
     if (!wallet) throw new Error("Wallet required");
     if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
 
-    const ctx = await getTokenContext();
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
     const [landDataAddress] = landDataPda(landId, programId);
-    const userTokenAccount = getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey);
+    const [userProfileAddress] = userProfilePda(wallet.publicKey, programId);
+    const userTokenAccount = getUserTokenAccountAddress(state.tokenMint, wallet.publicKey);
 
     const ix = await program.methods
       .upgradeLand(landId)
@@ -813,8 +1967,11 @@ export function createColonyClient(config: ColonyClientConfig) {
         user: wallet.publicKey,
         gameState: gameStateAddress,
         landData: landDataAddress,
-        tokenMint: ctx.tokenMint,
+        userProfile: userProfileAddress,
+        season: await getOpenSeasonAddress(),
+        tokenMint: state.tokenMint,
         userTokenAccount,
+        ...buildRevenueAccounts(state),
         tokenProgram: TOKEN_2022_PROGRAM_ID,
       })
       .instruction();
@@ -822,6 +1979,8 @@ export function createColonyClient(config: ColonyClientConfig) {
     const tx = new Transaction().add(
       ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 }),
       ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 100_000 }),
+      ...(await buildLandMigrationInstructions(landId)),
+      ...(await buildProfileMigrationInstructions(wallet.publicKey)),
       ix
     );
     const { blockhash } = await connection.getLatestBlockhash("confirmed");
@@ -832,35 +1991,1322 @@ export function createColonyClient(config: ColonyClientConfig) {
     return sendAndConfirmTx(signedTx);
   }
 
-  async function withdrawSol(): Promise<string> {
-    if (!wallet) throw new Error("Wallet required");
+  /**
+   * Upgrade a land using its pending earnings; only the shortfall is paid from the wallet
+   */
+  async function compound(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+    const [landDataAddress] = landDataPda(landId, programId);
+    const [userProfileAddress] = userProfilePda(wallet.publicKey, programId);
+    const userTokenAccount = getUserTokenAccountAddress(state.tokenMint, wallet.publicKey);
+
+    const ix = await program.methods
+      .compound(landId)
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataAddress,
+        userProfile: userProfileAddress,
+        season: await getOpenSeasonAddress(),
+        tokenMint: state.tokenMint,
+        tokenVault: tokenVaultAddress,
+        userTokenAccount,
+        teamTokenAccount: buildRevenueAccounts(state).teamTokenAccount,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .instruction();
+
+    const tx = new Transaction().add(
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 }),
+      ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 100_000 }),
+      ...(await buildLandMigrationInstructions(landId)),
+      ...(await buildProfileMigrationInstructions(wallet.publicKey)),
+      ix
+    );
+    const { blockhash } = await connection.getLatestBlockhash("confirmed");
+    tx.recentBlockhash = blockhash;
+    tx.feePayer = wallet.publicKey;
+
+    const signedTx = await wallet.signTransaction(tx);
+    return sendAndConfirmTx(signedTx);
+  }
+
+  /**
+   * Lock tokens for `duration` seconds to boost earnings of all owned lands
+   */
+  async function stake(amount: BN, duration: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    // A previous stake's unclaimed boost is settled into every owned land first
+    const profile = await getUserProfile(wallet.publicKey);
+    const landIds =
+      profile && profile.stakeBonusBps > 0
+        ? (await getUserLandIds(wallet.publicKey)).sort((a, b) => a - b)
+        : [];
+    const migrations = (
+      await Promise.all(landIds.map((landId) => buildLandMigrationInstructions(landId)))
+    ).flat();
+
+    const tx = await program.methods
+      .stake(amount, new BN(duration))
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        tokenMint: ctx.tokenMint,
+        stakeVault: stakeVaultAddress,
+        userTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .remainingAccounts(
+        landIds.map((landId) => ({
+          pubkey: landDataPda(landId, programId)[0],
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .preInstructions([
+        ...(await buildProfileMigrationInstructions(wallet.publicKey)),
+        ...migrations,
+      ])
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Withdraw staked tokens (early exit pays EARLY_UNSTAKE_PENALTY_BPS)
+   */
+  async function unstake(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const tx = await program.methods
+      .unstake()
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        tokenMint: ctx.tokenMint,
+        stakeVault: stakeVaultAddress,
+        tokenVault: ctx.tokenVaultAddress,
+        userTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Claim referral rewards accrued from referred players' claims
+   */
+  async function claimReferralRewards(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const tx = await program.methods
+      .claimReferralRewards()
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        tokenMint: ctx.tokenMint,
+        tokenVault: ctx.tokenVaultAddress,
+        userTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions(await buildProfileMigrationInstructions(wallet.publicKey))
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Create a guild led by the wallet; `taxBps` of members' claims go to the guild treasury
+   */
+  async function createGuild(name: string, taxBps: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const [guild] = guildPda(wallet.publicKey, programId);
+    const tx = await program.methods
+      .createGuild(name, taxBps)
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        guild,
+        tokenMint: ctx.tokenMint,
+        guildVault: guildVaultPda(guild, programId)[0],
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions(await buildProfileMigrationInstructions(wallet.publicKey))
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Invite a player to the wallet's guild (joining uses up the invite)
+   */
+  async function inviteToGuild(member: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const [guild] = guildPda(wallet.publicKey, programId);
+    const tx = await program.methods
+      .inviteToGuild(member)
+      .accounts({
+        leader: wallet.publicKey,
+        guild,
+        invite: guildInvitePda(guild, member, programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function revokeGuildInvite(member: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const [guild] = guildPda(wallet.publicKey, programId);
+    const tx = await program.methods
+      .revokeGuildInvite(member)
+      .accounts({
+        leader: wallet.publicKey,
+        guild,
+        invite: guildInvitePda(guild, member, programId)[0],
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Join a guild the wallet was invited to
+   */
+  async function joinGuild(guild: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const guildAccount = await getGuild(guild);
+    if (!guildAccount) throw new Error("Guild not found");
+
+    const tx = await program.methods
+      .joinGuild()
+      .accounts({
+        user: wallet.publicKey,
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        guild,
+        invite: guildInvitePda(guild, wallet.publicKey, programId)[0],
+        leader: guildAccount.leader,
+      })
+      .preInstructions(await buildProfileMigrationInstructions(wallet.publicKey))
+      .rpc();
+
+    return tx;
+  }
+
+  async function leaveGuild(guild: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .leaveGuild()
+      .accounts({
+        user: wallet.publicKey,
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        guild,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function kickGuildMember(member: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .kickGuildMember()
+      .accounts({
+        leader: wallet.publicKey,
+        guild: guildPda(wallet.publicKey, programId)[0],
+        memberProfile: userProfilePda(member, programId)[0],
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Dissolve the wallet's guild once all other members are gone, paying out the treasury
+   */
+  async function dissolveGuild(destination?: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const [guild] = guildPda(wallet.publicKey, programId);
+    const tx = await program.methods
+      .dissolveGuild()
+      .accounts({
+        leader: wallet.publicKey,
+        gameState: gameStateAddress,
+        leaderProfile: userProfilePda(wallet.publicKey, programId)[0],
+        guild,
+        tokenMint: ctx.tokenMint,
+        guildVault: guildVaultPda(guild, programId)[0],
+        destination:
+          destination ?? getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function setGuildTax(taxBps: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setGuildTax(taxBps)
+      .accounts({
+        leader: wallet.publicKey,
+        guild: guildPda(wallet.publicKey, programId)[0],
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Recount a member's land levels towards their guild's bonus (passes all of the member's lands)
+   */
+  async function refreshGuildBonus(member: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const profile = await getUserProfile(member);
+    if (!profile || profile.guild.equals(PublicKey.default)) {
+      throw new Error("Member is not in a guild");
+    }
+
+    const landIds = (await getUserLandIds(member)).sort((a, b) => a - b);
+    const migrations = (
+      await Promise.all(landIds.map((landId) => buildLandMigrationInstructions(landId)))
+    ).flat();
+
+    const tx = await program.methods
+      .refreshGuildBonus()
+      .accounts({
+        guild: profile.guild,
+        memberProfile: userProfilePda(member, programId)[0],
+      })
+      .remainingAccounts(
+        landIds.map((landId) => ({
+          pubkey: landDataPda(landId, programId)[0],
+          isWritable: false,
+          isSigner: false,
+        }))
+      )
+      .preInstructions(migrations)
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Merge adjacent owned lands into a mega-plot anchored at the lowest land id
+   */
+  async function mergeLands(landIds: number[]): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const sorted = [...landIds].sort((a, b) => a - b);
+    if (sorted.length < MIN_MEGA_PLOT_LANDS || sorted.length > MAX_MEGA_PLOT_LANDS) {
+      throw new Error(`A mega-plot needs ${MIN_MEGA_PLOT_LANDS}-${MAX_MEGA_PLOT_LANDS} lands`);
+    }
+    if (!areLandsConnected(sorted)) throw new Error("Lands are not adjacent");
+
+    const migrations = (
+      await Promise.all(sorted.map((landId) => buildLandMigrationInstructions(landId)))
+    ).flat();
+
+    const tx = await program.methods
+      .mergeLands(sorted[0])
+      .accounts({
+        owner: wallet.publicKey,
+        ownerProfile: userProfilePda(wallet.publicKey, programId)[0],
+        megaPlot: megaPlotPda(sorted[0], programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        sorted.map((landId) => ({
+          pubkey: landDataPda(landId, programId)[0],
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .preInstructions([
+        ...migrations,
+        ...(await buildProfileMigrationInstructions(wallet.publicKey)),
+      ])
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Split a mega-plot back into its lands (pending earnings stay claimable on each land)
+   */
+  async function splitMegaPlot(anchorLandId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const megaPlot = await getMegaPlot(anchorLandId);
+    if (!megaPlot) throw new Error(`Mega-plot #${anchorLandId} not found`);
+
+    const tx = await program.methods
+      .splitMegaPlot(anchorLandId)
+      .accounts({
+        owner: wallet.publicKey,
+        ownerProfile: userProfilePda(wallet.publicKey, programId)[0],
+        megaPlot: megaPlotPda(anchorLandId, programId)[0],
+      })
+      .remainingAccounts(
+        megaPlot.landIds.map((landId) => ({
+          pubkey: landDataPda(landId, programId)[0],
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Withdraw tokens from the wallet's guild treasury to `destination` (leader only)
+   */
+  async function withdrawGuildTreasury(amount: BN, destination?: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const [guild] = guildPda(wallet.publicKey, programId);
+    const tx = await program.methods
+      .withdrawGuildTreasury(amount)
+      .accounts({
+        leader: wallet.publicKey,
+        gameState: gameStateAddress,
+        guild,
+        tokenMint: ctx.tokenMint,
+        guildVault: guildVaultPda(guild, programId)[0],
+        destination:
+          destination ?? getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * After a token mint migration, move the caller's guild treasury to the new game token:
+   * the old-mint balance is paid to `destination` (defaults to the leader's old-mint account)
+   * and the vault is recreated for the current mint, in one transaction
+   */
+  async function migrateGuildVault(destination?: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const [guild] = guildPda(wallet.publicKey, programId);
+    const [guildVault] = guildVaultPda(guild, programId);
+    const vaultInfo = await connection.getAccountInfo(guildVault);
+    if (!vaultInfo) throw new Error("Guild treasury not found");
+    // Token accounts start with their mint
+    const oldMint = new PublicKey(vaultInfo.data.subarray(0, 32));
+    if (oldMint.equals(ctx.tokenMint)) throw new Error("Guild treasury already uses the game token");
+    const oldTokenProgram = vaultInfo.owner;
+
+    const closeIx = await program.methods
+      .migrateGuildVaultClose()
+      .accounts({
+        leader: wallet.publicKey,
+        gameState: gameStateAddress,
+        guild,
+        oldTokenMint: oldMint,
+        guildVault,
+        destination:
+          destination ??
+          getAssociatedTokenAddressSync(oldMint, wallet.publicKey, false, oldTokenProgram),
+        oldTokenProgram,
+      })
+      .instruction();
+
+    const tx = await program.methods
+      .migrateGuildVaultInit()
+      .accounts({
+        leader: wallet.publicKey,
+        gameState: gameStateAddress,
+        guild,
+        tokenMint: ctx.tokenMint,
+        guildVault,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([closeIx])
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Claim the token reward for an unlocked achievement
+   */
+  async function claimAchievementReward(achievement: Achievement): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const tx = await program.methods
+      .claimAchievementReward({ [achievement]: {} })
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+        tokenMint: ctx.tokenMint,
+        tokenVault: ctx.tokenVaultAddress,
+        userTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions(await buildProfileMigrationInstructions(wallet.publicKey))
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Request a random event for a land (request rent is refunded on fulfilment)
+   */
+  async function requestLandEvent(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const tx = await program.methods
+      .requestLandEvent(landId)
+      .accounts({
+        requester: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        randomnessRequest: randomnessRequestPda("landEvent", landId, programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions(await buildLandMigrationInstructions(landId))
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Fulfil a pending land event request. Oracle sources sign with the oracle wallet and pass
+   * their random value; the deterministic source ignores `oracleValue`.
+   */
+  async function fulfilLandEvent(
+    landId: number,
+    oracleValue: Uint8Array = new Uint8Array(32)
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const request = await getRandomnessRequest("landEvent", landId);
+    if (!request) throw new Error(`No pending event request for land #${landId}`);
+    const landData = await getLandData(landId);
+    if (!landData) throw new Error(`Land #${landId} not found`);
+
+    const tx = await program.methods
+      .fulfilLandEvent(landId, Array.from(oracleValue))
+      .accounts({
+        fulfiller: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        ownerProfile: userProfilePda(landData.owner, programId)[0],
+        randomnessRequest: randomnessRequestPda("landEvent", landId, programId)[0],
+        requester: request.requester,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Build the next defense level on a land (tokens burned)
+   */
+  async function buildDefense(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const ctx = await getTokenContext();
+
+    const tx = await program.methods
+      .buildDefense(landId)
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        tokenMint: ctx.tokenMint,
+        userTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .preInstructions(await buildLandMigrationInstructions(landId))
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Raid `targetLandId` from `attackerLandId` (burns RAID_COST; outcome applied on fulfilment)
+   */
+  async function requestRaid(attackerLandId: number, targetLandId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(attackerLandId) || !isValidLandId(targetLandId)) {
+      throw new Error("Invalid land ID (must be 1-21000)");
+    }
+
+    const ctx = await getTokenContext();
+
+    const tx = await program.methods
+      .requestRaid(attackerLandId, targetLandId)
+      .accounts({
+        attacker: wallet.publicKey,
+        gameState: gameStateAddress,
+        attackerLand: landDataPda(attackerLandId, programId)[0],
+        targetLand: landDataPda(targetLandId, programId)[0],
+        randomnessRequest: randomnessRequestPda("raid", targetLandId, programId)[0],
+        tokenMint: ctx.tokenMint,
+        userTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([
+        ...(await buildLandMigrationInstructions(attackerLandId)),
+        ...(await buildLandMigrationInstructions(targetLandId)),
+      ])
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Fulfil a pending raid on `targetLandId`. Oracle sources sign with the oracle wallet and pass
+   * their random value; the deterministic source ignores `oracleValue`.
+   */
+  async function fulfilRaid(
+    targetLandId: number,
+    oracleValue: Uint8Array = new Uint8Array(32)
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const request = await getRandomnessRequest("raid", targetLandId);
+    if (!request) throw new Error(`No pending raid on land #${targetLandId}`);
+    const target = await getLandData(targetLandId);
+    if (!target) throw new Error(`Land #${targetLandId} not found`);
+
+    const tx = await program.methods
+      .fulfilRaid(targetLandId, Array.from(oracleValue))
+      .accounts({
+        fulfiller: wallet.publicKey,
+        gameState: gameStateAddress,
+        targetLand: landDataPda(targetLandId, programId)[0],
+        targetOwnerProfile: userProfilePda(target.owner, programId)[0],
+        attackerLand: landDataPda(request.attackerLandId, programId)[0],
+        randomnessRequest: randomnessRequestPda("raid", targetLandId, programId)[0],
+        requester: request.requester,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Cancel a randomness request left unfulfilled for RANDOMNESS_REQUEST_EXPIRY_SLOTS
+   * (request rent is refunded to the requester)
+   */
+  async function cancelRandomnessRequest(
+    purpose: RandomnessPurpose,
+    landId: number
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const request = await getRandomnessRequest(purpose, landId);
+    if (!request) throw new Error(`No pending ${purpose} request for land #${landId}`);
+
+    const tx = await program.methods
+      .cancelRandomnessRequest({ [purpose]: {} }, landId)
+      .accounts({
+        caller: wallet.publicKey,
+        randomnessRequest: randomnessRequestPda(purpose, landId, programId)[0],
+        requester: request.requester,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function craftTokens(resource: Resource, amount: BN): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const [userProfileAddress] = userProfilePda(wallet.publicKey, programId);
+    const userTokenAccount = getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey);
+
+    const tx = await program.methods
+      .craftTokens({ [resource]: {} }, amount)
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfileAddress,
+        tokenMint: ctx.tokenMint,
+        tokenVault: ctx.tokenVaultAddress,
+        userTokenAccount,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions(await buildProfileMigrationInstructions(wallet.publicKey))
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Set the default payout address for claims (null clears it)
+   */
+  async function setPayoutAddress(payoutAddress: PublicKey | null): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setPayoutAddress(payoutAddress ?? PublicKey.default)
+      .accounts({
+        user: wallet.publicKey,
+        userProfile: userProfilePda(wallet.publicKey, programId)[0],
+      })
+      .preInstructions(await buildProfileMigrationInstructions(wallet.publicKey))
+      .rpc();
+
+    return tx;
+  }
+
+  async function mintLandNft(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const [landDataAddress] = landDataPda(landId, programId);
+    const [landMint] = landMintPda(landId, programId);
+
+    const tx = await program.methods
+      .mintLandNft(landId)
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataAddress,
+        landMint,
+        userLandTokenAccount: getUserTokenAccountAddress(landMint, wallet.publicKey),
+        nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Make the current NFT holder the owner of a land (after the NFT was transferred or sold)
+   */
+  async function syncLandOwner(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const landData = await getLandData(landId);
+    if (!landData) throw new Error(`Land #${landId} not found`);
+    const holder = await getLandNftHolder(landId);
+    if (!holder) throw new Error(`Land #${landId} has no NFT holder`);
+
+    const [landMint] = landMintPda(landId, programId);
+
+    const tx = await program.methods
+      .syncLandOwner(landId)
+      .accounts({
+        payer: wallet.publicKey,
+        landData: landDataPda(landId, programId)[0],
+        landMint,
+        holderTokenAccount: holder.tokenAccount,
+        previousOwnerProfile: userProfilePda(landData.owner, programId)[0],
+        newOwnerProfile: userProfilePda(holder.owner, programId)[0],
+        nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function refreshLandMetadata(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const tx = await program.methods
+      .refreshLandMetadata(landId)
+      .accounts({
+        payer: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        landMint: landMintPda(landId, programId)[0],
+        nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Offer a land for rent. Pass `renter = null` to let anyone accept.
+   */
+  async function createRentalOffer(
+    landId: number,
+    terms: {
+      renter: PublicKey | null;
+      duration: number;
+      upfrontFee: BN;
+      ownerShareBps: number;
+    }
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const tx = await program.methods
+      .createRentalOffer(
+        landId,
+        terms.renter ?? PublicKey.default,
+        new BN(terms.duration),
+        terms.upfrontFee,
+        terms.ownerShareBps
+      )
+      .accounts({
+        owner: wallet.publicKey,
+        landData: landDataPda(landId, programId)[0],
+        rental: rentalPda(landId, programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions(await buildLandMigrationInstructions(landId))
+      .rpc();
+
+    return tx;
+  }
+
+  async function acceptRental(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const ctx = await getTokenContext();
+    const landData = await getLandData(landId);
+    if (!landData) throw new Error(`Land #${landId} not found`);
+
+    const tx = await program.methods
+      .acceptRental(landId)
+      .accounts({
+        renter: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        rental: rentalPda(landId, programId)[0],
+        ownerProfile: userProfilePda(landData.owner, programId)[0],
+        owner: landData.owner,
+        tokenMint: ctx.tokenMint,
+        tokenVault: ctx.tokenVaultAddress,
+        renterTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
+        ownerTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, landData.owner),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Withdraw a rental offer, close an expired rental or terminate an active one early
+   */
+  async function endRental(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    const ctx = await getTokenContext();
+    const landData = await getLandData(landId);
+    if (!landData) throw new Error(`Land #${landId} not found`);
+    const rental = await getRental(landId);
+    if (!rental) throw new Error(`Land #${landId} has no rental`);
+
+    // Accepted rentals (active or expired) pay out the rental period's earnings
+    const isAccepted = !rental.startTime.isZero();
+
+    const tx = await program.methods
+      .endRental(landId)
+      .accounts({
+        signer: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        rental: rentalPda(landId, programId)[0],
+        rentalOwner: rental.owner,
+        ownerProfile: userProfilePda(landData.owner, programId)[0],
+        tokenMint: ctx.tokenMint,
+        tokenVault: ctx.tokenVaultAddress,
+        ownerTokenAccount: isAccepted
+          ? getUserTokenAccountAddress(ctx.tokenMint, landData.owner)
+          : null,
+        renterTokenAccount: isAccepted
+          ? getUserTokenAccountAddress(ctx.tokenMint, rental.renter)
+          : null,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function migrateLandData(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .migrateLandData(landId)
+      .accounts({
+        payer: wallet.publicKey,
+        landData: landDataPda(landId, programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function migrateUserProfile(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const [userProfileAddress] = userProfilePda(wallet.publicKey, programId);
+
+    const tx = await program.methods
+      .migrateUserProfile()
+      .accounts({
+        user: wallet.publicKey,
+        userProfile: userProfileAddress,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function withdrawSol(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .withdrawSol()
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        vault: vaultAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function withdrawTokens(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const authorityTokenAccount = getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey);
+
+    const tx = await program.methods
+      .withdrawTokens()
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        tokenMint: ctx.tokenMint,
+        tokenVault: ctx.tokenVaultAddress,
+        authorityTokenAccount,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function setGameActive(isActive: boolean): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setGameActive(isActive)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function setCraftRates(rates: BN[]): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setCraftRates(rates)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function setAchievementRewards(rewards: BN[]): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setAchievementRewards(rewards)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function setRandomnessSource(
+    source: RandomnessSource,
+    oracle: PublicKey = PublicKey.default
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setRandomnessSource({ [source]: {} }, oracle)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  async function setSalePhase(
+    phase: SalePhase,
+    merkleRoot: Uint8Array = new Uint8Array(32),
+    whitelistEnd: number = 0,
+    purchaseCap: number = 0
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setSalePhase({ [phase]: {} }, Array.from(merkleRoot), new BN(whitelistEnd), purchaseCap)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Accept `mint` for land purchases and upgrades at `pricePerToken` base units per whole game
+   * token, paid to the `treasury` token account (0 disables the mint)
+   */
+  async function setPaymentMint(
+    mint: PublicKey,
+    pricePerToken: BN,
+    treasury: PublicKey
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setPaymentMint(pricePerToken, treasury)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        mint,
+        paymentMint: paymentMintPda(mint, programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Set the SOL price of a land for buyLandWithSol (0 disables SOL payments)
+   */
+  async function setLandPriceLamports(price: BN): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setLandPriceLamports(price)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Split land purchase and upgrade payments: `vaultBps` to the token vault, `teamBps` to
+   * `teamWallet` (a game token account), the rest burned (owner only)
+   */
+  async function setRevenueSplit(
+    vaultBps: number,
+    teamBps: number,
+    teamWallet: PublicKey = PublicKey.default
+  ): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (vaultBps + teamBps > BPS_DENOMINATOR) throw new Error("Revenue split exceeds 100%");
+
+    const tx = await program.methods
+      .setRevenueSplit(vaultBps, teamBps, teamWallet)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Set buy_land rate limits: seconds between a wallet's purchases and purchases per slot (0 disables)
+   */
+  async function setPurchaseLimits(purchaseCooldown: number, slotPurchaseCap: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
 
     const tx = await program.methods
-      .withdrawSol()
+      .setPurchaseLimits(new BN(purchaseCooldown), slotPurchaseCap)
       .accounts({
         authority: wallet.publicKey,
         gameState: gameStateAddress,
-        vault: vaultAddress,
       })
       .rpc();
 
     return tx;
   }
 
-  async function withdrawTokens(): Promise<string> {
+  async function initReservedLands(): Promise<string> {
     if (!wallet) throw new Error("Wallet required");
 
-    const ctx = await getTokenContext();
-    const authorityTokenAccount = getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey);
+    const tx = await program.methods
+      .initReservedLands()
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        reservedLands: reservedLandsPda(programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Reserve (or release) the land_ids `start..=end` so buy_land rejects them
+   */
+  async function setReservedRange(start: number, end: number, reserved: boolean): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
 
     const tx = await program.methods
-      .withdrawTokens()
+      .setReservedRange(start, end, reserved)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        reservedLands: reservedLandsPda(programId)[0],
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Grant a land (reserved or not) to `recipient` without payment
+   */
+  async function adminGrantLand(landId: number, recipient: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+
+    if (await needsProfileMigration(recipient)) {
+      throw new Error("Recipient must migrate their profile first");
+    }
+
+    const [landMint] = landMintPda(landId, programId);
+    const tx = await program.methods
+      .adminGrantLand(landId)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        recipient,
+        landData: landDataPda(landId, programId)[0],
+        recipientProfile: userProfilePda(recipient, programId)[0],
+        landMint,
+        recipientLandTokenAccount: getUserTokenAccountAddress(landMint, recipient),
+        nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })])
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Set a land's level (pending earnings are settled at the old level first)
+   */
+  async function adminSetLevel(landId: number, level: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+    if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
+    if (level < 1 || level > MAX_LEVEL) throw new Error(`Level must be 1-${MAX_LEVEL}`);
+
+    const landData = await getLandData(landId);
+    if (!landData) throw new Error(`Land #${landId} not found`);
+
+    const tx = await program.methods
+      .adminSetLevel(landId, level)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(landId, programId)[0],
+        ownerProfile: userProfilePda(landData.owner, programId)[0],
+      })
+      .preInstructions(await buildLandMigrationInstructions(landId))
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Subscribe to admin audit events (land grants and level changes). Returns listener IDs for
+   * removeAuditListeners.
+   */
+  function addAuditListeners(handlers: {
+    onLandGranted?: (event: LandGrantedEvent) => void;
+    onLandLevelSet?: (event: LandLevelSetEvent) => void;
+  }): number[] {
+    const listeners: number[] = [];
+    if (handlers.onLandGranted) {
+      listeners.push(program.addEventListener("landGranted", handlers.onLandGranted as any));
+    }
+    if (handlers.onLandLevelSet) {
+      listeners.push(program.addEventListener("landLevelSet", handlers.onLandLevelSet as any));
+    }
+    return listeners;
+  }
+
+  async function removeAuditListeners(listeners: number[]): Promise<void> {
+    await Promise.all(listeners.map((listener) => program.removeEventListener(listener)));
+  }
+
+  /**
+   * Subscribe to revenue split reports from land purchases and upgrades. Returns a listener ID
+   * for removeRevenueListener.
+   */
+  function addRevenueListener(handler: (event: RevenueCollectedEvent) => void): number {
+    return program.addEventListener("revenueCollected", handler as any);
+  }
+
+  async function removeRevenueListener(listener: number): Promise<void> {
+    await program.removeEventListener(listener);
+  }
+
+  async function setReferralBps(referralBps: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .setReferralBps(referralBps)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Start the next season lasting `duration` seconds (owner only)
+   */
+  async function startSeason(duration: number, prizePool: BN): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+
+    const tx = await program.methods
+      .startSeason(new BN(duration), prizePool)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        season: seasonPda(state.currentSeason + 1, programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Close the ended season, freezing its leaderboard (owner only).
+   * Winners then claim their prizes with claimSeasonPrize.
+   */
+  async function closeSeason(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+
+    const tx = await program.methods
+      .closeSeason()
       .accounts({
         authority: wallet.publicKey,
         gameState: gameStateAddress,
+        season: seasonPda(state.currentSeason, programId)[0],
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Claim the wallet's leaderboard prize of a closed season
+   */
+  async function claimSeasonPrize(seasonId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const ctx = await getTokenContext();
+    const tx = await program.methods
+      .claimSeasonPrize(seasonId)
+      .accounts({
+        user: wallet.publicKey,
+        gameState: gameStateAddress,
+        season: seasonPda(seasonId, programId)[0],
         tokenMint: ctx.tokenMint,
         tokenVault: ctx.tokenVaultAddress,
-        authorityTokenAccount,
+        userTokenAccount: getUserTokenAccountAddress(ctx.tokenMint, wallet.publicKey),
         tokenProgram: TOKEN_2022_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -870,11 +3316,48 @@ export function createColonyClient(config: ColonyClientConfig) {
     return tx;
   }
 
-  async function setGameActive(isActive: boolean): Promise<string> {
+  async function setLandNftUri(uri: string): Promise<string> {
     if (!wallet) throw new Error("Wallet required");
 
     const tx = await program.methods
-      .setGameActive(isActive)
+      .setLandNftUri(uri)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Start switching the game token to `newMint` (owner only). The game is paused until the
+   * migration finishes; drain the vaults (withdrawTokens, players unstake or migrateTokenMintReturnStake, then
+   * migrateTokenMintSweepStakeVault), then call
+   * migrateTokenMintCloseVaults and migrateTokenMintFinish.
+   */
+  async function migrateTokenMintStart(newMint: PublicKey): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .migrateTokenMintStart(newMint)
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Abort a token mint migration that is still draining (owner only)
+   */
+  async function migrateTokenMintCancel(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .migrateTokenMintCancel()
       .accounts({
         authority: wallet.publicKey,
         gameState: gameStateAddress,
@@ -884,28 +3367,65 @@ export function createColonyClient(config: ColonyClientConfig) {
     return tx;
   }
 
-  async function setTokenMint(newMint: PublicKey): Promise<string> {
+  /**
+   * Return the stake of `staker` from the old mint's stake vault, penalty free (owner only)
+   */
+  async function migrateTokenMintReturnStake(staker: PublicKey): Promise<string> {
     if (!wallet) throw new Error("Wallet required");
 
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+    if (state.tokenMigration !== "draining") throw new Error("No token mint migration is draining");
+    const mintInfo = await connection.getAccountInfo(state.tokenMint);
+    if (!mintInfo) throw new Error(`Mint ${state.tokenMint.toBase58()} not found`);
+
     const tx = await program.methods
-      .setTokenMint(newMint)
+      .migrateTokenMintReturnStake()
       .accounts({
         authority: wallet.publicKey,
         gameState: gameStateAddress,
+        staker,
+        stakerProfile: userProfilePda(staker, programId)[0],
+        tokenMint: state.tokenMint,
+        stakeVault: stakeVaultAddress,
+        stakerTokenAccount: getAssociatedTokenAddressSync(state.tokenMint, staker, true, mintInfo.owner),
+        tokenProgram: mintInfo.owner,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .rpc();
 
     return tx;
   }
 
-  async function migrateV2(): Promise<string> {
+  /**
+   * Move tokens sent to the stake vault outside of `stake` to the owner, once every stake
+   * has been returned (owner only)
+   */
+  async function migrateTokenMintSweepStakeVault(): Promise<string> {
     if (!wallet) throw new Error("Wallet required");
 
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+    if (state.tokenMigration !== "draining") throw new Error("No token mint migration is draining");
+    const mintInfo = await connection.getAccountInfo(state.tokenMint);
+    if (!mintInfo) throw new Error(`Mint ${state.tokenMint.toBase58()} not found`);
+
     const tx = await program.methods
-      .migrateV2()
+      .migrateTokenMintSweepStakeVault()
       .accounts({
         authority: wallet.publicKey,
         gameState: gameStateAddress,
+        tokenMint: state.tokenMint,
+        stakeVault: stakeVaultAddress,
+        authorityTokenAccount: getAssociatedTokenAddressSync(
+          state.tokenMint,
+          wallet.publicKey,
+          true,
+          mintInfo.owner
+        ),
+        tokenProgram: mintInfo.owner,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -913,7 +3433,84 @@ export function createColonyClient(config: ColonyClientConfig) {
     return tx;
   }
 
-  async function adminCloseLand(landId: number): Promise<string> {
+  /**
+   * Close the drained token and stake vaults of the old mint (owner only)
+   */
+  async function migrateTokenMintCloseVaults(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+    if (state.tokenMigration !== "draining") throw new Error("No token mint migration is draining");
+    const mintInfo = await connection.getAccountInfo(state.tokenMint);
+    if (!mintInfo) throw new Error(`Mint ${state.tokenMint.toBase58()} not found`);
+
+    const tx = await program.methods
+      .migrateTokenMintCloseVaults()
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        tokenVault: state.tokenVaultBump !== 0 ? tokenVaultAddress : null,
+        stakeVault: state.stakeVaultBump !== 0 ? stakeVaultAddress : null,
+        tokenProgram: mintInfo.owner,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Switch to the pending mint, create its token vault and resume the game (owner only).
+   * Re-create the stake vault with initStakeVault afterwards.
+   */
+  async function migrateTokenMintFinish(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const state = await getGameState();
+    if (!state) throw new Error("Game not initialized");
+    if (state.tokenMigration !== "vaultsClosed") throw new Error("Token vaults are not closed");
+    const mintInfo = await connection.getAccountInfo(state.pendingTokenMint);
+    if (!mintInfo) throw new Error(`Mint ${state.pendingTokenMint.toBase58()} not found`);
+
+    const tx = await program.methods
+      .migrateTokenMintFinish()
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        newTokenMint: state.pendingTokenMint,
+        tokenVault: tokenVaultAddress,
+        tokenProgram: mintInfo.owner,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Upgrade GameState to the current layout version (owner only)
+   */
+  async function migrateGameState(): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const tx = await program.methods
+      .migrateGameState()
+      .accounts({
+        authority: wallet.publicKey,
+        gameState: gameStateAddress,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Build admin_close_land for a land (admin only), closing its rental or offer too. While the
+   * land NFT exists its holder must co-sign the transaction to burn it; send it once both
+   * signatures are in.
+   */
+  async function buildAdminCloseLandTransaction(landId: number): Promise<Transaction> {
     if (!wallet) throw new Error("Wallet required");
     if (!isValidLandId(landId)) throw new Error("Invalid land ID (must be 1-21000)");
 
@@ -922,6 +3519,9 @@ export function createColonyClient(config: ColonyClientConfig) {
     if (!landData) throw new Error(`Land #${landId} not found`);
 
     const [userProfileAddress] = userProfilePda(landData.owner, programId);
+    const [landMint] = landMintPda(landId, programId);
+    const holder = await getLandNftHolder(landId);
+    const rental = await getRental(landId);
 
     const tx = await program.methods
       .adminCloseLand(landId)
@@ -930,12 +3530,38 @@ export function createColonyClient(config: ColonyClientConfig) {
         gameState: gameStateAddress,
         landData: landDataAddress,
         userProfile: userProfileAddress,
+        landMint,
+        holder: holder?.owner ?? null,
+        holderTokenAccount: holder?.tokenAccount ?? null,
+        rental: rentalPda(landId, programId)[0],
+        rentalOwner: rental?.owner ?? null,
+        nftTokenProgram: TOKEN_2022_PROGRAM_ID,
       })
-      .rpc();
+      .transaction();
+    const { blockhash } = await connection.getLatestBlockhash("confirmed");
+    tx.recentBlockhash = blockhash;
+    tx.feePayer = wallet.publicKey;
 
     return tx;
   }
 
+  /**
+   * Close a land (admin only). Lands whose NFT is held by another wallet need
+   * buildAdminCloseLandTransaction and the holder's signature.
+   */
+  async function adminCloseLand(landId: number): Promise<string> {
+    if (!wallet) throw new Error("Wallet required");
+
+    const holder = await getLandNftHolder(landId);
+    if (holder && !holder.owner.equals(wallet.publicKey)) {
+      throw new Error(`Land #${landId} NFT holder ${holder.owner.toBase58()} must co-sign`);
+    }
+
+    const tx = await buildAdminCloseLandTransaction(landId);
+    const signedTx = await wallet.signTransaction(tx);
+    return sendAndConfirmTx(signedTx);
+  }
+
   async function adminCloseUserProfile(userPubkey: PublicKey): Promise<string> {
     if (!wallet) throw new Error("Wallet required");
 
@@ -964,6 +3590,8 @@ export function createColonyClient(config: ColonyClientConfig) {
     vaultAddress,
     tokenVaultAddress,
     getLandDataAddress: (landId: number) => landDataPda(landId, programId)[0],
+    getLandMintAddress: (landId: number) => landMintPda(landId, programId)[0],
+    getRentalAddress: (landId: number) => rentalPda(landId, programId)[0],
     getUserProfileAddress: (user: PublicKey) => userProfilePda(user, programId)[0],
     getUserTokenAccountAddress,
 
@@ -972,6 +3600,13 @@ export function createColonyClient(config: ColonyClientConfig) {
     getLandData,
     isLandOwned,
     isLandOwnedBy,
+    getLandNftHolder,
+    getRental,
+    getGuild,
+    getMegaPlot,
+    getPaymentMint,
+    getSeason,
+    getRandomnessRequest,
     getVaultBalance,
     getTokenVaultBalance,
     getUserTokenBalance,
@@ -979,28 +3614,106 @@ export function createColonyClient(config: ColonyClientConfig) {
     getPendingEarnings,
     getUserProfile,
     getUserLandCount,
+    getPendingResources,
+    getClaimStreak,
+    needsProfileMigration,
+    needsGameStateMigration,
     getAllSoldLandIds,
     getUserLandIds,
+    isLandReserved,
 
     // Write methods
     initializeGame,
-    initTokenVault,
+    initStakeVault,
     buyLand,
+    buyLandWithSol,
+    buyLandWithToken,
     claimEarnings,
     claimAllEarnings,
+    claimReferralRewards,
+    claimAchievementReward,
+    requestLandEvent,
+    fulfilLandEvent,
+    buildDefense,
+    requestRaid,
+    fulfilRaid,
+    cancelRandomnessRequest,
+    mergeLands,
+    splitMegaPlot,
     upgradeLand,
+    upgradeLandWithToken,
+    compound,
+    stake,
+    unstake,
+    createGuild,
+    inviteToGuild,
+    revokeGuildInvite,
+    joinGuild,
+    leaveGuild,
+    kickGuildMember,
+    dissolveGuild,
+    setGuildTax,
+    refreshGuildBonus,
+    withdrawGuildTreasury,
+    migrateGuildVault,
+    craftTokens,
+    setPayoutAddress,
+    mintLandNft,
+    syncLandOwner,
+    refreshLandMetadata,
+    createRentalOffer,
+    acceptRental,
+    endRental,
     withdrawSol,
     withdrawTokens,
     setGameActive,
-    setTokenMint,
-    migrateV2,
+    setCraftRates,
+    setReferralBps,
+    setAchievementRewards,
+    setRandomnessSource,
+    setSalePhase,
+    setPurchaseLimits,
+    setLandPriceLamports,
+    setRevenueSplit,
+    setPaymentMint,
+    initReservedLands,
+    setReservedRange,
+    adminGrantLand,
+    adminSetLevel,
+    addAuditListeners,
+    removeAuditListeners,
+    addRevenueListener,
+    removeRevenueListener,
+    startSeason,
+    closeSeason,
+    claimSeasonPrize,
+    setLandNftUri,
+    migrateTokenMintStart,
+    migrateTokenMintCancel,
+    migrateTokenMintReturnStake,
+    migrateTokenMintSweepStakeVault,
+    migrateTokenMintCloseVaults,
+    migrateTokenMintFinish,
+    migrateGameState,
+    migrateUserProfile,
+    migrateLandData,
     adminCloseLand,
+    buildAdminCloseLandTransaction,
     adminCloseUserProfile,
 
     // Helpers
     calculateEarnings,
+    calculateStakeBonus,
+    calculateBoostedEarnings,
+    getStakeBonusBps,
+    calculateResources,
     getUpgradeCost,
+    getUpgradeResourceCost,
     getEarningSpeed,
+    getLandRegion,
+    hasAchievement,
+    getCurrentClaimStreak,
+    getStreakBonusBps,
     isValidLandId,
   };
 }
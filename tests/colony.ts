@@ -2,20 +2,35 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { Colony } from "../target/types/colony";
 import { expect } from "chai";
+import { createHash } from "crypto";
 import {
   Keypair,
   PublicKey,
   SystemProgram,
   LAMPORTS_PER_SOL,
+  Transaction,
+  TransactionInstruction,
+  sendAndConfirmTransaction,
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddressSync,
   createMint,
   mintTo,
   getOrCreateAssociatedTokenAccount,
   getAccount,
+  getMint,
+  transferChecked,
+  closeAccount,
+  getPermanentDelegate,
+  getMintCloseAuthority,
+  ExtensionType,
+  getMintLen,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createInitializeNonTransferableMintInstruction,
 } from "@solana/spl-token";
 
 // ============================================================================
@@ -26,12 +41,16 @@ const TOKEN_MULTIPLIER = 10 ** TOKEN_DECIMALS;
 const LAND_PRICE = 100 * TOKEN_MULTIPLIER;
 const MAX_LANDS_PER_USER = 10;
 const SECONDS_PER_DAY = 86400;
-const EARNING_SPEEDS = [72, 108, 162, 243, 365, 547, 820, 1230, 1845, 2768].map(
+const EARNING_SPEEDS = [1_000, 2_000, 3_000, 5_000, 8_000, 13_000, 21_000, 34_000, 45_000, 79_000].map(
   (s) => s * TOKEN_MULTIPLIER
 );
 const UPGRADE_COSTS = [24, 48, 72, 96, 192, 384, 768, 1536, 3072].map(
   (c) => c * TOKEN_MULTIPLIER
 );
+const RAID_COST = 100 * TOKEN_MULTIPLIER;
+const DEFENSE_COSTS = [500, 1_500, 4_000].map((c) => c * TOKEN_MULTIPLIER);
+const MEGA_PLOT_BONUS_BPS_PER_LAND = 500;
+const MAX_MEGA_PLOT_LANDS = 4;
 
 // ============================================================================
 // HELPERS
@@ -49,6 +68,73 @@ function landDataPda(
   );
 }
 
+function randomnessRequestPda(
+  purpose: number,
+  landId: number,
+  programId: PublicKey
+): [PublicKey, number] {
+  const buf = Buffer.alloc(2);
+  buf.writeUInt16LE(landId);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("randomness"), Buffer.from([purpose]), buf],
+    programId
+  );
+}
+
+function reservedLandsPda(programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from("reserved_lands")], programId);
+}
+
+function paymentMintPda(mint: PublicKey, programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("payment_mint"), mint.toBuffer()],
+    programId
+  );
+}
+
+function megaPlotPda(
+  anchorLandId: number,
+  programId: PublicKey
+): [PublicKey, number] {
+  const buf = Buffer.alloc(2);
+  buf.writeUInt16LE(anchorLandId);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("mega_plot"), buf],
+    programId
+  );
+}
+
+function landMintPda(
+  landId: number,
+  programId: PublicKey
+): [PublicKey, number] {
+  const buf = Buffer.alloc(2);
+  buf.writeUInt16LE(landId);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("land_mint"), buf],
+    programId
+  );
+}
+
+function landNftAta(landId: number, owner: PublicKey, programId: PublicKey) {
+  const [landMint] = landMintPda(landId, programId);
+  return getAssociatedTokenAddressSync(
+    landMint,
+    owner,
+    false,
+    TOKEN_2022_PROGRAM_ID
+  );
+}
+
+function rentalPda(landId: number, programId: PublicKey): [PublicKey, number] {
+  const buf = Buffer.alloc(2);
+  buf.writeUInt16LE(landId);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("rental"), buf],
+    programId
+  );
+}
+
 function userProfilePda(
   user: PublicKey,
   programId: PublicKey
@@ -97,20 +183,60 @@ describe("colony", () => {
   let user1Ata: PublicKey;
   let user2Ata: PublicKey;
 
+  // Helper: revenue split recipients, passed only when their share is set
+  function revenueAccounts(state: any) {
+    return {
+      tokenVault: state.revenueVaultBps > 0 ? tokenVaultAddress : null,
+      teamTokenAccount: state.revenueTeamBps > 0 ? state.teamWallet : null,
+    };
+  }
+
+  // Helper: Token-2022 mint with the given extensions (payer is the mint authority)
+  async function createToken2022Mint(
+    extensions: ExtensionType[],
+    initExtensions: (mint: PublicKey) => TransactionInstruction[]
+  ): Promise<PublicKey> {
+    const mintKeypair = Keypair.generate();
+    const space = getMintLen(extensions);
+    const tx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        newAccountPubkey: mintKeypair.publicKey,
+        space,
+        lamports: await connection.getMinimumBalanceForRentExemption(space),
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      ...initExtensions(mintKeypair.publicKey),
+      createInitializeMintInstruction(
+        mintKeypair.publicKey,
+        TOKEN_DECIMALS,
+        payer.publicKey,
+        null,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    await sendAndConfirmTransaction(connection, tx, [payer, mintKeypair]);
+    return mintKeypair.publicKey;
+  }
+
   // Helper: buy land for a user
   async function buyLandForUser(
     user: Keypair,
     userAta: PublicKey,
-    landId: number
+    landId: number,
+    referrer: PublicKey | null = null,
+    proof: number[][] = []
   ) {
     const [landDataAddress] = landDataPda(landId, program.programId);
     const [userProfileAddress] = userProfilePda(
       user.publicKey,
       program.programId
     );
+    const [landMint] = landMintPda(landId, program.programId);
+    const state = await (program.account as any).gameState.fetch(gameStateAddress);
 
-    await program.methods
-      .buyLand(landId)
+    return program.methods
+      .buyLand(landId, proof)
       .accounts({
         user: user.publicKey,
         gameState: gameStateAddress,
@@ -118,16 +244,50 @@ describe("colony", () => {
         userProfile: userProfileAddress,
         tokenMint: mint,
         userTokenAccount: userAta,
+        landMint,
+        userLandTokenAccount: landNftAta(landId, user.publicKey, program.programId),
+        referrerProfile: referrer
+          ? userProfilePda(referrer, program.programId)[0]
+          : null,
+        reservedLands: state.reservedLandsEnabled ? reservedLandsPda(program.programId)[0] : null,
+        ...revenueAccounts(state),
         tokenProgram: TOKEN_PROGRAM_ID,
+        nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .signers([user])
       .rpc();
   }
 
-  // Helper: claim earnings for a user
-  async function claimForUser(user: Keypair, userAta: PublicKey, landId: number) {
+  // Helper: current season account while a season is open (scores claims and upgrades)
+  async function openSeasonAddress(): Promise<PublicKey | null> {
+    const state = await (program.account as any).gameState.fetch(gameStateAddress);
+    if (!state.seasonOpen) return null;
+    const id = Buffer.alloc(4);
+    id.writeUInt32LE(state.currentSeason);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("season"), id],
+      program.programId
+    )[0];
+  }
+
+  // Helper: claim earnings for a user (pass `ownerAta` to claim as renter)
+  async function claimForUser(
+    user: Keypair,
+    userAta: PublicKey,
+    landId: number,
+    ownerAta: PublicKey | null = null,
+    payoutAta: PublicKey | null = null
+  ) {
     const [landDataAddress] = landDataPda(landId, program.programId);
+    const land = await (program.account as any).landData.fetch(landDataAddress);
+    const [ownerProfileAddress] = userProfilePda(land.owner, program.programId);
+    const ownerProfile = await (program.account as any).userProfile.fetch(
+      ownerProfileAddress
+    );
+    const referred = !ownerProfile.referrer.equals(PublicKey.default);
+    const inGuild = !ownerProfile.guild.equals(PublicKey.default);
 
     await program.methods
       .claimEarnings(landId)
@@ -135,9 +295,24 @@ describe("colony", () => {
         user: user.publicKey,
         gameState: gameStateAddress,
         landData: landDataAddress,
+        userProfile: ownerProfileAddress,
+        rental: ownerAta ? rentalPda(landId, program.programId)[0] : null,
+        referrerProfile: referred
+          ? userProfilePda(ownerProfile.referrer, program.programId)[0]
+          : null,
+        season: await openSeasonAddress(),
+        guild: inGuild ? ownerProfile.guild : null,
+        guildVault: inGuild
+          ? PublicKey.findProgramAddressSync(
+              [Buffer.from("guild_vault"), ownerProfile.guild.toBuffer()],
+              program.programId
+            )[0]
+          : null,
         tokenMint: mint,
         tokenVault: tokenVaultAddress,
         userTokenAccount: userAta,
+        payoutTokenAccount: payoutAta,
+        ownerTokenAccount: ownerAta,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -153,21 +328,44 @@ describe("colony", () => {
     landId: number
   ) {
     const [landDataAddress] = landDataPda(landId, program.programId);
+    const [userProfileAddress] = userProfilePda(
+      user.publicKey,
+      program.programId
+    );
+    const state = await (program.account as any).gameState.fetch(gameStateAddress);
 
-    await program.methods
+    return program.methods
       .upgradeLand(landId)
       .accounts({
         user: user.publicKey,
         gameState: gameStateAddress,
         landData: landDataAddress,
+        userProfile: userProfileAddress,
+        season: await openSeasonAddress(),
         tokenMint: mint,
         userTokenAccount: userAta,
+        ...revenueAccounts(state),
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([user])
       .rpc();
   }
 
+  // Helper: all of a wallet's lands as writable remaining accounts, in land id order
+  async function ownedLandAccounts(owner: PublicKey) {
+    const lands = await program.account.landData.all([
+      { memcmp: { offset: 10, bytes: owner.toBase58() } },
+    ]);
+    return lands
+      .map((l: any) => l.account.landId as number)
+      .sort((a, b) => a - b)
+      .map((landId) => ({
+        pubkey: landDataPda(landId, program.programId)[0],
+        isWritable: true,
+        isSigner: false,
+      }));
+  }
+
   // ============================================================================
   // SETUP
   // ============================================================================
@@ -216,9 +414,9 @@ describe("colony", () => {
       console.log("    Token mint:", mint.toBase58());
     });
 
-    it("sets token mint on game state", async () => {
+    it("starts the token mint migration (no vaults yet)", async () => {
       await program.methods
-        .setTokenMint(mint)
+        .migrateTokenMintStart(mint)
         .accounts({
           authority: payer.publicKey,
           gameState: gameStateAddress,
@@ -228,22 +426,31 @@ describe("colony", () => {
       const gs = await (program.account as any).gameState.fetch(
         gameStateAddress
       );
-      expect(gs.tokenMint.toBase58()).to.equal(mint.toBase58());
+      expect(gs.tokenMigration).to.have.property("vaultsClosed");
+      expect(gs.pendingTokenMint.toBase58()).to.equal(mint.toBase58());
+      expect(gs.isActive).to.be.false;
     });
 
-    it("initializes token vault PDA", async () => {
+    it("finishes the migration, creating the token vault PDA", async () => {
       await program.methods
-        .initTokenVault()
+        .migrateTokenMintFinish()
         .accounts({
           authority: payer.publicKey,
           gameState: gameStateAddress,
-          tokenMint: mint,
+          newTokenMint: mint,
           tokenVault: tokenVaultAddress,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .rpc();
 
+      const gs = await (program.account as any).gameState.fetch(
+        gameStateAddress
+      );
+      expect(gs.tokenMint.toBase58()).to.equal(mint.toBase58());
+      expect(gs.tokenMigration).to.have.property("idle");
+      expect(gs.isActive).to.be.true;
+
       const vaultAccount = await getAccount(connection, tokenVaultAddress);
       expect(vaultAccount.mint.toBase58()).to.equal(mint.toBase58());
     });
@@ -611,7 +818,7 @@ describe("colony", () => {
       const land1 = await (program.account as any).landData.fetch(land1Addr);
       const land3 = await (program.account as any).landData.fetch(land3Addr);
 
-      // Calculate expected ratio: level 10 speed / level 1 speed = 2768/72 ≈ 38.4x
+      // Calculate expected ratio: level 10 speed / level 1 speed = 79000/1000 = 79x
       // Just verify level 10 land has more pending earnings in general
       // (exact timing comparison is tricky in tests)
       expect(EARNING_SPEEDS[9]).to.be.greaterThan(EARNING_SPEEDS[0]);
@@ -711,6 +918,29 @@ describe("colony", () => {
   // 7. ADMIN CLOSE OPERATIONS
   // ============================================================================
   describe("7. Admin Operations", () => {
+    it("rejects closing a land with an NFT without the holder's signature", async () => {
+      try {
+        await program.methods
+          .adminCloseLand(100)
+          .accounts({
+            authority: payer.publicKey,
+            gameState: gameStateAddress,
+            landData: landDataPda(100, program.programId)[0],
+            userProfile: userProfilePda(user2.publicKey, program.programId)[0],
+            landMint: landMintPda(100, program.programId)[0],
+            holder: null,
+            holderTokenAccount: landNftAta(100, user2.publicKey, program.programId),
+            rental: rentalPda(100, program.programId)[0],
+            rentalOwner: null,
+            nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NotNftHolder");
+      }
+    });
+
     it("admin closes land #100 (owned by user2)", async () => {
       const [landAddr] = landDataPda(100, program.programId);
       const [userProfileAddr] = userProfilePda(
@@ -732,13 +962,31 @@ describe("colony", () => {
           gameState: gameStateAddress,
           landData: landAddr,
           userProfile: userProfileAddr,
+          landMint: landMintPda(100, program.programId)[0],
+          holder: user2.publicKey,
+          holderTokenAccount: landNftAta(100, user2.publicKey, program.programId),
+          rental: rentalPda(100, program.programId)[0],
+          rentalOwner: null,
+          nftTokenProgram: TOKEN_2022_PROGRAM_ID,
         })
+        .signers([user2])
         .rpc();
 
       // Land account should be closed
       const landAccount = await connection.getAccountInfo(landAddr);
       expect(landAccount).to.be.null;
 
+      // The NFT is burned and its mint closed, so the land can be sold again
+      const nftAccount = await getAccount(
+        connection,
+        landNftAta(100, user2.publicKey, program.programId),
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      expect(Number(nftAccount.amount)).to.equal(0);
+      const mintAccount = await connection.getAccountInfo(landMintPda(100, program.programId)[0]);
+      expect(mintAccount).to.be.null;
+
       // User profile lands_owned decreased
       const profileAfter = await (program.account as any).userProfile.fetch(
         userProfileAddr
@@ -768,7 +1016,14 @@ describe("colony", () => {
           gameState: gameStateAddress,
           landData: landAddr,
           userProfile: userProfileAddr,
+          landMint: landMintPda(101, program.programId)[0],
+          holder: user2.publicKey,
+          holderTokenAccount: landNftAta(101, user2.publicKey, program.programId),
+          rental: rentalPda(101, program.programId)[0],
+          rentalOwner: null,
+          nftTokenProgram: TOKEN_2022_PROGRAM_ID,
         })
+        .signers([user2])
         .rpc();
 
       const profile = await (program.account as any).userProfile.fetch(
@@ -832,6 +1087,12 @@ describe("colony", () => {
             gameState: gameStateAddress,
             landData: landAddr,
             userProfile: userProfileAddr,
+            landMint: landMintPda(2, program.programId)[0],
+            holder: null,
+            holderTokenAccount: null,
+            rental: rentalPda(2, program.programId)[0],
+            rentalOwner: null,
+            nftTokenProgram: TOKEN_2022_PROGRAM_ID,
           })
           .signers([user2])
           .rpc();
@@ -840,6 +1101,23 @@ describe("colony", () => {
         expect(err.toString()).to.include("Unauthorized");
       }
     });
+
+    it("closed lands can be bought again with a fresh NFT", async () => {
+      await buyLandForUser(user2, user2Ata, 100);
+      await buyLandForUser(user2, user2Ata, 101);
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(100, program.programId)[0]
+      );
+      expect(land.owner.toBase58()).to.equal(user2.publicKey.toBase58());
+      const nftAccount = await getAccount(
+        connection,
+        landNftAta(100, user2.publicKey, program.programId),
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      expect(Number(nftAccount.amount)).to.equal(1);
+    });
   });
 
   // ============================================================================
@@ -886,11 +1164,95 @@ describe("colony", () => {
       }
     });
 
-    it("non-authority cannot set token mint", async () => {
+    it("non-authority cannot migrate the token mint", async () => {
       const fakeMint = Keypair.generate().publicKey;
       try {
         await program.methods
-          .setTokenMint(fakeMint)
+          .migrateTokenMintStart(fakeMint)
+          .accounts({
+            authority: user1.publicKey,
+            gameState: gameStateAddress,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+  });
+
+  // ============================================================================
+  // 9. RESOURCES
+  // ============================================================================
+  describe("9. Resources", () => {
+    // Helper: craft tokens from a resource
+    async function craftForUser(
+      user: Keypair,
+      userAta: PublicKey,
+      resource: string,
+      amount: number
+    ) {
+      const [userProfileAddress] = userProfilePda(
+        user.publicKey,
+        program.programId
+      );
+
+      await program.methods
+        .craftTokens({ [resource]: {} } as any, new BN(amount))
+        .accounts({
+          user: user.publicKey,
+          gameState: gameStateAddress,
+          userProfile: userProfileAddress,
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          userTokenAccount: userAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+    }
+
+    it("claim credits produced resources to the user profile", async () => {
+      // Refill the vault emptied in section 6
+      await mintTo(
+        connection,
+        payer,
+        mint,
+        tokenVaultAddress,
+        payer,
+        1_000_000 * TOKEN_MULTIPLIER
+      );
+      await sleep(2000);
+
+      const [profileAddr] = userProfilePda(user1.publicKey, program.programId);
+      const before = await (program.account as any).userProfile.fetch(
+        profileAddr
+      );
+
+      await claimForUser(user1, user1Ata, 3);
+
+      const after = await (program.account as any).userProfile.fetch(
+        profileAddr
+      );
+      expect(after.resources[0].gt(before.resources[0])).to.be.true;
+    });
+
+    it("rejects crafting while craft rates are zero (CraftingDisabled)", async () => {
+      try {
+        await craftForUser(user1, user1Ata, "ore", 1);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("CraftingDisabled");
+      }
+    });
+
+    it("non-authority cannot set craft rates", async () => {
+      try {
+        await program.methods
+          .setCraftRates([new BN(1), new BN(1), new BN(1)])
           .accounts({
             authority: user1.publicKey,
             gameState: gameStateAddress,
@@ -902,5 +1264,3080 @@ describe("colony", () => {
         expect(err.toString()).to.include("Unauthorized");
       }
     });
+
+    it("crafts ore into tokens at the configured rate", async () => {
+      const oreRate = 5 * TOKEN_MULTIPLIER; // 5 tokens per whole unit of ore
+      await program.methods
+        .setCraftRates([new BN(oreRate), new BN(0), new BN(0)])
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+        })
+        .rpc();
+
+      const [profileAddr] = userProfilePda(user1.publicKey, program.programId);
+      const profile = await (program.account as any).userProfile.fetch(
+        profileAddr
+      );
+      const ore = profile.resources[0].toNumber();
+      expect(ore).to.be.greaterThan(0);
+
+      const balanceBefore = Number(
+        (await getAccount(connection, user1Ata)).amount
+      );
+      await craftForUser(user1, user1Ata, "ore", ore);
+      const balanceAfter = Number(
+        (await getAccount(connection, user1Ata)).amount
+      );
+
+      expect(balanceAfter - balanceBefore).to.equal(
+        Math.floor((ore * oreRate) / 1_000)
+      );
+      const updated = await (program.account as any).userProfile.fetch(
+        profileAddr
+      );
+      expect(updated.resources[0].toNumber()).to.equal(0);
+    });
+  });
+
+  // ============================================================================
+  // 10. LAND NFTS
+  // ============================================================================
+  describe("10. Land NFTs", () => {
+    it("buyer holds the 1-of-1 land NFT", async () => {
+      const [landMint] = landMintPda(3, program.programId);
+      const nft = await getAccount(
+        connection,
+        landNftAta(3, user1.publicKey, program.programId),
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      expect(nft.mint.toBase58()).to.equal(landMint.toBase58());
+      expect(Number(nft.amount)).to.equal(1);
+
+      // The game can't freeze or move the holder's NFT
+      const mintInfo = await getMint(connection, landMint, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(mintInfo.freezeAuthority).to.equal(null);
+      expect(mintInfo.mintAuthority).to.equal(null);
+      expect(getPermanentDelegate(mintInfo)).to.equal(null);
+      expect(getMintCloseAuthority(mintInfo)?.closeAuthority.toBase58()).to.equal(
+        gameStateAddress.toBase58()
+      );
+    });
+
+    it("rejects sync while the NFT has not moved (OwnerAlreadySynced)", async () => {
+      try {
+        await syncLandOwner(3, user1.publicKey, user1.publicKey);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("OwnerAlreadySynced");
+      }
+    });
+
+    it("sync makes the new NFT holder the land owner", async () => {
+      const [landMint] = landMintPda(3, program.programId);
+      const destination = await getOrCreateAssociatedTokenAccount(
+        connection,
+        payer,
+        landMint,
+        user2.publicKey,
+        false,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      await transferChecked(
+        connection,
+        payer,
+        landNftAta(3, user1.publicKey, program.programId),
+        landMint,
+        destination.address,
+        user1,
+        1,
+        0,
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const [profileAddr] = userProfilePda(user2.publicKey, program.programId);
+      const profileBefore = await (program.account as any).userProfile.fetch(
+        profileAddr
+      );
+
+      await syncLandOwner(3, user1.publicKey, user2.publicKey);
+
+      const [landAddr] = landDataPda(3, program.programId);
+      const land = await (program.account as any).landData.fetch(landAddr);
+      expect(land.owner.toBase58()).to.equal(user2.publicKey.toBase58());
+
+      const profile = await (program.account as any).userProfile.fetch(
+        profileAddr
+      );
+      expect(profile.landsOwned).to.equal(profileBefore.landsOwned + 1);
+    });
+
+    async function syncLandOwner(
+      landId: number,
+      previousOwner: PublicKey,
+      holder: PublicKey
+    ) {
+      const [landMint] = landMintPda(landId, program.programId);
+      await program.methods
+        .syncLandOwner(landId)
+        .accounts({
+          payer: payer.publicKey,
+          landData: landDataPda(landId, program.programId)[0],
+          landMint,
+          holderTokenAccount: landNftAta(landId, holder, program.programId),
+          previousOwnerProfile: userProfilePda(previousOwner, program.programId)[0],
+          newOwnerProfile: userProfilePda(holder, program.programId)[0],
+          nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  });
+
+  // ============================================================================
+  // 11. LAND RENTAL
+  // ============================================================================
+  describe("11. Land Rental", () => {
+    const LAND_ID = 4; // owned by user1
+    const FEE = 1 * TOKEN_MULTIPLIER;
+    const OWNER_SHARE_BPS = 2_000;
+
+    it("owner offers land #4 to user2", async () => {
+      await program.methods
+        .createRentalOffer(
+          LAND_ID,
+          user2.publicKey,
+          new BN(SECONDS_PER_DAY),
+          new BN(FEE),
+          OWNER_SHARE_BPS
+        )
+        .accounts({
+          owner: user1.publicKey,
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          rental: rentalPda(LAND_ID, program.programId)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+      const rental = await (program.account as any).rental.fetch(
+        rentalPda(LAND_ID, program.programId)[0]
+      );
+      expect(rental.renter.toBase58()).to.equal(user2.publicKey.toBase58());
+      expect(rental.startTime.toNumber()).to.equal(0);
+    });
+
+    it("user2 accepts and pays the upfront fee to the owner", async () => {
+      const ownerBefore = Number((await getAccount(connection, user1Ata)).amount);
+
+      await program.methods
+        .acceptRental(LAND_ID)
+        .accounts({
+          renter: user2.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          rental: rentalPda(LAND_ID, program.programId)[0],
+          ownerProfile: userProfilePda(user1.publicKey, program.programId)[0],
+          owner: user1.publicKey,
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          renterTokenAccount: user2Ata,
+          ownerTokenAccount: user1Ata,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+      // Fee plus the owner's settled pending earnings
+      const ownerAfter = Number((await getAccount(connection, user1Ata)).amount);
+      expect(ownerAfter - ownerBefore).to.be.at.least(FEE);
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(LAND_ID, program.programId)[0]
+      );
+      expect(land.rentalEnd.toNumber()).to.be.greaterThan(0);
+    });
+
+    it("owner cannot claim while the land is rented (NotRenter)", async () => {
+      await sleep(2000);
+      try {
+        await claimForUser(user1, user1Ata, LAND_ID, user1Ata);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NotRenter");
+      }
+    });
+
+    it("renter claims and the owner receives its share", async () => {
+      const ownerBefore = Number((await getAccount(connection, user1Ata)).amount);
+      const renterBefore = Number((await getAccount(connection, user2Ata)).amount);
+
+      await claimForUser(user2, user2Ata, LAND_ID, user1Ata);
+
+      const ownerGain =
+        Number((await getAccount(connection, user1Ata)).amount) - ownerBefore;
+      const renterGain =
+        Number((await getAccount(connection, user2Ata)).amount) - renterBefore;
+      expect(renterGain).to.be.greaterThan(0);
+      expect(ownerGain).to.equal(
+        Math.floor(((ownerGain + renterGain) * OWNER_SHARE_BPS) / 10_000)
+      );
+    });
+
+    it("renter terminates early and the rental account is closed", async () => {
+      await program.methods
+        .endRental(LAND_ID)
+        .accounts({
+          signer: user2.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          rental: rentalPda(LAND_ID, program.programId)[0],
+          rentalOwner: user1.publicKey,
+          ownerProfile: userProfilePda(user1.publicKey, program.programId)[0],
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          ownerTokenAccount: user1Ata,
+          renterTokenAccount: user2Ata,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc();
+
+      const info = await connection.getAccountInfo(
+        rentalPda(LAND_ID, program.programId)[0]
+      );
+      expect(info).to.be.null;
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(LAND_ID, program.programId)[0]
+      );
+      expect(land.rentalEnd.toNumber()).to.equal(0);
+    });
+
+    // Loaded from tests/fixtures: land #901 (level 1) of a baseline profile, rented for the first
+    // day after mining launch at a 20% owner share; the rental expired long ago but was never ended
+    const rentalOwner = Keypair.fromSeed(new Uint8Array(32).fill(52));
+    const renter = Keypair.fromSeed(new Uint8Array(32).fill(53));
+    const RENTED_LAND = 901;
+    const RENTED_SHARE_BPS = 2_000;
+    const LEVEL_1_DAY_EARNINGS = 1_000 * TOKEN_MULTIPLIER; // EARNING_SPEEDS[0] of the program
+    let rentalOwnerAta: PublicKey;
+    let renterAta: PublicKey;
+
+    function endRentalAccounts(signer: PublicKey) {
+      return {
+        signer,
+        gameState: gameStateAddress,
+        landData: landDataPda(RENTED_LAND, program.programId)[0],
+        rental: rentalPda(RENTED_LAND, program.programId)[0],
+        rentalOwner: rentalOwner.publicKey,
+        ownerProfile: userProfilePda(rentalOwner.publicKey, program.programId)[0],
+        tokenMint: mint,
+        tokenVault: tokenVaultAddress,
+        ownerTokenAccount: rentalOwnerAta,
+        renterTokenAccount: renterAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+    }
+
+    it("nobody claims an expired rental's land until the rental is ended", async () => {
+      for (const wallet of [rentalOwner, renter]) {
+        const sig = await connection.requestAirdrop(wallet.publicKey, LAMPORTS_PER_SOL);
+        await connection.confirmTransaction(sig);
+      }
+      rentalOwnerAta = (
+        await getOrCreateAssociatedTokenAccount(connection, payer, mint, rentalOwner.publicKey)
+      ).address;
+      renterAta = (
+        await getOrCreateAssociatedTokenAccount(connection, payer, mint, renter.publicKey)
+      ).address;
+      await mintTo(connection, payer, mint, tokenVaultAddress, payer, 1_000_000 * TOKEN_MULTIPLIER);
+      await program.methods
+        .migrateUserProfile()
+        .accounts({
+          user: rentalOwner.publicKey,
+          userProfile: userProfilePda(rentalOwner.publicKey, program.programId)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([rentalOwner])
+        .rpc();
+
+      // The renter's right to claim ended with the rental
+      try {
+        await claimForUser(renter, renterAta, RENTED_LAND, rentalOwnerAta);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NotLandOwner");
+      }
+      // The owner can't claim the renter's unsettled period either
+      try {
+        await claimForUser(rentalOwner, rentalOwnerAta, RENTED_LAND);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("RentalNotEnded");
+      }
+    });
+
+    it("anyone ends an expired rental, settling only the rental period", async () => {
+      const rental = await (program.account as any).rental.fetch(
+        rentalPda(RENTED_LAND, program.programId)[0]
+      );
+      const ownerBefore = Number((await getAccount(connection, rentalOwnerAta)).amount);
+      const renterBefore = Number((await getAccount(connection, renterAta)).amount);
+
+      await program.methods
+        .endRental(RENTED_LAND)
+        .accounts(endRentalAccounts(payer.publicKey))
+        .rpc();
+
+      // Exactly one day of level 1 earnings, split by the owner share
+      const ownerShare = Math.floor((LEVEL_1_DAY_EARNINGS * RENTED_SHARE_BPS) / 10_000);
+      expect(Number((await getAccount(connection, renterAta)).amount) - renterBefore).to.equal(
+        LEVEL_1_DAY_EARNINGS - ownerShare
+      );
+      expect(Number((await getAccount(connection, rentalOwnerAta)).amount) - ownerBefore).to.equal(
+        ownerShare
+      );
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(RENTED_LAND, program.programId)[0]
+      );
+      expect(land.rentalEnd.toNumber()).to.equal(0);
+      expect(land.lastCheckout.eq(rental.endTime)).to.equal(true);
+      expect(await connection.getAccountInfo(rentalPda(RENTED_LAND, program.programId)[0])).to.be
+        .null;
+    });
+
+    it("the owner claims everything earned after the rental", async () => {
+      const before = Number((await getAccount(connection, rentalOwnerAta)).amount);
+      await claimForUser(rentalOwner, rentalOwnerAta, RENTED_LAND);
+
+      // Months have passed since the rental ended, far more than its single day
+      const gained = Number((await getAccount(connection, rentalOwnerAta)).amount) - before;
+      expect(gained).to.be.greaterThan(LEVEL_1_DAY_EARNINGS);
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(RENTED_LAND, program.programId)[0]
+      );
+      expect(land.lastCheckout.toNumber()).to.be.closeTo(Math.floor(Date.now() / 1000), 60);
+    });
+
+    it("admin closing a rented land closes its rental too", async () => {
+      const [rentalAddress] = rentalPda(RENTED_LAND, program.programId);
+      await program.methods
+        .createRentalOffer(RENTED_LAND, renter.publicKey, new BN(SECONDS_PER_DAY), new BN(0), 0)
+        .accounts({
+          owner: rentalOwner.publicKey,
+          landData: landDataPda(RENTED_LAND, program.programId)[0],
+          rental: rentalAddress,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([rentalOwner])
+        .rpc();
+      await program.methods
+        .acceptRental(RENTED_LAND)
+        .accounts({
+          renter: renter.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(RENTED_LAND, program.programId)[0],
+          rental: rentalAddress,
+          ownerProfile: userProfilePda(rentalOwner.publicKey, program.programId)[0],
+          owner: rentalOwner.publicKey,
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          renterTokenAccount: renterAta,
+          ownerTokenAccount: rentalOwnerAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([renter])
+        .rpc();
+
+      const rentalRent = (await connection.getAccountInfo(rentalAddress))!.lamports;
+      const ownerLamportsBefore = await connection.getBalance(rentalOwner.publicKey);
+      const adminCloseAccounts = {
+        authority: payer.publicKey,
+        gameState: gameStateAddress,
+        landData: landDataPda(RENTED_LAND, program.programId)[0],
+        userProfile: userProfilePda(rentalOwner.publicKey, program.programId)[0],
+        landMint: landMintPda(RENTED_LAND, program.programId)[0],
+        holder: null,
+        holderTokenAccount: null,
+        rental: rentalAddress,
+        rentalOwner: null,
+        nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+      };
+
+      // The rental can't be left behind
+      try {
+        await program.methods.adminCloseLand(RENTED_LAND).accounts(adminCloseAccounts).rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("RentalOwnerRequired");
+      }
+
+      await program.methods
+        .adminCloseLand(RENTED_LAND)
+        .accounts({ ...adminCloseAccounts, rentalOwner: rentalOwner.publicKey })
+        .rpc();
+
+      expect(await connection.getAccountInfo(landDataPda(RENTED_LAND, program.programId)[0])).to.be
+        .null;
+      expect(await connection.getAccountInfo(rentalAddress)).to.be.null;
+      expect(await connection.getBalance(rentalOwner.publicKey)).to.equal(
+        ownerLamportsBefore + rentalRent
+      );
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(rentalOwner.publicKey, program.programId)[0]
+      );
+      expect(profile.landsOwned).to.equal(0);
+    });
+  });
+
+  // ============================================================================
+  // 12. PAYOUT ADDRESS
+  // ============================================================================
+  describe("12. Payout Address", () => {
+    async function setPayoutAddress(user: Keypair, payoutAddress: PublicKey) {
+      await program.methods
+        .setPayoutAddress(payoutAddress)
+        .accounts({
+          user: user.publicKey,
+          userProfile: userProfilePda(user.publicKey, program.programId)[0],
+        })
+        .signers([user])
+        .rpc();
+    }
+
+    it("claims into an explicit destination token account", async () => {
+      await sleep(2000);
+      const before = Number((await getAccount(connection, user2Ata)).amount);
+
+      await claimForUser(user1, user1Ata, 5, null, user2Ata);
+
+      const after = Number((await getAccount(connection, user2Ata)).amount);
+      expect(after).to.be.greaterThan(before);
+    });
+
+    it("requires a payout account once a default payout address is set", async () => {
+      await setPayoutAddress(user1, user2.publicKey);
+      await sleep(2000);
+      try {
+        await claimForUser(user1, user1Ata, 5);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("PayoutAccountRequired");
+      }
+    });
+
+    it("rejects a destination not owned by the default payout address", async () => {
+      try {
+        await claimForUser(user1, user1Ata, 5, null, user1Ata);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("PayoutAddressMismatch");
+      }
+    });
+
+    it("claims to the default payout address and can clear it", async () => {
+      const before = Number((await getAccount(connection, user2Ata)).amount);
+      await claimForUser(user1, user1Ata, 5, null, user2Ata);
+      const after = Number((await getAccount(connection, user2Ata)).amount);
+      expect(after).to.be.greaterThan(before);
+
+      await setPayoutAddress(user1, PublicKey.default);
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user1.publicKey, program.programId)[0]
+      );
+      expect(profile.payoutAddress.toBase58()).to.equal(
+        PublicKey.default.toBase58()
+      );
+    });
+  });
+
+  // ============================================================================
+  // 13. COMPOUND
+  // ============================================================================
+  describe("13. Compound", () => {
+    async function compoundForUser(user: Keypair, userAta: PublicKey, landId: number) {
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      await program.methods
+        .compound(landId)
+        .accounts({
+          user: user.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(landId, program.programId)[0],
+          userProfile: userProfilePda(user.publicKey, program.programId)[0],
+          season: await openSeasonAddress(),
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          userTokenAccount: userAta,
+          teamTokenAccount: revenueAccounts(state).teamTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+    }
+
+    it("upgrades land #5 using pending earnings and the user's shortfall", async () => {
+      await sleep(2000);
+      const userBefore = Number((await getAccount(connection, user1Ata)).amount);
+      const vaultBefore = Number(
+        (await getAccount(connection, tokenVaultAddress)).amount
+      );
+      const stateBefore = await (program.account as any).gameState.fetch(gameStateAddress);
+
+      await compoundForUser(user1, user1Ata, 5);
+
+      const userSpent =
+        userBefore - Number((await getAccount(connection, user1Ata)).amount);
+      const vaultPaid =
+        vaultBefore - Number((await getAccount(connection, tokenVaultAddress)).amount);
+      expect(vaultPaid).to.be.greaterThan(0);
+      // No revenue split configured yet: the whole cost is burned
+      expect(userSpent + vaultPaid).to.equal(UPGRADE_COSTS[0]);
+      const stateAfter = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(stateAfter.totalRevenue.sub(stateBefore.totalRevenue).toNumber()).to.equal(
+        UPGRADE_COSTS[0]
+      );
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(5, program.programId)[0]
+      );
+      expect(land.level).to.equal(2);
+      expect(land.fixedEarnings.toNumber()).to.equal(0);
+    });
+
+    it("rejects compound from non-owner", async () => {
+      try {
+        await compoundForUser(user2, user2Ata, 5);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NotLandOwner");
+      }
+    });
+
+    it("rejects compound while the land is rented", async () => {
+      try {
+        await compoundForUser(user1, user1Ata, 4);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandRented");
+      }
+    });
+  });
+
+  // ============================================================================
+  // 14. STAKING
+  // ============================================================================
+  describe("14. Staking", () => {
+    const [stakeVaultAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake_vault")],
+      program.programId
+    );
+    const STAKE_AMOUNT = 100_000 * TOKEN_MULTIPLIER;
+    const ONE_YEAR = 365 * SECONDS_PER_DAY;
+
+    function stakeAccounts(user: Keypair, userAta: PublicKey) {
+      return {
+        user: user.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfilePda(user.publicKey, program.programId)[0],
+        tokenMint: mint,
+        stakeVault: stakeVaultAddress,
+        userTokenAccount: userAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+    }
+
+    it("initializes stake vault PDA", async () => {
+      await program.methods
+        .initStakeVault()
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          tokenMint: mint,
+          stakeVault: stakeVaultAddress,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const vaultAccount = await getAccount(connection, stakeVaultAddress);
+      expect(vaultAccount.mint.toBase58()).to.equal(mint.toBase58());
+    });
+
+    it("rejects stake duration below the minimum", async () => {
+      try {
+        await program.methods
+          .stake(new BN(STAKE_AMOUNT), new BN(SECONDS_PER_DAY))
+          .accounts(stakeAccounts(user2, user2Ata))
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidStakeDuration");
+      }
+    });
+
+    it("stakes tokens for a full boost", async () => {
+      await mintTo(connection, payer, mint, user2Ata, payer, STAKE_AMOUNT);
+
+      await program.methods
+        .stake(new BN(STAKE_AMOUNT), new BN(ONE_YEAR))
+        .accounts(stakeAccounts(user2, user2Ata))
+        .signers([user2])
+        .rpc();
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user2.publicKey, program.programId)[0]
+      );
+      expect(profile.stakedAmount.toNumber()).to.equal(STAKE_AMOUNT);
+      expect(profile.stakeBonusBps).to.equal(5_000);
+      expect(
+        Number((await getAccount(connection, stakeVaultAddress)).amount)
+      ).to.equal(STAKE_AMOUNT);
+    });
+
+    it("rejects a second stake while one is active", async () => {
+      try {
+        await program.methods
+          .stake(new BN(1), new BN(ONE_YEAR))
+          .accounts(stakeAccounts(user2, user2Ata))
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("StakeAlreadyActive");
+      }
+    });
+
+    it("early unstake pays the penalty into the token vault", async () => {
+      const userBefore = Number((await getAccount(connection, user2Ata)).amount);
+      const vaultBefore = Number(
+        (await getAccount(connection, tokenVaultAddress)).amount
+      );
+
+      await program.methods
+        .unstake()
+        .accounts({ ...stakeAccounts(user2, user2Ata), tokenVault: tokenVaultAddress })
+        .signers([user2])
+        .rpc();
+
+      const penalty = STAKE_AMOUNT / 5;
+      const userAfter = Number((await getAccount(connection, user2Ata)).amount);
+      const vaultAfter = Number(
+        (await getAccount(connection, tokenVaultAddress)).amount
+      );
+      expect(userAfter - userBefore).to.equal(STAKE_AMOUNT - penalty);
+      expect(vaultAfter - vaultBefore).to.equal(penalty);
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user2.publicKey, program.programId)[0]
+      );
+      expect(profile.stakedAmount.toNumber()).to.equal(0);
+    });
+
+    it("re-staking settles the previous stake's unclaimed boost into the lands", async () => {
+      await mintTo(connection, payer, mint, user2Ata, payer, STAKE_AMOUNT);
+      const lands = await ownedLandAccounts(user2.publicKey);
+      expect(lands.length).to.be.greaterThan(0);
+
+      // Without the lands the pending boost would be lost
+      try {
+        await program.methods
+          .stake(new BN(STAKE_AMOUNT), new BN(ONE_YEAR))
+          .accounts(stakeAccounts(user2, user2Ata))
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandCountMismatch");
+      }
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user2.publicKey, program.programId)[0]
+      );
+      const before = await Promise.all(
+        lands.map((l) => (program.account as any).landData.fetch(l.pubkey))
+      );
+      await program.methods
+        .stake(new BN(STAKE_AMOUNT), new BN(ONE_YEAR))
+        .accounts(stakeAccounts(user2, user2Ata))
+        .remainingAccounts(lands)
+        .signers([user2])
+        .rpc();
+
+      // Lands that earned during the old boost window keep their boost as pending earnings
+      let settled = 0;
+      for (const [i, l] of lands.entries()) {
+        const land = await (program.account as any).landData.fetch(l.pubkey);
+        const boosted =
+          before[i].rentalEnd.toNumber() === 0 &&
+          before[i].lastCheckout.lt(profile.stakeLockEnd);
+        if (boosted) {
+          settled++;
+          expect(land.lastCheckout.gt(before[i].lastCheckout)).to.equal(true);
+          expect(land.fixedEarnings.gt(before[i].fixedEarnings)).to.equal(true);
+        } else {
+          expect(land.lastCheckout.eq(before[i].lastCheckout)).to.equal(true);
+        }
+      }
+      expect(settled).to.be.greaterThan(0);
+
+      // Leave user2 unstaked for the later tests
+      await program.methods
+        .unstake()
+        .accounts({ ...stakeAccounts(user2, user2Ata), tokenVault: tokenVaultAddress })
+        .signers([user2])
+        .rpc();
+    });
+  });
+
+  // ============================================================================
+  // 15. REFERRALS
+  // ============================================================================
+  describe("15. Referrals", () => {
+    const user3 = Keypair.generate();
+    let user3Ata: PublicKey;
+
+    before(async () => {
+      const sig = await connection.requestAirdrop(
+        user3.publicKey,
+        10 * LAMPORTS_PER_SOL
+      );
+      await connection.confirmTransaction(sig);
+      user3Ata = (
+        await getOrCreateAssociatedTokenAccount(connection, payer, mint, user3.publicKey)
+      ).address;
+      await mintTo(connection, payer, mint, user3Ata, payer, 20_000 * TOKEN_MULTIPLIER);
+    });
+
+    it("admin sets the referral reward to 10%", async () => {
+      await program.methods
+        .setReferralBps(1_000)
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(state.referralBps).to.equal(1_000);
+    });
+
+    it("records the referrer on the first purchase only", async () => {
+      await buyLandForUser(user3, user3Ata, 300, user1.publicKey);
+      await buyLandForUser(user3, user3Ata, 301, user2.publicKey);
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user3.publicKey, program.programId)[0]
+      );
+      expect(profile.referrer.toBase58()).to.equal(user1.publicKey.toBase58());
+    });
+
+    it("accrues referral rewards on the referred player's claims", async () => {
+      const [referrerProfile] = userProfilePda(user1.publicKey, program.programId);
+      const before = (await (program.account as any).userProfile.fetch(referrerProfile))
+        .referralRewards.toNumber();
+
+      await sleep(2000);
+      await claimForUser(user3, user3Ata, 300);
+
+      const after = (await (program.account as any).userProfile.fetch(referrerProfile))
+        .referralRewards.toNumber();
+      expect(after).to.be.greaterThan(before);
+    });
+
+    it("referrer claims accrued rewards", async () => {
+      const [referrerProfile] = userProfilePda(user1.publicKey, program.programId);
+      const rewards = (await (program.account as any).userProfile.fetch(referrerProfile))
+        .referralRewards.toNumber();
+      const balanceBefore = Number((await getAccount(connection, user1Ata)).amount);
+
+      await program.methods
+        .claimReferralRewards()
+        .accounts({
+          user: user1.publicKey,
+          gameState: gameStateAddress,
+          userProfile: referrerProfile,
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          userTokenAccount: user1Ata,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+      const balanceAfter = Number((await getAccount(connection, user1Ata)).amount);
+      expect(balanceAfter - balanceBefore).to.equal(rewards);
+      const profile = await (program.account as any).userProfile.fetch(referrerProfile);
+      expect(profile.referralRewards.toNumber()).to.equal(0);
+    });
+  });
+
+  // ============================================================================
+  // 16. GUILDS
+  // ============================================================================
+  describe("16. Guilds", () => {
+    // user2 leads, user1 joins and leaves again
+    const [guildAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("guild"), user2.publicKey.toBuffer()],
+      program.programId
+    );
+    const [guildVaultAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("guild_vault"), guildAddress.toBuffer()],
+      program.programId
+    );
+    it("creates a guild with a 10% tax", async () => {
+      await program.methods
+        .createGuild("Olympus", 1_000)
+        .accounts({
+          user: user2.publicKey,
+          gameState: gameStateAddress,
+          userProfile: userProfilePda(user2.publicKey, program.programId)[0],
+          guild: guildAddress,
+          tokenMint: mint,
+          guildVault: guildVaultAddress,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+      const guild = await (program.account as any).guild.fetch(guildAddress);
+      expect(guild.name).to.equal("Olympus");
+      expect(guild.members.length).to.equal(1);
+    });
+
+    it("rejects a tax above the maximum", async () => {
+      try {
+        await program.methods
+          .setGuildTax(5_000)
+          .accounts({ leader: user2.publicKey, guild: guildAddress })
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidGuildTax");
+      }
+    });
+
+    const [user1InviteAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("guild_invite"), guildAddress.toBuffer(), user1.publicKey.toBuffer()],
+      program.programId
+    );
+    const joinAccounts = {
+      user: user1.publicKey,
+      userProfile: userProfilePda(user1.publicKey, program.programId)[0],
+      guild: guildAddress,
+      invite: user1InviteAddress,
+      leader: user2.publicKey,
+    };
+
+    async function inviteUser1() {
+      await program.methods
+        .inviteToGuild(user1.publicKey)
+        .accounts({
+          leader: user2.publicKey,
+          guild: guildAddress,
+          invite: user1InviteAddress,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+    }
+
+    it("cannot join without an invite", async () => {
+      try {
+        await program.methods.joinGuild().accounts(joinAccounts).signers([user1]).rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("AccountNotInitialized");
+      }
+    });
+
+    it("user1 joins with an invite and leaves the guild", async () => {
+      const accounts = {
+        user: user1.publicKey,
+        userProfile: userProfilePda(user1.publicKey, program.programId)[0],
+        guild: guildAddress,
+      };
+
+      await inviteUser1();
+      await program.methods.joinGuild().accounts(joinAccounts).signers([user1]).rpc();
+      let guild = await (program.account as any).guild.fetch(guildAddress);
+      expect(guild.members.length).to.equal(2);
+      expect(await connection.getAccountInfo(user1InviteAddress)).to.be.null;
+
+      await program.methods.leaveGuild().accounts(accounts).signers([user1]).rpc();
+      guild = await (program.account as any).guild.fetch(guildAddress);
+      expect(guild.members.length).to.equal(1);
+      const profile = await (program.account as any).userProfile.fetch(
+        accounts.userProfile
+      );
+      expect(profile.guild.toBase58()).to.equal(PublicKey.default.toBase58());
+    });
+
+    it("leader cannot leave the guild", async () => {
+      try {
+        await program.methods
+          .leaveGuild()
+          .accounts({
+            user: user2.publicKey,
+            userProfile: userProfilePda(user2.publicKey, program.programId)[0],
+            guild: guildAddress,
+          })
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("GuildLeaderCannotLeave");
+      }
+    });
+
+    it("leader kicks a member", async () => {
+      await inviteUser1();
+      await program.methods.joinGuild().accounts(joinAccounts).signers([user1]).rpc();
+
+      await program.methods
+        .kickGuildMember()
+        .accounts({
+          leader: user2.publicKey,
+          guild: guildAddress,
+          memberProfile: userProfilePda(user1.publicKey, program.programId)[0],
+        })
+        .signers([user2])
+        .rpc();
+
+      const guild = await (program.account as any).guild.fetch(guildAddress);
+      expect(guild.members.length).to.equal(1);
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user1.publicKey, program.programId)[0]
+      );
+      expect(profile.guild.toBase58()).to.equal(PublicKey.default.toBase58());
+    });
+
+    it("refreshes the guild bonus from the leader's lands", async () => {
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user2.publicKey, program.programId)[0]
+      );
+      const lands = await program.account.landData.all([
+        { memcmp: { offset: 10, bytes: user2.publicKey.toBase58() } },
+      ]);
+      const sorted = lands
+        .map((l: any) => l.account)
+        .sort((a: any, b: any) => a.landId - b.landId);
+      expect(sorted.length).to.equal(profile.landsOwned);
+
+      await program.methods
+        .refreshGuildBonus()
+        .accounts({
+          guild: guildAddress,
+          memberProfile: userProfilePda(user2.publicKey, program.programId)[0],
+        })
+        .remainingAccounts(
+          sorted.map((land: any) => ({
+            pubkey: landDataPda(land.landId, program.programId)[0],
+            isWritable: false,
+            isSigner: false,
+          }))
+        )
+        .rpc();
+
+      const guild = await (program.account as any).guild.fetch(guildAddress);
+      const levels = sorted.reduce((sum: number, land: any) => sum + land.level, 0);
+      expect(guild.totalLandLevels).to.equal(levels);
+      expect(guild.bonusBps).to.equal(Math.min(levels * 5, 1_000));
+    });
+
+    it("rejects a refresh that omits some of the member's lands", async () => {
+      try {
+        await program.methods
+          .refreshGuildBonus()
+          .accounts({
+            guild: guildAddress,
+            memberProfile: userProfilePda(user2.publicKey, program.programId)[0],
+          })
+          .remainingAccounts([])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandCountMismatch");
+      }
+    });
+
+    it("member claims pay the guild tax into the guild treasury", async () => {
+      await sleep(2000);
+      const before = Number((await getAccount(connection, guildVaultAddress)).amount);
+      await claimForUser(user2, user2Ata, 100);
+      const after = Number((await getAccount(connection, guildVaultAddress)).amount);
+      expect(after).to.be.greaterThan(before);
+    });
+
+    it("leader withdraws from the guild treasury", async () => {
+      const treasury = Number((await getAccount(connection, guildVaultAddress)).amount);
+      const balanceBefore = Number((await getAccount(connection, user2Ata)).amount);
+
+      await program.methods
+        .withdrawGuildTreasury(new BN(treasury))
+        .accounts({
+          leader: user2.publicKey,
+          gameState: gameStateAddress,
+          guild: guildAddress,
+          tokenMint: mint,
+          guildVault: guildVaultAddress,
+          destination: user2Ata,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc();
+
+      const balanceAfter = Number((await getAccount(connection, user2Ata)).amount);
+      expect(balanceAfter - balanceBefore).to.equal(treasury);
+    });
+
+    it("non-leader cannot withdraw from the guild treasury", async () => {
+      try {
+        await program.methods
+          .withdrawGuildTreasury(new BN(1))
+          .accounts({
+            leader: user1.publicKey,
+            gameState: gameStateAddress,
+            guild: guildAddress,
+            tokenMint: mint,
+            guildVault: guildVaultAddress,
+            destination: user1Ata,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+
+    it("leader dissolves the guild and closes its treasury", async () => {
+      await program.methods
+        .dissolveGuild()
+        .accounts({
+          leader: user2.publicKey,
+          gameState: gameStateAddress,
+          leaderProfile: userProfilePda(user2.publicKey, program.programId)[0],
+          guild: guildAddress,
+          tokenMint: mint,
+          guildVault: guildVaultAddress,
+          destination: user2Ata,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc();
+
+      expect(await connection.getAccountInfo(guildAddress)).to.be.null;
+      expect(await connection.getAccountInfo(guildVaultAddress)).to.be.null;
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user2.publicKey, program.programId)[0]
+      );
+      expect(profile.guild.toBase58()).to.equal(PublicKey.default.toBase58());
+    });
+  });
+
+  // ============================================================================
+  // 17. SEASONS
+  // ============================================================================
+  describe("17. Seasons", () => {
+    const PRIZE_POOL = 1_000 * TOKEN_MULTIPLIER;
+
+    function seasonPda(seasonId: number): PublicKey {
+      const id = Buffer.alloc(4);
+      id.writeUInt32LE(seasonId);
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("season"), id],
+        program.programId
+      )[0];
+    }
+
+    it("non-authority cannot start a season", async () => {
+      try {
+        await program.methods
+          .startSeason(new BN(5), new BN(PRIZE_POOL))
+          .accounts({
+            authority: user1.publicKey,
+            gameState: gameStateAddress,
+            season: seasonPda(1),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+
+    it("authority starts season 1", async () => {
+      await program.methods
+        .startSeason(new BN(5), new BN(PRIZE_POOL))
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          season: seasonPda(1),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(state.currentSeason).to.equal(1);
+      expect(state.seasonOpen).to.equal(true);
+    });
+
+    it("claims and upgrades update the leaderboard", async () => {
+      await sleep(1000);
+      await claimForUser(user1, user1Ata, 2);
+      await upgradeLandForUser(user1, user1Ata, 2);
+      await claimForUser(user2, user2Ata, 100);
+
+      const season = await (program.account as any).season.fetch(seasonPda(1));
+      const players = season.leaderboard.map((e: any) => e.player.toBase58());
+      expect(players).to.include(user1.publicKey.toBase58());
+      expect(players).to.include(user2.publicKey.toBase58());
+      // user1 spent 1,000 tokens on the upgrade, so outranks user2
+      expect(season.leaderboard[0].player.toBase58()).to.equal(
+        user1.publicKey.toBase58()
+      );
+      for (let i = 1; i < season.leaderboard.length; i++) {
+        expect(season.leaderboard[i - 1].score.gte(season.leaderboard[i].score)).to.equal(true);
+      }
+    });
+
+    it("rejects closing before the season ends", async () => {
+      try {
+        await closeSeason();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("SeasonNotEnded");
+      }
+    });
+
+    async function closeSeason() {
+      await program.methods
+        .closeSeason()
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          season: seasonPda(1),
+        })
+        .rpc();
+    }
+
+    function claimPrize(user: Keypair, userAta: PublicKey) {
+      return program.methods
+        .claimSeasonPrize(1)
+        .accounts({
+          user: user.publicKey,
+          gameState: gameStateAddress,
+          season: seasonPda(1),
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          userTokenAccount: userAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+    }
+
+    it("rejects claiming a prize while the season is open", async () => {
+      try {
+        await claimPrize(user1, user1Ata);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("SeasonOpen");
+      }
+    });
+
+    // user2's tokens, parked with user1 while user2's token account is closed
+    let user2Balance: bigint;
+
+    it("closes the season even if a winner closed their token account", async () => {
+      await sleep(5000);
+      // user2 is ranked but has no token account for the game mint anymore
+      const balance = (await getAccount(connection, user2Ata)).amount;
+      user2Balance = balance;
+      await transferChecked(
+        connection, payer, user2Ata, mint, user1Ata, user2, balance, TOKEN_DECIMALS
+      );
+      await closeAccount(connection, payer, user2Ata, user2.publicKey, user2);
+
+      await closeSeason();
+
+      const season = await (program.account as any).season.fetch(seasonPda(1));
+      expect(season.closed).to.equal(true);
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(state.seasonOpen).to.equal(false);
+    });
+
+    it("winners claim their prizes by rank, once", async () => {
+      const before = Number((await getAccount(connection, user1Ata)).amount);
+      await claimPrize(user1, user1Ata);
+      const after = Number((await getAccount(connection, user1Ata)).amount);
+      expect(after - before).to.equal((PRIZE_POOL * 3_000) / 10_000);
+
+      try {
+        await claimPrize(user1, user1Ata);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("SeasonPrizeClaimed");
+      }
+
+      // The claim re-creates the closed token account
+      await claimPrize(user2, user2Ata);
+      expect(Number((await getAccount(connection, user2Ata)).amount)).to.equal(
+        (PRIZE_POOL * 2_000) / 10_000
+      );
+      const season = await (program.account as any).season.fetch(seasonPda(1));
+      expect(season.prizesClaimed).to.equal(0b11);
+
+      await transferChecked(
+        connection, payer, user1Ata, mint, user2Ata, user1, user2Balance, TOKEN_DECIMALS
+      );
+    });
+
+    it("rejects a claim from a player off the leaderboard", async () => {
+      const payerAta = await getOrCreateAssociatedTokenAccount(connection, payer, mint, payer.publicKey);
+      try {
+        await claimPrize(payer, payerAta.address);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NoSeasonPrize");
+      }
+    });
+  });
+
+  // ============================================================================
+  // 18. ACHIEVEMENTS
+  // ============================================================================
+  describe("18. Achievements", () => {
+    const REWARD = 50 * TOKEN_MULTIPLIER;
+    const FIRST_LAND = 1 << 0;
+    const LEVEL_5 = 1 << 1;
+    const TEN_LANDS = 1 << 2;
+
+    function claimAccounts(user: Keypair, userAta: PublicKey) {
+      return {
+        user: user.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfilePda(user.publicKey, program.programId)[0],
+        tokenMint: mint,
+        tokenVault: tokenVaultAddress,
+        userTokenAccount: userAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      };
+    }
+
+    it("admin sets achievement rewards", async () => {
+      await program.methods
+        .setAchievementRewards([new BN(REWARD), new BN(REWARD), new BN(REWARD), new BN(REWARD)])
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+    });
+
+    it("unlocks milestones reached by user1", async () => {
+      // user1 bought 10 lands and upgraded land #1 to level 10, unlocking the land milestones
+      await sleep(1000);
+      await claimForUser(user1, user1Ata, 1);
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user1.publicKey, program.programId)[0]
+      );
+      expect(profile.achievements & FIRST_LAND).to.not.equal(0);
+      expect(profile.achievements & LEVEL_5).to.not.equal(0);
+      expect(profile.achievements & TEN_LANDS).to.not.equal(0);
+      expect(profile.claimStreak).to.be.greaterThan(0);
+    });
+
+    it("pays an achievement reward once", async () => {
+      const before = Number((await getAccount(connection, user1Ata)).amount);
+      await program.methods
+        .claimAchievementReward({ firstLand: {} })
+        .accounts(claimAccounts(user1, user1Ata))
+        .signers([user1])
+        .rpc();
+      const after = Number((await getAccount(connection, user1Ata)).amount);
+      expect(after - before).to.equal(REWARD);
+
+      try {
+        await program.methods
+          .claimAchievementReward({ firstLand: {} })
+          .accounts(claimAccounts(user1, user1Ata))
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("AchievementAlreadyClaimed");
+      }
+    });
+
+    it("rejects rewards for locked achievements", async () => {
+      try {
+        await program.methods
+          .claimAchievementReward({ claimStreak30: {} })
+          .accounts(claimAccounts(user1, user1Ata))
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("AchievementLocked");
+      }
+    });
+  });
+
+  // ============================================================================
+  // 19. CLAIM STREAK
+  // ============================================================================
+  describe("19. Claim Streak", () => {
+    it("view returns the current claim streak", async () => {
+      const streak = await program.methods
+        .getClaimStreak()
+        .accounts({
+          userProfile: userProfilePda(user1.publicKey, program.programId)[0],
+        })
+        .view();
+      expect(streak).to.equal(1);
+    });
+
+    it("repeat claims on the same day keep the streak", async () => {
+      await sleep(1000);
+      await claimForUser(user1, user1Ata, 1);
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user1.publicKey, program.programId)[0]
+      );
+      expect(profile.claimStreak).to.equal(1);
+    });
+  });
+
+  // ============================================================================
+  // 20. RANDOM LAND EVENTS
+  // ============================================================================
+  // The deterministic source needs a build with `anchor build -- --features test-randomness`
+  describe("20. Random Land Events", () => {
+    const LAND_ID = 6; // owned by user1
+    const LAND_EVENT = 0; // RandomnessPurpose::LandEvent
+
+    async function requestLandEvent(requester: Keypair) {
+      await program.methods
+        .requestLandEvent(LAND_ID)
+        .accounts({
+          requester: requester.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          randomnessRequest: randomnessRequestPda(LAND_EVENT, LAND_ID, program.programId)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requester])
+        .rpc();
+    }
+
+    it("rejects requests while randomness is disabled", async () => {
+      try {
+        await requestLandEvent(user2);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("RandomnessDisabled");
+      }
+    });
+
+    it("admin selects the deterministic source", async () => {
+      await program.methods
+        .setRandomnessSource({ deterministic: {} }, PublicKey.default)
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(state.randomnessSource).to.have.property("deterministic");
+    });
+
+    it("requests and fulfils a land event", async () => {
+      await requestLandEvent(user2);
+      const requestAddress = randomnessRequestPda(LAND_EVENT, LAND_ID, program.programId)[0];
+      const request = await (program.account as any).randomnessRequest.fetch(requestAddress);
+      expect(request.requester.toBase58()).to.equal(user2.publicKey.toBase58());
+
+      await sleep(1000); // fulfilment must land in a later slot
+      await program.methods
+        .fulfilLandEvent(LAND_ID, Array(32).fill(0))
+        .accounts({
+          fulfiller: payer.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          ownerProfile: userProfilePda(user1.publicKey, program.programId)[0],
+          randomnessRequest: requestAddress,
+          requester: user2.publicKey,
+        })
+        .rpc();
+
+      expect(await connection.getAccountInfo(requestAddress)).to.equal(null);
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(LAND_ID, program.programId)[0]
+      );
+      expect([0, -5_000, 5_000]).to.include(land.eventModifierBps);
+      if (land.eventModifierBps !== 0) {
+        expect(land.eventEnd.toNumber()).to.be.greaterThan(land.lastCheckout.toNumber());
+      }
+    });
+
+    it("enforces the per-land request cooldown", async () => {
+      try {
+        await requestLandEvent(user2);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandEventCooldown");
+      }
+    });
+
+    it("rejects cancelling a request before it expires", async () => {
+      const CANCEL_LAND_ID = 7; // owned by user1
+      const requestAddress = randomnessRequestPda(LAND_EVENT, CANCEL_LAND_ID, program.programId)[0];
+      await program.methods
+        .requestLandEvent(CANCEL_LAND_ID)
+        .accounts({
+          requester: user2.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(CANCEL_LAND_ID, program.programId)[0],
+          randomnessRequest: requestAddress,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+      try {
+        await program.methods
+          .cancelRandomnessRequest({ landEvent: {} }, CANCEL_LAND_ID)
+          .accounts({
+            caller: payer.publicKey,
+            randomnessRequest: requestAddress,
+            requester: user2.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("RandomnessRequestNotExpired");
+      }
+    });
+  });
+
+  describe("21. Raids", () => {
+    const TARGET_LAND_ID = 6; // owned by user1, never claimed
+    const ATTACKER_LAND_ID = 100; // owned by user2
+    const RAID = 1; // RandomnessPurpose::Raid
+
+    async function requestRaid(attackerLandId: number, targetLandId: number) {
+      await program.methods
+        .requestRaid(attackerLandId, targetLandId)
+        .accounts({
+          attacker: user2.publicKey,
+          gameState: gameStateAddress,
+          attackerLand: landDataPda(attackerLandId, program.programId)[0],
+          targetLand: landDataPda(targetLandId, program.programId)[0],
+          randomnessRequest: randomnessRequestPda(RAID, targetLandId, program.programId)[0],
+          tokenMint: mint,
+          userTokenAccount: user2Ata,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+    }
+
+    it("builds a defense level by burning tokens", async () => {
+      const before = Number((await getAccount(connection, user1Ata)).amount);
+
+      await program.methods
+        .buildDefense(TARGET_LAND_ID)
+        .accounts({
+          user: user1.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(TARGET_LAND_ID, program.programId)[0],
+          tokenMint: mint,
+          userTokenAccount: user1Ata,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+      const after = Number((await getAccount(connection, user1Ata)).amount);
+      expect(before - after).to.equal(DEFENSE_COSTS[0]);
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(TARGET_LAND_ID, program.programId)[0]
+      );
+      expect(land.defenseLevel).to.equal(1);
+    });
+
+    it("rejects raiding your own land", async () => {
+      try {
+        await requestRaid(ATTACKER_LAND_ID, 101);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("CannotRaidOwnLand");
+      }
+    });
+
+    it("rejects raiding a land inside its post-claim protection window", async () => {
+      // Land 5 was claimed by user1 in the payout address tests
+      try {
+        await requestRaid(ATTACKER_LAND_ID, 5);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandProtected");
+      }
+    });
+
+    it("raids a land and applies the outcome on fulfilment", async () => {
+      const targetAddress = landDataPda(TARGET_LAND_ID, program.programId)[0];
+      const attackerAddress = landDataPda(ATTACKER_LAND_ID, program.programId)[0];
+      const requestAddress = randomnessRequestPda(RAID, TARGET_LAND_ID, program.programId)[0];
+      const balanceBefore = Number((await getAccount(connection, user2Ata)).amount);
+      const attackerBefore = await (program.account as any).landData.fetch(attackerAddress);
+
+      await requestRaid(ATTACKER_LAND_ID, TARGET_LAND_ID);
+
+      const balanceAfter = Number((await getAccount(connection, user2Ata)).amount);
+      expect(balanceBefore - balanceAfter).to.equal(RAID_COST);
+      const request = await (program.account as any).randomnessRequest.fetch(requestAddress);
+      expect(request.attackerLandId).to.equal(ATTACKER_LAND_ID);
+
+      await sleep(1000); // fulfilment must land in a later slot
+      await program.methods
+        .fulfilRaid(TARGET_LAND_ID, Array(32).fill(0))
+        .accounts({
+          fulfiller: payer.publicKey,
+          gameState: gameStateAddress,
+          targetLand: targetAddress,
+          targetOwnerProfile: userProfilePda(user1.publicKey, program.programId)[0],
+          attackerLand: attackerAddress,
+          randomnessRequest: requestAddress,
+          requester: user2.publicKey,
+        })
+        .rpc();
+
+      expect(await connection.getAccountInfo(requestAddress)).to.equal(null);
+      const attackerAfter = await (program.account as any).landData.fetch(attackerAddress);
+      // Stolen earnings (if any) are credited to the attacking land, never removed
+      expect(attackerAfter.fixedEarnings.gte(attackerBefore.fixedEarnings)).to.equal(true);
+      const target = await (program.account as any).landData.fetch(targetAddress);
+      expect(target.lastRaidedAt.toNumber()).to.be.greaterThan(0);
+    });
+
+    it("enforces the per-land raid cooldown", async () => {
+      try {
+        await requestRaid(ATTACKER_LAND_ID, TARGET_LAND_ID);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("RaidCooldown");
+      }
+    });
+  });
+
+  describe("22. Mega-plots", () => {
+    async function mergeLands(user: Keypair, landIds: number[]) {
+      await program.methods
+        .mergeLands(landIds[0])
+        .accounts({
+          owner: user.publicKey,
+          ownerProfile: userProfilePda(user.publicKey, program.programId)[0],
+          megaPlot: megaPlotPda(landIds[0], program.programId)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          landIds.map((landId) => ({
+            pubkey: landDataPda(landId, program.programId)[0],
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([user])
+        .rpc();
+    }
+
+    async function splitMegaPlot(user: Keypair, landIds: number[]) {
+      await program.methods
+        .splitMegaPlot(landIds[0])
+        .accounts({
+          owner: user.publicKey,
+          ownerProfile: userProfilePda(user.publicKey, program.programId)[0],
+          megaPlot: megaPlotPda(landIds[0], program.programId)[0],
+        })
+        .remainingAccounts(
+          landIds.map((landId) => ({
+            pubkey: landDataPda(landId, program.programId)[0],
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([user])
+        .rpc();
+    }
+
+    async function fetchLand(landId: number) {
+      return (program.account as any).landData.fetch(landDataPda(landId, program.programId)[0]);
+    }
+
+    it("rejects merging lands that are not adjacent", async () => {
+      try {
+        await mergeLands(user1, [7, 9]);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandsNotAdjacent");
+      }
+    });
+
+    it("rejects merging another player's land", async () => {
+      try {
+        await mergeLands(user1, [100, 101]);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NotLandOwner");
+      }
+    });
+
+    it("merges adjacent lands into a mega-plot with a merge bonus", async () => {
+      const before = [await fetchLand(100), await fetchLand(101)];
+      const combined = before.reduce((sum, land) => sum + EARNING_SPEEDS[land.level - 1], 0);
+
+      await mergeLands(user2, [100, 101]);
+
+      const megaPlot = await (program.account as any).megaPlot.fetch(
+        megaPlotPda(100, program.programId)[0]
+      );
+      const expectedSpeed = combined + (combined * MEGA_PLOT_BONUS_BPS_PER_LAND) / 10_000;
+      expect(megaPlot.owner.toBase58()).to.equal(user2.publicKey.toBase58());
+      expect(megaPlot.landIds).to.deep.equal([100, 101]);
+      expect(megaPlot.earningSpeed.toNumber()).to.equal(expectedSpeed);
+
+      const anchor = await fetchLand(100);
+      const member = await fetchLand(101);
+      expect(anchor.megaPlot).to.equal(100);
+      expect(anchor.megaSpeed.toNumber()).to.equal(expectedSpeed);
+      expect(member.megaPlot).to.equal(100);
+      // Ownership is unchanged
+      expect(member.owner.toBase58()).to.equal(user2.publicKey.toBase58());
+    });
+
+    it("blocks upgrades of merged lands", async () => {
+      try {
+        await upgradeLandForUser(user2, user2Ata, 101);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandMerged");
+      }
+    });
+
+    it("rejects raids on merged lands", async () => {
+      try {
+        await program.methods
+          .requestRaid(6, 101)
+          .accounts({
+            attacker: user1.publicKey,
+            gameState: gameStateAddress,
+            attackerLand: landDataPda(6, program.programId)[0],
+            targetLand: landDataPda(101, program.programId)[0],
+            randomnessRequest: randomnessRequestPda(1, 101, program.programId)[0],
+            tokenMint: mint,
+            userTokenAccount: user1Ata,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandMerged");
+      }
+    });
+
+    it("splits the mega-plot back into its lands", async () => {
+      const megaPlotAddress = megaPlotPda(100, program.programId)[0];
+
+      await splitMegaPlot(user2, [100, 101]);
+
+      expect(await connection.getAccountInfo(megaPlotAddress)).to.equal(null);
+      for (const landId of [100, 101]) {
+        const land = await fetchLand(landId);
+        expect(land.megaPlot).to.equal(0);
+        expect(land.megaSpeed.toNumber()).to.equal(0);
+      }
+    });
+
+    // A row of user1's lands, the largest mega-plot allowed
+    const MAX_PLOT = [7, 8, 9, 10];
+
+    it("rejects merging more than the maximum number of lands", async () => {
+      try {
+        await mergeLands(user1, [6, ...MAX_PLOT]);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidMegaPlot");
+      }
+    });
+
+    it("merges the maximum number of lands, settling each of them", async () => {
+      expect(MAX_PLOT.length).to.equal(MAX_MEGA_PLOT_LANDS);
+      const before = await Promise.all(MAX_PLOT.map(fetchLand));
+      const combined = before.reduce((sum, land) => sum + EARNING_SPEEDS[land.level - 1], 0);
+
+      await mergeLands(user1, MAX_PLOT);
+
+      const megaPlot = await (program.account as any).megaPlot.fetch(
+        megaPlotPda(MAX_PLOT[0], program.programId)[0]
+      );
+      const bonusBps = MEGA_PLOT_BONUS_BPS_PER_LAND * (MAX_PLOT.length - 1);
+      const expectedSpeed = combined + (combined * bonusBps) / 10_000;
+      expect(megaPlot.landIds).to.deep.equal(MAX_PLOT);
+      expect(megaPlot.earningSpeed.toNumber()).to.equal(expectedSpeed);
+
+      for (const [i, landId] of MAX_PLOT.entries()) {
+        const land = await fetchLand(landId);
+        expect(land.megaPlot).to.equal(MAX_PLOT[0]);
+        // Only the anchor carries the combined earning stream
+        expect(land.megaSpeed.toNumber()).to.equal(i === 0 ? expectedSpeed : 0);
+        expect(land.owner.toBase58()).to.equal(user1.publicKey.toBase58());
+        expect(land.level).to.equal(before[i].level);
+        // Each land's pending earnings were settled into fixed earnings at the merge
+        expect(land.lastCheckout.toNumber()).to.be.greaterThan(before[i].lastCheckout.toNumber());
+        expect(land.fixedEarnings.toNumber()).to.be.greaterThan(0);
+      }
+    });
+
+    it("member lands pay out their settled earnings but produce no resources", async () => {
+      const memberId = MAX_PLOT[MAX_PLOT.length - 1];
+      const [profileAddress] = userProfilePda(user1.publicKey, program.programId);
+      await sleep(2000);
+      const before = await (program.account as any).userProfile.fetch(profileAddress);
+
+      await claimForUser(user1, user1Ata, memberId);
+
+      const after = await (program.account as any).userProfile.fetch(profileAddress);
+      after.resources.forEach((amount: BN, i: number) =>
+        expect(amount.eq(before.resources[i])).to.equal(true)
+      );
+      const member = await fetchLand(memberId);
+      expect(member.fixedEarnings.toNumber()).to.equal(0);
+      expect(member.megaPlot).to.equal(MAX_PLOT[0]);
+    });
+
+    it("splits the maximum mega-plot back into all of its lands", async () => {
+      await splitMegaPlot(user1, MAX_PLOT);
+
+      expect(
+        await connection.getAccountInfo(megaPlotPda(MAX_PLOT[0], program.programId)[0])
+      ).to.equal(null);
+      for (const landId of MAX_PLOT) {
+        const land = await fetchLand(landId);
+        expect(land.megaPlot).to.equal(0);
+        expect(land.megaSpeed.toNumber()).to.equal(0);
+      }
+    });
+  });
+
+  describe("23. Whitelist Sale", () => {
+    const sha256 = (data: Buffer) => createHash("sha256").update(data).digest();
+    // Two-leaf tree: parent hashes the sorted pair of children
+    const user1Leaf = sha256(user1.publicKey.toBuffer());
+    const user2Leaf = sha256(user2.publicKey.toBuffer());
+    const root = sha256(
+      Buffer.concat(
+        Buffer.compare(user1Leaf, user2Leaf) <= 0
+          ? [user1Leaf, user2Leaf]
+          : [user2Leaf, user1Leaf]
+      )
+    );
+    const user2Proof = [Array.from(user1Leaf)];
+
+    async function setSalePhase(phase: object, whitelistEnd: number, cap: number) {
+      await program.methods
+        .setSalePhase(phase, Array.from(root), new BN(whitelistEnd), cap)
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+    }
+
+    it("admin opens a whitelist window", async () => {
+      const whitelistEnd = Math.floor(Date.now() / 1000) + 3600;
+      await setSalePhase({ whitelist: {} }, whitelistEnd, 1);
+
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(state.salePhase).to.have.property("whitelist");
+      expect(Buffer.from(state.whitelistRoot).equals(root)).to.equal(true);
+      expect(state.whitelistPurchaseCap).to.equal(1);
+    });
+
+    it("rejects purchases without a valid proof", async () => {
+      try {
+        await buyLandForUser(user2, user2Ata, 102);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NotWhitelisted");
+      }
+    });
+
+    it("lets whitelisted wallets buy with a Merkle proof", async () => {
+      await buyLandForUser(user2, user2Ata, 102, null, user2Proof);
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user2.publicKey, program.programId)[0]
+      );
+      expect(profile.whitelistPurchases).to.equal(1);
+    });
+
+    it("enforces the per-wallet whitelist cap", async () => {
+      try {
+        await buyLandForUser(user2, user2Ata, 103, null, user2Proof);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("WhitelistCapReached");
+      }
+    });
+
+    it("starts a fresh per-wallet count in the next whitelist window", async () => {
+      const whitelistEnd = Math.floor(Date.now() / 1000) + 3600;
+      await setSalePhase({ whitelist: {} }, whitelistEnd, 1);
+      await buyLandForUser(user2, user2Ata, 103, null, user2Proof);
+
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user2.publicKey, program.programId)[0]
+      );
+      expect(profile.whitelistWindow).to.equal(state.whitelistWindow);
+      expect(profile.whitelistPurchases).to.equal(1);
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(103, program.programId)[0]
+      );
+      expect(land.owner.toBase58()).to.equal(user2.publicKey.toBase58());
+    });
+
+    it("reopens public sales", async () => {
+      await setSalePhase({ public: {} }, 0, 0);
+
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(state.salePhase).to.have.property("public");
+    });
+  });
+
+  describe("24. Purchase Rate Limits", () => {
+    async function setPurchaseLimits(cooldown: number, slotCap: number) {
+      await program.methods
+        .setPurchaseLimits(new BN(cooldown), slotCap)
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+    }
+
+    it("admin sets purchase limits", async () => {
+      await setPurchaseLimits(3600, 5);
+
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(state.purchaseCooldown.toNumber()).to.equal(3600);
+      expect(state.slotPurchaseCap).to.equal(5);
+    });
+
+    it("rejects a wallet buying again within the cooldown", async () => {
+      // user2 bought land 103 in the whitelist tests
+      try {
+        await buyLandForUser(user2, user2Ata, 104);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("PurchaseCooldown");
+      }
+    });
+
+    it("allows purchases again once limits are lifted", async () => {
+      await setPurchaseLimits(0, 0);
+      await buyLandForUser(user2, user2Ata, 104);
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user2.publicKey, program.programId)[0]
+      );
+      expect(profile.lastPurchaseTime.toNumber()).to.be.greaterThan(0);
+    });
+  });
+
+  describe("25. Reserved Lands", () => {
+    const RESERVED_START = 500;
+    const RESERVED_END = 509;
+
+    it("admin reserves a land range", async () => {
+      await program.methods
+        .initReservedLands()
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          reservedLands: reservedLandsPda(program.programId)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .setReservedRange(RESERVED_START, RESERVED_END, true)
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          reservedLands: reservedLandsPda(program.programId)[0],
+        })
+        .rpc();
+
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(state.reservedLandsEnabled).to.equal(true);
+    });
+
+    it("rejects buying a reserved land", async () => {
+      try {
+        await buyLandForUser(user2, user2Ata, RESERVED_START);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandReserved");
+      }
+    });
+
+    it("still sells lands outside the reserved range", async () => {
+      await buyLandForUser(user2, user2Ata, RESERVED_END + 1);
+    });
+
+    it("admin grants a reserved land without payment", async () => {
+      const recipient = user2.publicKey;
+      const [landMint] = landMintPda(RESERVED_START, program.programId);
+      const [recipientProfile] = userProfilePda(recipient, program.programId);
+      const before = Number((await getAccount(connection, user2Ata)).amount);
+      const profileBefore = await (program.account as any).userProfile.fetch(recipientProfile);
+
+      await program.methods
+        .adminGrantLand(RESERVED_START)
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          recipient,
+          landData: landDataPda(RESERVED_START, program.programId)[0],
+          recipientProfile,
+          landMint,
+          recipientLandTokenAccount: landNftAta(RESERVED_START, recipient, program.programId),
+          nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(RESERVED_START, program.programId)[0]
+      );
+      expect(land.owner.toBase58()).to.equal(recipient.toBase58());
+      expect(land.level).to.equal(1);
+      expect(Number((await getAccount(connection, user2Ata)).amount)).to.equal(before);
+
+      // Grants are not purchases
+      const profileAfter = await (program.account as any).userProfile.fetch(recipientProfile);
+      expect(profileAfter.landsOwned).to.equal(profileBefore.landsOwned + 1);
+      expect(profileAfter.landsPurchased.eq(profileBefore.landsPurchased)).to.equal(true);
+    });
+
+    it("rejects grants from non-authority", async () => {
+      try {
+        await program.methods
+          .adminGrantLand(RESERVED_START + 1)
+          .accounts({
+            authority: user1.publicKey,
+            gameState: gameStateAddress,
+            recipient: user1.publicKey,
+            landData: landDataPda(RESERVED_START + 1, program.programId)[0],
+            recipientProfile: userProfilePda(user1.publicKey, program.programId)[0],
+            landMint: landMintPda(RESERVED_START + 1, program.programId)[0],
+            recipientLandTokenAccount: landNftAta(
+              RESERVED_START + 1,
+              user1.publicKey,
+              program.programId
+            ),
+            nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+
+    async function setReservedRange(start: number, end: number, reserved: boolean) {
+      await program.methods
+        .setReservedRange(start, end, reserved)
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          reservedLands: reservedLandsPda(program.programId)[0],
+        })
+        .rpc();
+    }
+
+    // Reserved land ids in `from..=to`, read from the bitmap (bit land_id - 1)
+    async function reservedIds(from: number, to: number): Promise<number[]> {
+      const { bits } = await (program.account as any).reservedLands.fetch(
+        reservedLandsPda(program.programId)[0]
+      );
+      const ids: number[] = [];
+      for (let id = from; id <= to; id++) {
+        if (bits[(id - 1) >> 3] & (1 << ((id - 1) & 7))) ids.push(id);
+      }
+      return ids;
+    }
+
+    function idRange(start: number, end: number): number[] {
+      return Array.from({ length: end - start + 1 }, (_, i) => start + i);
+    }
+
+    it("sets and clears ranges crossing byte boundaries", async () => {
+      // Lands 1005..=1030 are bits 1004..=1029: the tail of byte 125, bytes 126-127, the head of 128
+      await setReservedRange(1005, 1030, true);
+      expect(await reservedIds(995, 1040)).to.deep.equal(idRange(1005, 1030));
+
+      // Clearing 1010..=1020 also crosses a byte boundary and leaves both ends reserved
+      await setReservedRange(1010, 1020, false);
+      expect(await reservedIds(995, 1040)).to.deep.equal([
+        ...idRange(1005, 1009),
+        ...idRange(1021, 1030),
+      ]);
+
+      await setReservedRange(1005, 1030, false);
+      expect(await reservedIds(995, 1040)).to.deep.equal([]);
+    });
+
+    it("sets and clears a single land", async () => {
+      await setReservedRange(2004, 2004, true);
+      expect(await reservedIds(1995, 2015)).to.deep.equal([2004]);
+
+      await setReservedRange(2004, 2004, false);
+      expect(await reservedIds(1995, 2015)).to.deep.equal([]);
+    });
+
+    it("sets and clears the last land id", async () => {
+      await setReservedRange(21000, 21000, true);
+      expect(await reservedIds(20985, 21000)).to.deep.equal([21000]);
+
+      await setReservedRange(20990, 21000, true);
+      expect(await reservedIds(20985, 21000)).to.deep.equal(idRange(20990, 21000));
+
+      await setReservedRange(20990, 21000, false);
+      expect(await reservedIds(20985, 21000)).to.deep.equal([]);
+    });
+
+    it("rejects ranges past the last land id", async () => {
+      try {
+        await setReservedRange(20990, 21001, true);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidLandId");
+      }
+    });
+
+    it("leaves the existing reservation untouched", async () => {
+      expect(await reservedIds(RESERVED_START - 8, RESERVED_END + 8)).to.deep.equal(
+        idRange(RESERVED_START, RESERVED_END)
+      );
+    });
+  });
+
+  describe("26. Admin Levels", () => {
+    const LAND_ID = 510; // bought by user2 in the reserved lands tests
+
+    async function adminSetLevel(authority: Keypair, level: number) {
+      return program.methods
+        .adminSetLevel(LAND_ID, level)
+        .accounts({
+          authority: authority.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          ownerProfile: userProfilePda(user2.publicKey, program.programId)[0],
+        })
+        .signers(authority === payer ? [] : [authority])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    it("rejects level changes from non-authority", async () => {
+      try {
+        await adminSetLevel(user1, 3);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+
+    it("rejects invalid levels", async () => {
+      try {
+        await adminSetLevel(payer, 11);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidLevel");
+      }
+    });
+
+    it("sets a level after settling earnings and emits an audit event", async () => {
+      await sleep(2000); // accrue some earnings at level 1
+      const tx = await adminSetLevel(payer, 3);
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(LAND_ID, program.programId)[0]
+      );
+      expect(land.level).to.equal(3);
+      expect(land.fixedEarnings.toNumber()).to.be.greaterThan(0);
+
+      const txInfo = await connection.getTransaction(tx, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+      const events = [...parser.parseLogs(txInfo!.meta!.logMessages!)];
+      const event = events.find((e) => e.name === "landLevelSet");
+      expect(event).to.not.equal(undefined);
+      expect(event!.data.oldLevel).to.equal(1);
+      expect(event!.data.newLevel).to.equal(3);
+      expect((event!.data.settledEarnings as BN).eq(land.fixedEarnings)).to.equal(true);
+    });
+
+    it("does not unlock the level milestone", async () => {
+      const [profileAddr] = userProfilePda(user2.publicKey, program.programId);
+      const before = await (program.account as any).userProfile.fetch(profileAddr);
+      expect(before.achievements & (1 << 1)).to.equal(0);
+
+      await adminSetLevel(payer, 5);
+
+      const after = await (program.account as any).userProfile.fetch(profileAddr);
+      expect(after.achievements).to.equal(before.achievements);
+    });
+  });
+
+  describe("27. SOL Land Payments", () => {
+    const LAND_ID = 520;
+    const PRICE = new BN(LAMPORTS_PER_SOL / 10);
+
+    async function buyLandWithSol(user: Keypair, landId: number) {
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      await program.methods
+        .buyLandWithSol(landId, [])
+        .accounts({
+          user: user.publicKey,
+          gameState: gameStateAddress,
+          vault: vaultAddress,
+          landData: landDataPda(landId, program.programId)[0],
+          userProfile: userProfilePda(user.publicKey, program.programId)[0],
+          landMint: landMintPda(landId, program.programId)[0],
+          userLandTokenAccount: landNftAta(landId, user.publicKey, program.programId),
+          referrerProfile: null,
+          reservedLands: state.reservedLandsEnabled ? reservedLandsPda(program.programId)[0] : null,
+          nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+    }
+
+    it("rejects SOL purchases while no SOL price is set", async () => {
+      try {
+        await buyLandWithSol(user2, LAND_ID);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("SolPaymentDisabled");
+      }
+    });
+
+    it("buys a land with SOL into the vault", async () => {
+      await program.methods
+        .setLandPriceLamports(PRICE)
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+
+      const vaultBefore = await connection.getBalance(vaultAddress);
+      const stateBefore = await (program.account as any).gameState.fetch(gameStateAddress);
+      const tokensBefore = Number((await getAccount(connection, user2Ata)).amount);
+
+      await buyLandWithSol(user2, LAND_ID);
+
+      expect((await connection.getBalance(vaultAddress)) - vaultBefore).to.equal(PRICE.toNumber());
+      const stateAfter = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(stateAfter.totalSolCollected.sub(stateBefore.totalSolCollected).eq(PRICE)).to.equal(true);
+      expect(stateAfter.totalLandsSold.toNumber()).to.equal(stateBefore.totalLandsSold.toNumber() + 1);
+      // No game tokens are burned
+      expect(Number((await getAccount(connection, user2Ata)).amount)).to.equal(tokensBefore);
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(LAND_ID, program.programId)[0]
+      );
+      expect(land.owner.toBase58()).to.equal(user2.publicKey.toBase58());
+    });
+
+    it("still rejects reserved lands", async () => {
+      try {
+        await buyLandWithSol(user2, 501);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("LandReserved");
+      }
+    });
+  });
+
+  describe("28. Alternative Payment Mints", () => {
+    const LAND_ID = 530;
+    const PRICE_PER_TOKEN = new BN(10_000); // 0.01 USDC per game token
+    let usdcMint: PublicKey;
+    let treasury: PublicKey;
+    let user2Usdc: PublicKey;
+
+    function paymentAccounts(user: PublicKey) {
+      return {
+        paymentMint: paymentMintPda(usdcMint, program.programId)[0],
+        paymentTokenMint: usdcMint,
+        userPaymentAccount: user === user2.publicKey ? user2Usdc : PublicKey.default,
+        treasuryTokenAccount: treasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+    }
+
+    before(async () => {
+      usdcMint = await createMint(connection, payer, payer.publicKey, null, 6);
+      treasury = (
+        await getOrCreateAssociatedTokenAccount(connection, payer, usdcMint, payer.publicKey)
+      ).address;
+      user2Usdc = (
+        await getOrCreateAssociatedTokenAccount(connection, payer, usdcMint, user2.publicKey)
+      ).address;
+      await mintTo(connection, payer, usdcMint, user2Usdc, payer, 1_000_000_000);
+    });
+
+    it("rejects whitelisting the game token itself", async () => {
+      try {
+        await program.methods
+          .setPaymentMint(PRICE_PER_TOKEN, treasury)
+          .accounts({
+            authority: payer.publicKey,
+            gameState: gameStateAddress,
+            mint,
+            paymentMint: paymentMintPda(mint, program.programId)[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidTokenMint");
+      }
+    });
+
+    it("admin whitelists a payment mint", async () => {
+      await program.methods
+        .setPaymentMint(PRICE_PER_TOKEN, treasury)
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          mint: usdcMint,
+          paymentMint: paymentMintPda(usdcMint, program.programId)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const paymentMint = await (program.account as any).paymentMint.fetch(
+        paymentMintPda(usdcMint, program.programId)[0]
+      );
+      expect(paymentMint.pricePerToken.eq(PRICE_PER_TOKEN)).to.equal(true);
+      expect(paymentMint.treasury.toBase58()).to.equal(treasury.toBase58());
+    });
+
+    it("buys a land with the payment mint into the treasury", async () => {
+      const state = await (program.account as any).gameState.fetch(gameStateAddress);
+      const treasuryBefore = Number((await getAccount(connection, treasury)).amount);
+      const usdcBefore = Number((await getAccount(connection, user2Usdc)).amount);
+      const tokensBefore = Number((await getAccount(connection, user2Ata)).amount);
+
+      await program.methods
+        .buyLandWithToken(LAND_ID, [])
+        .accounts({
+          user: user2.publicKey,
+          gameState: gameStateAddress,
+          ...paymentAccounts(user2.publicKey),
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          userProfile: userProfilePda(user2.publicKey, program.programId)[0],
+          landMint: landMintPda(LAND_ID, program.programId)[0],
+          userLandTokenAccount: landNftAta(LAND_ID, user2.publicKey, program.programId),
+          referrerProfile: null,
+          reservedLands: state.reservedLandsEnabled ? reservedLandsPda(program.programId)[0] : null,
+          nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+      const paid = usdcBefore - Number((await getAccount(connection, user2Usdc)).amount);
+      expect(paid).to.be.greaterThan(0);
+      expect(Number((await getAccount(connection, treasury)).amount) - treasuryBefore).to.equal(paid);
+      // No game tokens are burned
+      expect(Number((await getAccount(connection, user2Ata)).amount)).to.equal(tokensBefore);
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(LAND_ID, program.programId)[0]
+      );
+      expect(land.owner.toBase58()).to.equal(user2.publicKey.toBase58());
+    });
+
+    it("upgrades a land with the payment mint", async () => {
+      const treasuryBefore = Number((await getAccount(connection, treasury)).amount);
+
+      await program.methods
+        .upgradeLandWithToken(LAND_ID)
+        .accounts({
+          user: user2.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          userProfile: userProfilePda(user2.publicKey, program.programId)[0],
+          season: await openSeasonAddress(),
+          ...paymentAccounts(user2.publicKey),
+        })
+        .signers([user2])
+        .rpc();
+
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(LAND_ID, program.programId)[0]
+      );
+      expect(land.level).to.equal(2);
+      expect(Number((await getAccount(connection, treasury)).amount)).to.be.greaterThan(treasuryBefore);
+    });
+  });
+
+  describe("29. Revenue Split", () => {
+    const LAND_ID = 531;
+    const VAULT_BPS = 3_000;
+    const TEAM_BPS = 2_000;
+    let teamAta: PublicKey;
+
+    async function setRevenueSplit(vaultBps: number, teamBps: number, teamWallet: PublicKey) {
+      return program.methods
+        .setRevenueSplit(vaultBps, teamBps, teamWallet)
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+    }
+
+    before(async () => {
+      teamAta = (
+        await getOrCreateAssociatedTokenAccount(connection, payer, mint, payer.publicKey)
+      ).address;
+    });
+
+    it("rejects a split above 100%", async () => {
+      try {
+        await setRevenueSplit(6_000, 5_000, teamAta);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidRevenueSplit");
+      }
+    });
+
+    it("rejects a team share without a team wallet", async () => {
+      try {
+        await setRevenueSplit(0, TEAM_BPS, PublicKey.default);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidRevenueSplit");
+      }
+    });
+
+    it("splits a land purchase between burn, token vault and team", async () => {
+      await setRevenueSplit(VAULT_BPS, TEAM_BPS, teamAta);
+
+      const supplyBefore = Number((await getMint(connection, mint)).supply);
+      const userBefore = Number((await getAccount(connection, user2Ata)).amount);
+      const vaultBefore = Number((await getAccount(connection, tokenVaultAddress)).amount);
+      const teamBefore = Number((await getAccount(connection, teamAta)).amount);
+      const [user2ProfileAddress] = userProfilePda(user2.publicKey, program.programId);
+      const stateBefore = await (program.account as any).gameState.fetch(gameStateAddress);
+      const profileBefore = await (program.account as any).userProfile.fetch(user2ProfileAddress);
+
+      await buyLandForUser(user2, user2Ata, LAND_ID);
+
+      const paid = userBefore - Number((await getAccount(connection, user2Ata)).amount);
+      const toVault = Number((await getAccount(connection, tokenVaultAddress)).amount) - vaultBefore;
+      const toTeam = Number((await getAccount(connection, teamAta)).amount) - teamBefore;
+      const burned = supplyBefore - Number((await getMint(connection, mint)).supply);
+
+      expect(toVault).to.equal(Math.floor((paid * VAULT_BPS) / 10_000));
+      expect(toTeam).to.equal(Math.floor((paid * TEAM_BPS) / 10_000));
+      expect(burned).to.equal(paid - toVault - toTeam);
+
+      const stateAfter = await (program.account as any).gameState.fetch(gameStateAddress);
+      const profileAfter = await (program.account as any).userProfile.fetch(user2ProfileAddress);
+      expect(stateAfter.totalRevenue.sub(stateBefore.totalRevenue).toNumber()).to.equal(paid);
+      expect(profileAfter.landsPurchased.sub(profileBefore.landsPurchased).toNumber()).to.equal(1);
+    });
+
+    it("requires the team token account while the team share is set", async () => {
+      try {
+        await program.methods
+          .upgradeLand(LAND_ID)
+          .accounts({
+            user: user2.publicKey,
+            gameState: gameStateAddress,
+            landData: landDataPda(LAND_ID, program.programId)[0],
+            userProfile: userProfilePda(user2.publicKey, program.programId)[0],
+            season: await openSeasonAddress(),
+            tokenMint: mint,
+            userTokenAccount: user2Ata,
+            tokenVault: tokenVaultAddress,
+            teamTokenAccount: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("RevenueAccountRequired");
+      }
+    });
+
+    it("reports each portion of an upgrade payment in an event", async () => {
+      const tx = await upgradeLandForUser(user2, user2Ata, LAND_ID);
+
+      const txInfo = await connection.getTransaction(tx, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+      const event = [...parser.parseLogs(txInfo!.meta!.logMessages!)].find(
+        (e) => e.name === "revenueCollected"
+      );
+      expect(event).to.not.equal(undefined);
+      expect(event!.data.landId).to.equal(LAND_ID);
+      expect(event!.data.source).to.have.property("upgrade");
+
+      const burned = event!.data.burned as BN;
+      const toVault = event!.data.toVault as BN;
+      const toTeam = event!.data.toTeam as BN;
+      const cost = burned.add(toVault).add(toTeam);
+      expect(toVault.eq(cost.muln(VAULT_BPS).divn(10_000))).to.equal(true);
+      expect(toTeam.eq(cost.muln(TEAM_BPS).divn(10_000))).to.equal(true);
+    });
+
+    after(async () => {
+      // Back to burning everything for later tests
+      await setRevenueSplit(0, 0, PublicKey.default);
+    });
+  });
+
+  describe("30. Token-2022 Extensions", () => {
+    const LAND_ID = 540;
+    const LAND_PRICE_TOKENS = 10_000 * TOKEN_MULTIPLIER; // matches lib.rs
+    const PRICE_PER_TOKEN = 10_000;
+    const TRANSFER_FEE_BPS = 100; // 1%
+    const user4 = Keypair.generate();
+    let feeMint: PublicKey;
+    let treasury: PublicKey;
+    let user4FeeAta: PublicKey;
+
+    async function setPaymentMint(paymentMint: PublicKey) {
+      return program.methods
+        .setPaymentMint(new BN(PRICE_PER_TOKEN), treasury)
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          mint: paymentMint,
+          paymentMint: paymentMintPda(paymentMint, program.programId)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    before(async () => {
+      const sig = await connection.requestAirdrop(user4.publicKey, 10 * LAMPORTS_PER_SOL);
+      await connection.confirmTransaction(sig);
+
+      feeMint = await createToken2022Mint([ExtensionType.TransferFeeConfig], (mint) => [
+        createInitializeTransferFeeConfigInstruction(
+          mint,
+          payer.publicKey,
+          payer.publicKey,
+          TRANSFER_FEE_BPS,
+          BigInt(1_000_000 * TOKEN_MULTIPLIER),
+          TOKEN_2022_PROGRAM_ID
+        ),
+      ]);
+      treasury = (
+        await getOrCreateAssociatedTokenAccount(
+          connection, payer, feeMint, payer.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID
+        )
+      ).address;
+      user4FeeAta = (
+        await getOrCreateAssociatedTokenAccount(
+          connection, payer, feeMint, user4.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID
+        )
+      ).address;
+      await mintTo(
+        connection, payer, feeMint, user4FeeAta, payer, 1_000 * TOKEN_MULTIPLIER, [], undefined, TOKEN_2022_PROGRAM_ID
+      );
+    });
+
+    it("rejects a non-transferable payment mint", async () => {
+      const nonTransferable = await createToken2022Mint([ExtensionType.NonTransferable], (mint) => [
+        createInitializeNonTransferableMintInstruction(mint, TOKEN_2022_PROGRAM_ID),
+      ]);
+      try {
+        await setPaymentMint(nonTransferable);
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("UnsupportedMintExtension");
+      }
+    });
+
+    it("charges the transfer fee on top so the treasury receives the full price", async () => {
+      await setPaymentMint(feeMint);
+
+      const expected = (LAND_PRICE_TOKENS * PRICE_PER_TOKEN) / TOKEN_MULTIPLIER;
+      const treasuryBefore = Number(
+        (await getAccount(connection, treasury, undefined, TOKEN_2022_PROGRAM_ID)).amount
+      );
+      const userBefore = Number(
+        (await getAccount(connection, user4FeeAta, undefined, TOKEN_2022_PROGRAM_ID)).amount
+      );
+
+      await program.methods
+        .buyLandWithToken(LAND_ID, [])
+        .accounts({
+          user: user4.publicKey,
+          gameState: gameStateAddress,
+          paymentMint: paymentMintPda(feeMint, program.programId)[0],
+          paymentTokenMint: feeMint,
+          userPaymentAccount: user4FeeAta,
+          treasuryTokenAccount: treasury,
+          landData: landDataPda(LAND_ID, program.programId)[0],
+          userProfile: userProfilePda(user4.publicKey, program.programId)[0],
+          landMint: landMintPda(LAND_ID, program.programId)[0],
+          userLandTokenAccount: landNftAta(LAND_ID, user4.publicKey, program.programId),
+          referrerProfile: null,
+          reservedLands: reservedLandsPda(program.programId)[0],
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user4])
+        .rpc();
+
+      const received =
+        Number((await getAccount(connection, treasury, undefined, TOKEN_2022_PROGRAM_ID)).amount) -
+        treasuryBefore;
+      const paid =
+        userBefore -
+        Number((await getAccount(connection, user4FeeAta, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+      expect(received).to.equal(expected);
+      expect(paid).to.be.greaterThan(expected);
+      expect(paid - expected).to.equal(Math.ceil((paid * TRANSFER_FEE_BPS) / 10_000));
+    });
+  });
+
+  describe("31. Token Mint Migration", () => {
+    const [stakeVaultAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake_vault")],
+      program.programId
+    );
+    // user1 leads a guild whose treasury is created in the old mint
+    const [guildAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("guild"), user1.publicKey.toBuffer()],
+      program.programId
+    );
+    const [guildVaultAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("guild_vault"), guildAddress.toBuffer()],
+      program.programId
+    );
+    let newMint: PublicKey;
+    let oldMint: PublicKey;
+
+    async function startMigration() {
+      return program.methods
+        .migrateTokenMintStart(newMint)
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+    }
+
+    async function closeVaults() {
+      return program.methods
+        .migrateTokenMintCloseVaults()
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          tokenVault: tokenVaultAddress,
+          stakeVault: stakeVaultAddress,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    async function finishMigration() {
+      return program.methods
+        .migrateTokenMintFinish()
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          newTokenMint: newMint,
+          tokenVault: tokenVaultAddress,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    before(async () => {
+      // The new mint charges a 1% transfer fee
+      newMint = await createToken2022Mint([ExtensionType.TransferFeeConfig], (mint) => [
+        createInitializeTransferFeeConfigInstruction(
+          mint,
+          payer.publicKey,
+          payer.publicKey,
+          100,
+          BigInt(1_000_000 * TOKEN_MULTIPLIER),
+          TOKEN_2022_PROGRAM_ID
+        ),
+      ]);
+    });
+
+    it("rejects migrating to the current mint while vaults exist", async () => {
+      try {
+        await program.methods
+          .migrateTokenMintStart(mint)
+          .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidTokenMint");
+      }
+    });
+
+    it("pauses the game while draining", async () => {
+      await startMigration();
+
+      const gs = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(gs.tokenMigration).to.have.property("draining");
+      expect(gs.isActive).to.be.false;
+
+      try {
+        await program.methods
+          .setGameActive(true)
+          .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("TokenMigrationInProgress");
+      }
+    });
+
+    it("rejects finishing before the vaults are closed", async () => {
+      try {
+        await finishMigration();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidTokenMigrationStep");
+      }
+    });
+
+    it("rejects closing a vault that still holds tokens", async () => {
+      expect(Number((await getAccount(connection, tokenVaultAddress)).amount)).to.be.greaterThan(0);
+      try {
+        await closeVaults();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("VaultNotEmpty");
+      }
+    });
+
+    it("cancel resumes the game on the old mint", async () => {
+      await program.methods
+        .migrateTokenMintCancel()
+        .accounts({ authority: payer.publicKey, gameState: gameStateAddress })
+        .rpc();
+
+      const gs = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(gs.tokenMigration).to.have.property("idle");
+      expect(gs.isActive).to.be.true;
+      expect(gs.tokenMint.toBase58()).to.equal(mint.toBase58());
+    });
+
+    it("creates a guild treasury in the old mint", async () => {
+      await program.methods
+        .createGuild("Atlantis", 0)
+        .accounts({
+          user: user1.publicKey,
+          gameState: gameStateAddress,
+          userProfile: userProfilePda(user1.publicKey, program.programId)[0],
+          guild: guildAddress,
+          tokenMint: mint,
+          guildVault: guildVaultAddress,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+      const vault = await getAccount(connection, guildVaultAddress);
+      expect(vault.mint.toBase58()).to.equal(mint.toBase58());
+    });
+
+    it("owner returns outstanding stakes while draining", async () => {
+      const [user2Profile] = userProfilePda(user2.publicKey, program.programId);
+      const STAKE = 10 * TOKEN_MULTIPLIER;
+      const stakeAccounts = (user: Keypair, userAta: PublicKey) => ({
+        user: user.publicKey,
+        gameState: gameStateAddress,
+        userProfile: userProfilePda(user.publicKey, program.programId)[0],
+        tokenMint: mint,
+        stakeVault: stakeVaultAddress,
+        userTokenAccount: userAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      });
+      await mintTo(connection, payer, mint, user2Ata, payer, STAKE);
+      await program.methods
+        .stake(new BN(STAKE), new BN(30 * SECONDS_PER_DAY))
+        .accounts(stakeAccounts(user2, user2Ata))
+        .remainingAccounts(await ownedLandAccounts(user2.publicKey))
+        .signers([user2])
+        .rpc();
+      const before = Number((await getAccount(connection, user2Ata)).amount);
+
+      await startMigration();
+
+      // No new stakes once the migration runs
+      try {
+        await program.methods
+          .stake(new BN(STAKE), new BN(30 * SECONDS_PER_DAY))
+          .accounts(stakeAccounts(user1, user1Ata))
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("TokenMigrationInProgress");
+      }
+
+      await program.methods
+        .migrateTokenMintReturnStake()
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          staker: user2.publicKey,
+          stakerProfile: user2Profile,
+          tokenMint: mint,
+          stakeVault: stakeVaultAddress,
+          stakerTokenAccount: user2Ata,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      expect(Number((await getAccount(connection, user2Ata)).amount) - before).to.equal(STAKE);
+      const profile = await (program.account as any).userProfile.fetch(user2Profile);
+      expect(profile.stakedAmount.toNumber()).to.equal(0);
+      const gs = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(gs.totalStaked.toNumber()).to.equal(0);
+    });
+
+    it("owner sweeps dust sent to the stake vault so it can be closed", async () => {
+      await transferChecked(
+        connection, payer, user1Ata, mint, stakeVaultAddress, user1, 1, TOKEN_DECIMALS
+      );
+
+      const authorityAta = await getOrCreateAssociatedTokenAccount(connection, payer, mint, payer.publicKey);
+      await program.methods
+        .withdrawTokens()
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          authorityTokenAccount: authorityAta.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      try {
+        await closeVaults();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("VaultNotEmpty");
+      }
+
+      await program.methods
+        .migrateTokenMintSweepStakeVault()
+        .accounts({
+          authority: payer.publicKey,
+          gameState: gameStateAddress,
+          tokenMint: mint,
+          stakeVault: stakeVaultAddress,
+          authorityTokenAccount: authorityAta.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      expect(Number((await getAccount(connection, stakeVaultAddress)).amount)).to.equal(0);
+    });
+
+    it("migrates once the vaults are drained", async () => {
+      await closeVaults();
+      let gs = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(gs.tokenMigration).to.have.property("vaultsClosed");
+      expect(await connection.getAccountInfo(tokenVaultAddress)).to.equal(null);
+      expect(await connection.getAccountInfo(stakeVaultAddress)).to.equal(null);
+
+      await finishMigration();
+      gs = await (program.account as any).gameState.fetch(gameStateAddress);
+      expect(gs.tokenMint.toBase58()).to.equal(newMint.toBase58());
+      expect(gs.tokenMigration).to.have.property("idle");
+      expect(gs.isActive).to.be.true;
+      expect(gs.stakeVaultBump).to.equal(0);
+      // The team wallet held the old mint: cleared, its share is burned until reconfigured
+      expect(gs.teamWallet.toBase58()).to.equal(PublicKey.default.toBase58());
+      expect(gs.revenueTeamBps).to.equal(0);
+
+      const vaultAccount = await getAccount(connection, tokenVaultAddress, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(vaultAccount.mint.toBase58()).to.equal(newMint.toBase58());
+      expect(Number(vaultAccount.amount)).to.equal(0);
+
+      // Later sections use the migrated mint
+      oldMint = mint;
+      mint = newMint;
+    });
+
+    it("leader moves the guild treasury to the new mint", async () => {
+      const closeIx = await program.methods
+        .migrateGuildVaultClose()
+        .accounts({
+          leader: user1.publicKey,
+          gameState: gameStateAddress,
+          guild: guildAddress,
+          oldTokenMint: oldMint,
+          guildVault: guildVaultAddress,
+          destination: user1Ata,
+          oldTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction();
+
+      await program.methods
+        .migrateGuildVaultInit()
+        .accounts({
+          leader: user1.publicKey,
+          gameState: gameStateAddress,
+          guild: guildAddress,
+          tokenMint: mint,
+          guildVault: guildVaultAddress,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([closeIx])
+        .signers([user1])
+        .rpc();
+
+      const vault = await getAccount(connection, guildVaultAddress, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(vault.mint.toBase58()).to.equal(mint.toBase58());
+      expect(Number(vault.amount)).to.equal(0);
+    });
+
+    it("compounds from covering earnings on the fee-bearing mint without touching the wallet", async () => {
+      // Loaded from tests/fixtures: a baseline profile and level 1 land #900 earning since launch
+      const owner = Keypair.fromSeed(new Uint8Array(32).fill(51));
+      const LAND_ID = 900;
+      const [landDataAddress] = landDataPda(LAND_ID, program.programId);
+      const [profileAddress] = userProfilePda(owner.publicKey, program.programId);
+      const sig = await connection.requestAirdrop(owner.publicKey, LAMPORTS_PER_SOL);
+      await connection.confirmTransaction(sig);
+      const ownerAta = (
+        await getOrCreateAssociatedTokenAccount(
+          connection, payer, mint, owner.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID
+        )
+      ).address;
+      await mintTo(
+        connection, payer, mint, tokenVaultAddress, payer, 10_000 * TOKEN_MULTIPLIER, [], undefined, TOKEN_2022_PROGRAM_ID
+      );
+
+      const migrations = [
+        await program.methods
+          .migrateUserProfile()
+          .accounts({
+            user: owner.publicKey,
+            userProfile: profileAddress,
+            systemProgram: SystemProgram.programId,
+          })
+          .instruction(),
+        await program.methods
+          .migrateLandData(LAND_ID)
+          .accounts({
+            payer: owner.publicKey,
+            landData: landDataAddress,
+            systemProgram: SystemProgram.programId,
+          })
+          .instruction(),
+      ];
+
+      const vaultBalance = async () =>
+        Number((await getAccount(connection, tokenVaultAddress, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+      const supply = async () =>
+        Number((await getMint(connection, mint, undefined, TOKEN_2022_PROGRAM_ID)).supply);
+      const stateBefore = await (program.account as any).gameState.fetch(gameStateAddress);
+      const vaultBefore = await vaultBalance();
+      const supplyBefore = await supply();
+
+      await program.methods
+        .compound(LAND_ID)
+        .accounts({
+          user: owner.publicKey,
+          gameState: gameStateAddress,
+          landData: landDataAddress,
+          userProfile: profileAddress,
+          season: await openSeasonAddress(),
+          tokenMint: mint,
+          tokenVault: tokenVaultAddress,
+          userTokenAccount: ownerAta,
+          teamTokenAccount: revenueAccounts(stateBefore).teamTokenAccount,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .preInstructions(migrations)
+        .signers([owner])
+        .rpc();
+
+      // The earnings cover the whole cost: the vault keeps its share and burns the rest
+      const stateAfter = await (program.account as any).gameState.fetch(gameStateAddress);
+      const cost = stateAfter.totalRevenue.sub(stateBefore.totalRevenue).toNumber();
+      const toVault = Math.floor((cost * stateBefore.revenueVaultBps) / 10_000);
+      expect(cost).to.be.greaterThan(0);
+      expect(vaultBefore - (await vaultBalance())).to.equal(cost - toVault);
+      expect(supplyBefore - (await supply())).to.equal(cost - toVault);
+      const ownerAccount = await getAccount(connection, ownerAta, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(Number(ownerAccount.amount)).to.equal(0);
+
+      const land = await (program.account as any).landData.fetch(landDataAddress);
+      expect(land.level).to.equal(2);
+      expect(land.fixedEarnings.toNumber()).to.be.greaterThan(0);
+    });
+  });
+
+  describe("32. Account Versions", () => {
+    it("stamps new accounts with the current layout version", async () => {
+      const gs = await (program.account as any).gameState.fetch(gameStateAddress);
+      const land = await (program.account as any).landData.fetch(
+        landDataPda(1, program.programId)[0]
+      );
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user1.publicKey, program.programId)[0]
+      );
+      expect(gs.version).to.equal(3);
+      expect(land.version).to.equal(2);
+      expect(profile.version).to.equal(2);
+    });
+
+    it("migrating an up-to-date account leaves it unchanged", async () => {
+      const [landDataAddress] = landDataPda(1, program.programId);
+      const before = await connection.getAccountInfo(landDataAddress);
+
+      await program.methods
+        .migrateLandData(1)
+        .accounts({
+          payer: payer.publicKey,
+          landData: landDataAddress,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const after = await connection.getAccountInfo(landDataAddress);
+      expect(after!.data.equals(before!.data)).to.equal(true);
+    });
+
+    it("user migrates their own profile", async () => {
+      await program.methods
+        .migrateUserProfile()
+        .accounts({
+          user: user1.publicKey,
+          userProfile: userProfilePda(user1.publicKey, program.programId)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+      const profile = await (program.account as any).userProfile.fetch(
+        userProfilePda(user1.publicKey, program.programId)[0]
+      );
+      expect(profile.version).to.equal(2);
+      expect(profile.owner.toBase58()).to.equal(user1.publicKey.toBase58());
+    });
+
+    it("migrates a baseline profile before it can be used", async () => {
+      // Loaded from tests/fixtures/legacy-user-profile.json (token_balance = 5 tokens)
+      const legacyUser = Keypair.fromSeed(new Uint8Array(32).fill(50));
+      const [profileAddress] = userProfilePda(legacyUser.publicKey, program.programId);
+      const sig = await connection.requestAirdrop(legacyUser.publicKey, LAMPORTS_PER_SOL);
+      await connection.confirmTransaction(sig);
+      expect((await connection.getAccountInfo(profileAddress))!.data.length).to.equal(50);
+
+      try {
+        await program.methods
+          .setPayoutAddress(legacyUser.publicKey)
+          .accounts({ user: legacyUser.publicKey, userProfile: profileAddress })
+          .signers([legacyUser])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("AccountDidNotDeserialize");
+      }
+
+      await program.methods
+        .migrateUserProfile()
+        .accounts({
+          user: legacyUser.publicKey,
+          userProfile: profileAddress,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([legacyUser])
+        .rpc();
+
+      const info = await connection.getAccountInfo(profileAddress);
+      expect(info!.data.length).to.equal((program.account as any).userProfile.size);
+      const profile = await (program.account as any).userProfile.fetch(profileAddress);
+      expect(profile.version).to.equal(2);
+      expect(profile.owner.toBase58()).to.equal(legacyUser.publicKey.toBase58());
+      expect(profile.landsPurchased.toNumber()).to.equal(0);
+
+      await program.methods
+        .setPayoutAddress(legacyUser.publicKey)
+        .accounts({ user: legacyUser.publicKey, userProfile: profileAddress })
+        .signers([legacyUser])
+        .rpc();
+    });
+
+    it("non-authority cannot migrate game state", async () => {
+      try {
+        await program.methods
+          .migrateGameState()
+          .accounts({
+            authority: user1.publicKey,
+            gameState: gameStateAddress,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
   });
 });